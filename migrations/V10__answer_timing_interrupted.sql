@@ -0,0 +1,4 @@
+-- Answers whose timing was interrupted by a pause, the window losing focus or inactivity.
+-- Their time only counts while the question was active, and timing statistics leave them out.
+ALTER TABLE answers ADD COLUMN timing_interrupted INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE deck_questions ADD COLUMN timing_interrupted INTEGER NOT NULL DEFAULT 0;
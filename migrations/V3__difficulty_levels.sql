@@ -0,0 +1,7 @@
+-- Current adaptive difficulty level per operation type
+
+CREATE TABLE IF NOT EXISTS difficulty_levels (
+    operation_type TEXT PRIMARY KEY,
+    level INTEGER NOT NULL,
+    updated_at TEXT NOT NULL
+);
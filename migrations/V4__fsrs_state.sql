@@ -0,0 +1,4 @@
+-- FSRS scheduling state alongside the SM-2 columns
+
+ALTER TABLE review_items ADD COLUMN fsrs_stability REAL;
+ALTER TABLE review_items ADD COLUMN fsrs_difficulty REAL;
@@ -0,0 +1,24 @@
+-- History of every scheduling decision, one row per graded answer
+CREATE TABLE IF NOT EXISTS review_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    operation_id INTEGER NOT NULL,
+    deck_id INTEGER REFERENCES decks(id),
+    scheduler TEXT NOT NULL,
+    quality INTEGER NOT NULL,
+    prev_repetitions INTEGER NOT NULL,
+    prev_interval INTEGER NOT NULL,
+    prev_ease_factor REAL NOT NULL,
+    prev_stability REAL,
+    prev_difficulty REAL,
+    new_repetitions INTEGER NOT NULL,
+    new_interval INTEGER NOT NULL,
+    new_ease_factor REAL NOT NULL,
+    new_stability REAL,
+    new_difficulty REAL,
+    next_review_date TEXT NOT NULL,
+    reviewed_at TEXT NOT NULL,
+    FOREIGN KEY (operation_id) REFERENCES operations(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_log_operation ON review_log(operation_id);
+CREATE INDEX IF NOT EXISTS idx_review_log_reviewed ON review_log(reviewed_at);
@@ -0,0 +1,53 @@
+-- Merge duplicate operations so each fact (type + operands) has a single row and review item
+
+-- Map every duplicate operation to the oldest row for the same fact
+CREATE TEMP TABLE operation_merge AS
+SELECT o.id AS duplicate_id, f.canonical_id
+FROM operations o
+INNER JOIN (
+    SELECT operation_type, operand1, operand2, MIN(id) AS canonical_id
+    FROM operations
+    GROUP BY operation_type, operand1, operand2
+    HAVING COUNT(*) > 1
+) f ON o.operation_type = f.operation_type
+   AND o.operand1 = f.operand1
+   AND o.operand2 = f.operand2
+WHERE o.id <> f.canonical_id;
+
+-- Keep only the most recently reviewed review item per fact
+CREATE TEMP TABLE review_item_facts AS
+SELECT r.id, COALESCE(m.canonical_id, r.operation_id) AS canonical_id, r.last_reviewed_date
+FROM review_items r
+LEFT JOIN operation_merge m ON r.operation_id = m.duplicate_id;
+
+DELETE FROM review_items
+WHERE id NOT IN (
+    SELECT (
+        SELECT k.id FROM review_item_facts k
+        WHERE k.canonical_id = g.canonical_id
+        ORDER BY k.last_reviewed_date IS NULL, k.last_reviewed_date DESC, k.id ASC
+        LIMIT 1
+    )
+    FROM review_item_facts g
+    GROUP BY g.canonical_id
+);
+
+-- Point all history at the canonical operation
+UPDATE review_items
+SET operation_id = (SELECT canonical_id FROM operation_merge WHERE duplicate_id = review_items.operation_id)
+WHERE operation_id IN (SELECT duplicate_id FROM operation_merge);
+
+UPDATE answers
+SET operation_id = (SELECT canonical_id FROM operation_merge WHERE duplicate_id = answers.operation_id)
+WHERE operation_id IN (SELECT duplicate_id FROM operation_merge);
+
+UPDATE review_log
+SET operation_id = (SELECT canonical_id FROM operation_merge WHERE duplicate_id = review_log.operation_id)
+WHERE operation_id IN (SELECT duplicate_id FROM operation_merge);
+
+DELETE FROM operations WHERE id IN (SELECT duplicate_id FROM operation_merge);
+
+DROP TABLE operation_merge;
+DROP TABLE review_item_facts;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_operations_fact ON operations(operation_type, operand1, operand2);
@@ -0,0 +1,14 @@
+-- Questions of each unfinished deck in order, with answers journaled as they are given,
+-- so a deck interrupted by a crash can be resumed; rows are removed once the deck is saved
+CREATE TABLE IF NOT EXISTS deck_questions (
+    deck_id INTEGER NOT NULL REFERENCES decks(id),
+    position INTEGER NOT NULL,
+    operation_id INTEGER REFERENCES operations(id),
+    operation_type TEXT NOT NULL,
+    operand1 INTEGER NOT NULL,
+    operand2 INTEGER NOT NULL,
+    user_answer INTEGER,
+    time_spent_seconds REAL,
+    answered_at TEXT,
+    PRIMARY KEY (deck_id, position)
+);
@@ -0,0 +1,63 @@
+-- Practice profiles sharing one database; all earlier history belongs to the default profile
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO users (id, name) VALUES (1, 'Default');
+
+-- SQLite only adds a REFERENCES column with a non-NULL default while foreign key enforcement
+-- is off, which it is during migrations (see connection::init_connection)
+ALTER TABLE decks ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1 REFERENCES users(id);
+ALTER TABLE answers ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1 REFERENCES users(id);
+ALTER TABLE review_log ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1 REFERENCES users(id);
+
+CREATE INDEX IF NOT EXISTS idx_decks_user ON decks(user_id);
+CREATE INDEX IF NOT EXISTS idx_answers_user ON answers(user_id);
+CREATE INDEX IF NOT EXISTS idx_review_log_user ON review_log(user_id);
+
+-- Each profile has its own schedule per operation, so the key becomes (user_id, operation_id)
+CREATE TABLE review_items_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL DEFAULT 1 REFERENCES users(id),
+    operation_id INTEGER NOT NULL,
+    repetitions INTEGER NOT NULL DEFAULT 0,
+    interval INTEGER NOT NULL DEFAULT 0,
+    ease_factor REAL NOT NULL DEFAULT 2.5,
+    next_review_date TEXT NOT NULL,
+    last_reviewed_date TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    fsrs_stability REAL,
+    fsrs_difficulty REAL,
+    UNIQUE (user_id, operation_id),
+    FOREIGN KEY (operation_id) REFERENCES operations(id)
+);
+
+INSERT INTO review_items_new (id, operation_id, repetitions, interval, ease_factor,
+                              next_review_date, last_reviewed_date, created_at,
+                              fsrs_stability, fsrs_difficulty)
+SELECT id, operation_id, repetitions, interval, ease_factor,
+       next_review_date, last_reviewed_date, created_at,
+       fsrs_stability, fsrs_difficulty
+FROM review_items;
+
+DROP TABLE review_items;
+ALTER TABLE review_items_new RENAME TO review_items;
+
+CREATE INDEX IF NOT EXISTS idx_next_review ON review_items(user_id, next_review_date);
+
+-- Adaptive levels follow each profile's own answers
+CREATE TABLE difficulty_levels_new (
+    user_id INTEGER NOT NULL DEFAULT 1 REFERENCES users(id),
+    operation_type TEXT NOT NULL,
+    level INTEGER NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, operation_type)
+);
+
+INSERT INTO difficulty_levels_new (operation_type, level, updated_at)
+SELECT operation_type, level, updated_at FROM difficulty_levels;
+
+DROP TABLE difficulty_levels;
+ALTER TABLE difficulty_levels_new RENAME TO difficulty_levels;
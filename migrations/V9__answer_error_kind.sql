@@ -0,0 +1,6 @@
+-- Likely cause of a wrong answer (see error_analysis::ErrorKind); NULL for correct answers.
+-- Wrong answers recorded before this migration keep a NULL label; ErrorsRepository classifies
+-- them when mistakes are counted.
+ALTER TABLE answers ADD COLUMN error_kind TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_answers_error_kind ON answers(error_kind);
@@ -0,0 +1,348 @@
+use crate::database::DifficultyLevelsRepository;
+use crate::database::analytics::{AccuracyRepository, TimeStatisticsRepository};
+use crate::difficulty::{ADAPTIVE_PROFILE_NAME, DifficultyProfile, OperationProfile};
+use crate::operations::OperationType;
+use crate::spaced_repetition::AnswerTimedEvaluator;
+use chrono::{DateTime, Utc};
+use log::info;
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+
+pub const MIN_LEVEL: i32 = 1;
+pub const MAX_LEVEL: i32 = 5;
+/// Level assigned to an operation type the first time it is practiced adaptively
+pub const DEFAULT_LEVEL: i32 = 2;
+
+/// Accuracy band (in percent) the adaptive generator aims for
+pub const TARGET_ACCURACY_LOW: f64 = 80.0;
+pub const TARGET_ACCURACY_HIGH: f64 = 85.0;
+
+/// Number of answers at the current level required before the level may change
+pub const MIN_ANSWERS_FOR_ADJUSTMENT: i64 = 10;
+
+const ALL_OPERATION_TYPES: [OperationType; 4] = [
+    OperationType::Addition,
+    OperationType::Subtraction,
+    OperationType::Multiplication,
+    OperationType::Division,
+];
+
+/// Outcome of evaluating recent performance at the current level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelChange {
+    Raise,
+    Lower,
+    Hold,
+}
+
+/// Decide whether to move up or down a level
+///
+/// Raises the level when accuracy is above the target band and answers are not slower than
+/// one standard deviation over the user's baseline; lowers it when accuracy drops below the band.
+pub fn decide_level_change(
+    accuracy: f64,
+    recent_time: Option<&AnswerTimedEvaluator>,
+    baseline_time: Option<&AnswerTimedEvaluator>,
+) -> LevelChange {
+    if accuracy < TARGET_ACCURACY_LOW {
+        return LevelChange::Lower;
+    }
+    if accuracy <= TARGET_ACCURACY_HIGH {
+        return LevelChange::Hold;
+    }
+
+    let is_slow = match (recent_time, baseline_time) {
+        (Some(recent), Some(baseline)) => {
+            recent.average > baseline.average + baseline.standard_deviation
+        }
+        _ => false,
+    };
+
+    if is_slow {
+        LevelChange::Hold
+    } else {
+        LevelChange::Raise
+    }
+}
+
+/// Operand ranges for an operation type at a given level
+pub fn operation_profile(operation_type: &OperationType, level: i32) -> OperationProfile {
+    let level = level.clamp(MIN_LEVEL, MAX_LEVEL);
+    let weight = (MAX_LEVEL - level + 2) as u32;
+
+    let (operand1, operand2) = match (operation_type, level) {
+        (OperationType::Addition | OperationType::Subtraction, 1) => (1..=9, 1..=9),
+        (OperationType::Addition | OperationType::Subtraction, 2) => (10..=99, 1..=9),
+        (OperationType::Addition | OperationType::Subtraction, 3) => (10..=99, 10..=99),
+        (OperationType::Addition | OperationType::Subtraction, 4) => (100..=999, 10..=99),
+        (OperationType::Addition | OperationType::Subtraction, _) => (100..=999, 100..=999),
+        // For division the first range is the quotient, the second the divisor
+        (OperationType::Multiplication | OperationType::Division, 1) => (1..=5, 1..=5),
+        (OperationType::Multiplication | OperationType::Division, 2) => (1..=9, 1..=9),
+        (OperationType::Multiplication | OperationType::Division, 3) => (2..=12, 2..=12),
+        (OperationType::Multiplication | OperationType::Division, 4) => (10..=99, 2..=9),
+        (OperationType::Multiplication | OperationType::Division, _) => (10..=99, 10..=99),
+    };
+
+    OperationProfile::new(operation_type.clone(), weight, operand1, operand2)
+}
+
+/// Build the adaptive difficulty profile from the stored levels
+///
+/// Operation types without a stored level use `DEFAULT_LEVEL`. Lower levels get a larger share
+/// of the mix so weaker operation types are practiced more often.
+pub fn profile_for_levels(levels: &HashMap<String, i32>) -> DifficultyProfile {
+    DifficultyProfile {
+        name: ADAPTIVE_PROFILE_NAME,
+        description: "Adjusts operand size and mix per operation type to keep accuracy near 80-85%",
+        operations: ALL_OPERATION_TYPES
+            .iter()
+            .map(|op_type| {
+                let level = levels
+                    .get(op_type.as_str())
+                    .copied()
+                    .unwrap_or(DEFAULT_LEVEL);
+                operation_profile(op_type, level)
+            })
+            .collect(),
+    }
+}
+
+/// Tracks and updates the user's difficulty level per operation type
+pub struct AdaptiveDifficulty<'a> {
+    conn: &'a Connection,
+    get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    user_id: i64,
+}
+
+impl<'a> AdaptiveDifficulty<'a> {
+    pub fn new(
+        conn: &'a Connection,
+        user_id: i64,
+        get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    ) -> Self {
+        Self {
+            conn,
+            get_current_time,
+            user_id,
+        }
+    }
+
+    /// Current level per operation type (only types with a stored level)
+    pub fn levels(&self) -> Result<HashMap<String, i32>> {
+        let repo = DifficultyLevelsRepository::new(
+            self.conn,
+            self.user_id,
+            Box::new(|| (self.get_current_time)()),
+        );
+        Ok(repo
+            .get_all()?
+            .into_iter()
+            .map(|record| (record.operation_type, record.level))
+            .collect())
+    }
+
+    /// Adaptive profile built from the current levels
+    pub fn profile(&self) -> Result<DifficultyProfile> {
+        Ok(profile_for_levels(&self.levels()?))
+    }
+
+    /// Re-evaluate each operation type and persist any level change
+    ///
+    /// Only answers given since the last level change are considered, so a level moves at most
+    /// one step per batch of `MIN_ANSWERS_FOR_ADJUSTMENT` answers.
+    /// Returns the decision taken for each operation type that had enough data.
+    pub fn update_levels(&self) -> Result<HashMap<String, LevelChange>> {
+        let levels_repo = DifficultyLevelsRepository::new(
+            self.conn,
+            self.user_id,
+            Box::new(|| (self.get_current_time)()),
+        );
+        let accuracy_repo = AccuracyRepository::new(self.conn, self.user_id);
+        let time_repo = TimeStatisticsRepository::new(self.conn, self.user_id);
+
+        let baseline_times = time_repo.all_operations()?;
+        let recent_accuracy = accuracy_repo.all_operations_last_10_decks()?;
+        let recent_times = time_repo.all_operations_last_10_decks()?;
+
+        let mut changes = HashMap::new();
+
+        for op_type in ALL_OPERATION_TYPES.iter() {
+            let key = op_type.as_str();
+            let stored = levels_repo.get(key)?;
+
+            // Without a stored level, judge the recent decks; otherwise only the current level
+            let (level, accuracy, recent_time) = match &stored {
+                Some(record) => (
+                    record.level,
+                    accuracy_repo
+                        .all_operations_since(record.updated_at)?
+                        .remove(key),
+                    time_repo
+                        .all_operations_since(record.updated_at)?
+                        .remove(key),
+                ),
+                None => (
+                    DEFAULT_LEVEL,
+                    recent_accuracy.get(key).copied(),
+                    recent_times.get(key).copied(),
+                ),
+            };
+
+            let Some((_, total, accuracy)) = accuracy else {
+                continue;
+            };
+            if total < MIN_ANSWERS_FOR_ADJUSTMENT {
+                continue;
+            }
+
+            let change =
+                decide_level_change(accuracy, recent_time.as_ref(), baseline_times.get(key));
+            let new_level = match change {
+                LevelChange::Raise => (level + 1).min(MAX_LEVEL),
+                LevelChange::Lower => (level - 1).max(MIN_LEVEL),
+                LevelChange::Hold => level,
+            };
+
+            if new_level != level || stored.is_none() {
+                info!(
+                    "Adaptive difficulty for {}: {:.1}% accuracy over {} answers, level {} -> {}",
+                    key, accuracy, total, level, new_level
+                );
+                levels_repo.set(key, new_level)?;
+            }
+
+            changes.insert(key.to_string(), change);
+        }
+
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DEFAULT_USER_ID;
+    use crate::database::connection::init_connection;
+    use crate::database::{AnswersRepository, DecksRepository, OperationsRepository};
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    fn answer_deck(conn: &Connection, op_type: &str, correct: usize, incorrect: usize) {
+        let decks_repo = DecksRepository::new(conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let ops_repo = OperationsRepository::new(conn);
+        let answers_repo = AnswersRepository::new(conn, DEFAULT_USER_ID);
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo.insert(op_type, 2, 3, 5, Some(deck_id)).unwrap();
+        for _ in 0..correct {
+            answers_repo
+                .insert(op_id, 5, true, 2.0, Some(deck_id))
+                .unwrap();
+        }
+        for _ in 0..incorrect {
+            answers_repo
+                .insert(op_id, 4, false, 2.0, Some(deck_id))
+                .unwrap();
+        }
+        decks_repo.complete(deck_id).unwrap();
+    }
+
+    #[test]
+    fn test_decide_level_change_bands() {
+        assert_eq!(decide_level_change(70.0, None, None), LevelChange::Lower);
+        assert_eq!(decide_level_change(82.0, None, None), LevelChange::Hold);
+        assert_eq!(decide_level_change(95.0, None, None), LevelChange::Raise);
+    }
+
+    #[test]
+    fn test_decide_level_change_holds_when_slow() {
+        let baseline = AnswerTimedEvaluator::new(3.0, 1.0);
+        let slow = AnswerTimedEvaluator::new(5.0, 1.0);
+        let fast = AnswerTimedEvaluator::new(3.5, 1.0);
+        assert_eq!(
+            decide_level_change(95.0, Some(&slow), Some(&baseline)),
+            LevelChange::Hold
+        );
+        assert_eq!(
+            decide_level_change(95.0, Some(&fast), Some(&baseline)),
+            LevelChange::Raise
+        );
+    }
+
+    #[test]
+    fn test_operation_profile_grows_with_level() {
+        let low = operation_profile(&OperationType::Addition, 1);
+        let high = operation_profile(&OperationType::Addition, MAX_LEVEL);
+        assert!(high.operand1.end() > low.operand1.end());
+        assert!(low.weight > high.weight);
+
+        // Out-of-range levels are clamped
+        assert_eq!(operation_profile(&OperationType::Addition, 0), low);
+        assert_eq!(operation_profile(&OperationType::Addition, 99), high);
+    }
+
+    #[test]
+    fn test_profile_for_levels_uses_defaults() {
+        let profile = profile_for_levels(&HashMap::new());
+        assert!(profile.is_adaptive());
+        assert_eq!(profile.operations.len(), 4);
+        assert_eq!(
+            profile.operations[0],
+            operation_profile(&OperationType::Addition, DEFAULT_LEVEL)
+        );
+    }
+
+    #[test]
+    fn test_update_levels_requires_enough_answers() {
+        let conn = create_test_db();
+        answer_deck(&conn, "ADD", 3, 0);
+
+        let adaptive = AdaptiveDifficulty::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        assert!(adaptive.update_levels().unwrap().is_empty());
+        assert!(adaptive.levels().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_update_levels_raises_and_lowers() {
+        let conn = create_test_db();
+        answer_deck(&conn, "ADD", 10, 0);
+        answer_deck(&conn, "MULTIPLY", 5, 5);
+
+        let adaptive = AdaptiveDifficulty::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let changes = adaptive.update_levels().unwrap();
+        assert_eq!(changes.get("ADD"), Some(&LevelChange::Raise));
+        assert_eq!(changes.get("MULTIPLY"), Some(&LevelChange::Lower));
+
+        let levels = adaptive.levels().unwrap();
+        assert_eq!(levels.get("ADD"), Some(&(DEFAULT_LEVEL + 1)));
+        assert_eq!(levels.get("MULTIPLY"), Some(&(DEFAULT_LEVEL - 1)));
+    }
+
+    #[test]
+    fn test_update_levels_only_counts_answers_since_change() {
+        let conn = create_test_db();
+        answer_deck(&conn, "ADD", 10, 0);
+
+        let past = Utc::now() - chrono::Duration::days(1);
+        let adaptive = AdaptiveDifficulty::new(&conn, DEFAULT_USER_ID, Box::new(move || past));
+        adaptive.update_levels().unwrap();
+
+        // Stored level now dates from yesterday; the same answers count once more
+        let adaptive = AdaptiveDifficulty::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        adaptive.update_levels().unwrap();
+        assert_eq!(
+            adaptive.levels().unwrap().get("ADD"),
+            Some(&(DEFAULT_LEVEL + 2))
+        );
+
+        // Level changed just now, so no answers are newer and nothing moves
+        assert!(adaptive.update_levels().unwrap().is_empty());
+        assert_eq!(
+            adaptive.levels().unwrap().get("ADD"),
+            Some(&(DEFAULT_LEVEL + 2))
+        );
+    }
+}
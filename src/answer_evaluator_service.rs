@@ -1,68 +1,172 @@
-use crate::database::analytics::TimeStatisticsRepository;
-use crate::spaced_repetition::AnswerTimedEvaluator;
+use crate::database::SettingsRepository;
+use crate::database::analytics::{TimeStatisticsRepository, TimingBaselines};
+use crate::database::settings::{PARTIAL_CREDIT_KEY, TIMING_ESTIMATOR_KEY};
+use crate::error_analysis::ErrorKind;
+use crate::operations::Operation;
+use crate::spaced_repetition::{AnswerTimedEvaluator, PartialCreditPolicy};
+use crate::timing_estimator::TimingEstimator;
+use log::warn;
 use rusqlite::Connection;
+use sra::sm_2::Quality;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 /// Service for evaluating answer performance based on historical timing data
+///
+/// Settings and the timing baselines of each operation type are read once and reused for every
+/// answer the service grades, so a service is meant to grade a single deck.
 pub struct AnswerEvaluatorService<'a> {
     conn: &'a Connection,
+    user_id: i64,
+    partial_credit: PartialCreditPolicy,
+    estimator: TimingEstimator,
+    baselines: RefCell<HashMap<String, TimingBaselines>>,
 }
 
 impl<'a> AnswerEvaluatorService<'a> {
-    /// Create a new AnswerEvaluatorService
-    pub fn new(conn: &'a Connection) -> Self {
-        Self { conn }
+    /// Create a new AnswerEvaluatorService, reading the partial credit policy and the timing
+    /// estimator from the database settings
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        Self {
+            conn,
+            user_id,
+            partial_credit: Self::load_partial_credit_policy(conn),
+            estimator: Self::load_timing_estimator(conn),
+            baselines: RefCell::new(HashMap::new()),
+        }
     }
 
-    /// Get or create an AnswerTimedEvaluator for the given operation type
+    /// Get or create an AnswerTimedEvaluator for the given question
     ///
-    /// Retrieves historical timing statistics from the database for the operation type.
-    /// Falls back to default values (average: 3.0s, stdev: 2.0s) if no historical data exists.
-    pub fn get_evaluator(&self, operation_type: &str) -> AnswerTimedEvaluator {
-        TimeStatisticsRepository::new(self.conn)
-            .for_operation_type(operation_type)
-            .ok()
-            .flatten()
+    /// Compares with earlier answers to questions of similar difficulty (digit count, carries,
+    /// operand magnitude) once there are enough of them, otherwise with all answers of the
+    /// operation type, leaving out outliers as the stored `TimingEstimator` decides. Falls back to
+    /// default values (average: 3.0s, stdev: 2.0s) if no historical data exists.
+    pub fn get_evaluator(&self, operation: &Operation) -> AnswerTimedEvaluator {
+        let mut baselines = self.baselines.borrow_mut();
+        baselines
+            .entry(operation.operation_type.as_str().to_string())
+            .or_insert_with(|| {
+                TimeStatisticsRepository::new(self.conn, self.user_id)
+                    .with_estimator(self.estimator)
+                    .baselines(&operation.operation_type)
+                    .unwrap_or_default()
+            })
+            .for_operation(operation)
             .unwrap_or_else(|| {
                 // Fallback if no historical data exists
                 AnswerTimedEvaluator::new(3.0, 2.0)
             })
     }
+
+    /// Answer-time estimator persisted in the database settings when the service was created
+    /// (median absolute deviation cut-off by default)
+    pub fn timing_estimator(&self) -> TimingEstimator {
+        self.estimator
+    }
+
+    fn load_timing_estimator(conn: &Connection) -> TimingEstimator {
+        SettingsRepository::new(conn)
+            .get(TIMING_ESTIMATOR_KEY)
+            .ok()
+            .flatten()
+            .and_then(|name| {
+                let estimator = TimingEstimator::from(&name);
+                if estimator.is_none() {
+                    warn!("Unknown timing estimator '{}', using default", name);
+                }
+                estimator
+            })
+            .unwrap_or_default()
+    }
+
+    /// Partial credit policy persisted in the database settings when the service was created
+    /// (off by default)
+    pub fn partial_credit_policy(&self) -> PartialCreditPolicy {
+        self.partial_credit
+    }
+
+    fn load_partial_credit_policy(conn: &Connection) -> PartialCreditPolicy {
+        SettingsRepository::new(conn)
+            .get(PARTIAL_CREDIT_KEY)
+            .ok()
+            .flatten()
+            .and_then(|name| {
+                let policy = PartialCreditPolicy::from(&name);
+                if policy.is_none() {
+                    warn!("Unknown partial credit policy '{}', using default", name);
+                }
+                policy
+            })
+            .unwrap_or_default()
+    }
+
+    /// Grade an answer from the timing history of similar questions, or from the likely cause of
+    /// the mistake when it is wrong
+    pub fn evaluate(
+        &self,
+        operation: &Operation,
+        is_correct: bool,
+        error_kind: Option<ErrorKind>,
+        time_spent: f64,
+    ) -> Quality {
+        self.get_evaluator(operation).evaluate_answer(
+            is_correct,
+            error_kind,
+            time_spent,
+            self.partial_credit,
+        )
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::arc_with_non_send_sync)]
 mod tests {
     use super::*;
-    use crate::database::{AnswersRepository, Database, OperationsRepository};
+    use crate::database::analytics::time_statistics::MIN_BUCKET_ANSWERS;
+    use crate::database::{
+        AnswersRepository, DEFAULT_USER_ID, Database, DecksRepository, OperationsRepository,
+    };
+    use crate::operations::OperationType;
+    use crate::spaced_repetition::quality_grade;
     use std::sync::Arc;
 
     #[test]
     fn test_get_evaluator_with_historical_data() {
         let db = Arc::new(Database::new(":memory:").unwrap());
-        let service = AnswerEvaluatorService::new(&db.conn);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
 
         // Insert some operations and answers to create historical data
+        let repo_decks =
+            DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let deck_id = repo_decks.create().unwrap();
         let repo_operations = OperationsRepository::new(&db.conn);
-        let operation_id = repo_operations.insert("addition", 5, 3, 8, None).unwrap();
+        let operation_id = repo_operations
+            .insert("ADD", 5, 3, 8, Some(deck_id))
+            .unwrap();
 
-        // Insert several answers with known times
-        let repo_answers = AnswersRepository::new(&db.conn);
-        let _ = repo_answers.insert(operation_id, 8, true, 1.0, None);
-        let _ = repo_answers.insert(operation_id, 8, true, 1.5, None);
-        let _ = repo_answers.insert(operation_id, 8, true, 2.0, None);
+        // Insert several answers with known times; only completed decks count
+        let repo_answers = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
+        for time_spent in [1.0, 1.5, 2.0] {
+            repo_answers
+                .insert(operation_id, 8, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        repo_decks.complete(deck_id).unwrap();
 
-        let evaluator = service.get_evaluator("addition");
+        let evaluator = service.get_evaluator(&Operation::new(OperationType::Addition, 5, 3));
 
-        // Verify that the evaluator was created and has valid values
-        assert!(evaluator.average > 0.0);
-        assert!(evaluator.standard_deviation >= 0.0);
+        // Too few answers for the bucket's own baseline, so all additions are averaged
+        assert!((evaluator.average - 1.5).abs() < 0.001);
+        assert!((evaluator.standard_deviation - (1.0f64 / 6.0).sqrt()).abs() < 0.001);
     }
 
     #[test]
     fn test_get_evaluator_without_historical_data() {
         let db = Arc::new(Database::new(":memory:").unwrap());
-        let service = AnswerEvaluatorService::new(&db.conn);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
 
-        let evaluator = service.get_evaluator("multiplication");
+        let evaluator = service.get_evaluator(&Operation::new(OperationType::Multiplication, 7, 8));
 
         // Should return default fallback values
         assert_eq!(evaluator.average, 3.0);
@@ -72,11 +176,11 @@ mod tests {
     #[test]
     fn test_get_evaluator_different_operation_types() {
         let db = Arc::new(Database::new(":memory:").unwrap());
-        let service = AnswerEvaluatorService::new(&db.conn);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
 
         // Test that different operation types can have different statistics
-        let eval_add = service.get_evaluator("addition");
-        let eval_sub = service.get_evaluator("subtraction");
+        let eval_add = service.get_evaluator(&Operation::new(OperationType::Addition, 5, 3));
+        let eval_sub = service.get_evaluator(&Operation::new(OperationType::Subtraction, 5, 3));
 
         // Both should return fallback since no data exists
         assert_eq!(eval_add.average, 3.0);
@@ -86,12 +190,179 @@ mod tests {
     #[test]
     fn test_get_evaluator_fallback_values() {
         let db = Arc::new(Database::new(":memory:").unwrap());
-        let service = AnswerEvaluatorService::new(&db.conn);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
 
-        let evaluator = service.get_evaluator("division");
+        let evaluator = service.get_evaluator(&Operation::new(OperationType::Division, 56, 8));
 
         // Verify fallback values
         assert_eq!(evaluator.average, 3.0);
         assert_eq!(evaluator.standard_deviation, 2.0);
     }
+
+    #[test]
+    fn test_evaluate_uses_stored_partial_credit_policy() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        let near_miss = Some(ErrorKind::OffByOne);
+        let seven_eights = Operation::new(OperationType::Multiplication, 7, 8);
+
+        assert_eq!(service.partial_credit_policy(), PartialCreditPolicy::Off);
+        let grade = service.evaluate(&seven_eights, false, near_miss, 2.0);
+        assert_eq!(quality_grade(grade), 0);
+
+        // The policy is read when the service is created
+        let settings = SettingsRepository::new(&db.conn);
+        settings.set(PARTIAL_CREDIT_KEY, "near-miss").unwrap();
+        let grade = service.evaluate(&seven_eights, false, near_miss, 2.0);
+        assert_eq!(quality_grade(grade), 0);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        let grade = service.evaluate(&seven_eights, false, near_miss, 2.0);
+        assert_eq!(quality_grade(grade), 2);
+
+        settings.set(PARTIAL_CREDIT_KEY, "generous").unwrap();
+        assert_eq!(
+            AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID).partial_credit_policy(),
+            PartialCreditPolicy::Off
+        );
+    }
+
+    #[test]
+    fn test_get_evaluator_prefers_similar_questions() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        let decks = DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let answers = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
+
+        let deck_id = decks.create().unwrap();
+        let operations = OperationsRepository::new(&db.conn);
+        let easy_id = operations.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        let hard_id = operations
+            .insert("ADD", 87, 96, 183, Some(deck_id))
+            .unwrap();
+        for _ in 0..10 {
+            answers
+                .insert(easy_id, 5, true, 1.0, Some(deck_id))
+                .unwrap();
+        }
+        for time_spent in [8.0, 9.0, 10.0, 9.0, 9.0] {
+            answers
+                .insert(hard_id, 183, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        decks.complete(deck_id).unwrap();
+
+        let hard = service.get_evaluator(&Operation::new(OperationType::Addition, 78, 69));
+        assert!((hard.average - 9.0).abs() < 0.001);
+        // Eight and a half seconds is a normal time for a hard addition, not serious difficulty
+        let grade = service.evaluate(
+            &Operation::new(OperationType::Addition, 78, 69),
+            true,
+            None,
+            8.5,
+        );
+        assert_eq!(quality_grade(grade), 5);
+
+        // No three-digit additions answered yet: all additions together
+        let three_digits = service.get_evaluator(&Operation::new(OperationType::Addition, 12, 3));
+        assert!(three_digits.average > 1.0 && three_digits.average < 9.0);
+    }
+
+    #[test]
+    fn test_baselines_are_read_once_per_service() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let decks = DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let answers = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
+        let op_id = OperationsRepository::new(&db.conn)
+            .insert("MULTIPLY", 7, 8, 56, None)
+            .unwrap();
+        let seven_eights = Operation::new(OperationType::Multiplication, 7, 8);
+        let practise = |time_spent: f64| {
+            let deck_id = decks.create().unwrap();
+            answers
+                .insert(op_id, 56, true, time_spent, Some(deck_id))
+                .unwrap();
+            decks.complete(deck_id).unwrap();
+        };
+
+        practise(2.0);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        assert!((service.get_evaluator(&seven_eights).average - 2.0).abs() < 0.001);
+
+        // A deck completed meanwhile only counts for the next service
+        practise(4.0);
+        assert!((service.get_evaluator(&seven_eights).average - 2.0).abs() < 0.001);
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        assert!((service.get_evaluator(&seven_eights).average - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_deck_is_graded_against_one_reading_of_every_bucket() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let decks = DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let answers = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
+        let operations = OperationsRepository::new(&db.conn);
+        let easy_id = operations.insert("ADD", 2, 3, 5, None).unwrap();
+        let hard_id = operations.insert("ADD", 87, 96, 183, None).unwrap();
+        let practise = |op_id: i64, answer: i32, time_spent: f64| {
+            let deck_id = decks.create().unwrap();
+            for _ in 0..MIN_BUCKET_ANSWERS {
+                answers
+                    .insert(op_id, answer, true, time_spent, Some(deck_id))
+                    .unwrap();
+            }
+            decks.complete(deck_id).unwrap();
+        };
+        practise(easy_id, 5, 1.0);
+        practise(hard_id, 183, 9.0);
+        let easy = Operation::new(OperationType::Addition, 2, 3);
+        let hard = Operation::new(OperationType::Addition, 87, 96);
+
+        // As for a deck being saved: its first answer reads the baselines of every bucket
+        let deck_service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        assert!((deck_service.get_evaluator(&easy).average - 1.0).abs() < 0.001);
+
+        // Later answers of the deck use that reading, not a new one per answer
+        practise(hard_id, 183, 19.0);
+        assert!((deck_service.get_evaluator(&hard).average - 9.0).abs() < 0.001);
+
+        // The next deck's service sees the new answers
+        let next_deck_service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        assert!((next_deck_service.get_evaluator(&hard).average - 14.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_get_evaluator_ignores_outlier_with_stored_estimator() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        let decks = DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let answers = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
+
+        let deck_id = decks.create().unwrap();
+        let op_id = OperationsRepository::new(&db.conn)
+            .insert("MULTIPLY", 7, 8, 56, Some(deck_id))
+            .unwrap();
+        for time_spent in [2.0, 2.5, 3.0, 2.5, 240.0] {
+            answers
+                .insert(op_id, 56, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        decks.complete(deck_id).unwrap();
+        let seven_eights = Operation::new(OperationType::Multiplication, 7, 8);
+
+        assert_eq!(service.timing_estimator(), TimingEstimator::default());
+        assert!((service.get_evaluator(&seven_eights).average - 2.5).abs() < 0.001);
+
+        let settings = SettingsRepository::new(&db.conn);
+        settings.set(TIMING_ESTIMATOR_KEY, "mean").unwrap();
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        assert!(service.get_evaluator(&seven_eights).average > 40.0);
+
+        settings.set(TIMING_ESTIMATOR_KEY, "trimmed:0.2").unwrap();
+        let service = AnswerEvaluatorService::new(&db.conn, DEFAULT_USER_ID);
+        assert_eq!(
+            service.timing_estimator(),
+            TimingEstimator::Trimmed { fraction: 0.2 }
+        );
+        assert!((service.get_evaluator(&seven_eights).average - 8.0 / 3.0).abs() < 0.001);
+    }
 }
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use clap::Parser;
+use memory_practice::database::{DEFAULT_USER_ID, Database, UsersRepository};
+use memory_practice::export::{ExportFilter, ExportFormat, HistoryExport};
+use memory_practice::operations::OperationType;
+use std::path::PathBuf;
+
+/// Exports practice history for spreadsheets and external analysis
+#[derive(Parser, Debug)]
+#[command(name = "Export History")]
+#[command(about = "Exports operations, answers, decks and review items to CSV or JSON", long_about = None)]
+struct Args {
+    /// Path to the SQLite database file
+    #[arg(
+        value_name = "DATABASE_FILE",
+        help = "Path to the SQLite database file"
+    )]
+    database_file: PathBuf,
+
+    /// Output format
+    #[arg(
+        long,
+        default_value = "json",
+        value_parser = parse_format,
+        help = "Output format (csv, json)"
+    )]
+    format: ExportFormat,
+
+    /// JSON file, or directory receiving one CSV file per table
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        help = "JSON file or CSV directory (JSON defaults to standard output)"
+    )]
+    output: Option<PathBuf>,
+
+    /// First day to export
+    #[arg(
+        long,
+        value_name = "YYYY-MM-DD",
+        value_parser = parse_date,
+        help = "First day to export (inclusive)"
+    )]
+    since: Option<NaiveDate>,
+
+    /// Last day to export
+    #[arg(
+        long,
+        value_name = "YYYY-MM-DD",
+        value_parser = parse_date,
+        help = "Last day to export (inclusive)"
+    )]
+    until: Option<NaiveDate>,
+
+    /// Operation types to export
+    #[arg(
+        long = "operation-type",
+        value_name = "TYPE",
+        value_parser = parse_operation_type,
+        help = "Only export this operation type (ADD, SUBTRACT, MULTIPLY, DIVIDE); repeatable"
+    )]
+    operation_types: Vec<String>,
+
+    /// Profile whose history is exported
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Export this profile's history (default: the default profile)"
+    )]
+    user: Option<String>,
+}
+
+fn parse_format(value: &str) -> Result<ExportFormat, String> {
+    ExportFormat::from(value)
+        .ok_or_else(|| format!("Unknown format '{}'. Expected one of: csv, json", value))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}'. Expected YYYY-MM-DD", value))
+}
+
+fn parse_operation_type(value: &str) -> Result<String, String> {
+    let op = value.to_uppercase();
+    if OperationType::all().iter().any(|t| t.as_str() == op) {
+        Ok(op)
+    } else {
+        Err(format!(
+            "Unknown operation type '{}'. Expected one of: ADD, SUBTRACT, MULTIPLY, DIVIDE",
+            value
+        ))
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+fn main() {
+    let args = Args::parse();
+    let db_path = args.database_file.to_string_lossy();
+
+    let db = match Database::new(&db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("Error opening database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let user_id = match &args.user {
+        None => DEFAULT_USER_ID,
+        Some(name) => match UsersRepository::new(&db.conn).find_by_name(name) {
+            Ok(Some(user)) => user.id,
+            Ok(None) => {
+                eprintln!("Error: no profile named '{}'", name);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error reading profiles: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let filter = ExportFilter {
+        user_id,
+        since: args.since.map(start_of_day),
+        until: args
+            .until
+            .and_then(|date| date.checked_add_days(Days::new(1)))
+            .map(start_of_day),
+        operation_types: args.operation_types.clone(),
+    };
+
+    let export = match HistoryExport::collect(&db.conn, &filter, db.get_current_time()) {
+        Ok(export) => export,
+        Err(e) => {
+            eprintln!("Error reading history: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match (args.format, &args.output) {
+        (ExportFormat::Json, output) => {
+            export
+                .to_json()
+                .map_err(|e| e.into())
+                .and_then(|json| match output {
+                    Some(path) => std::fs::write(path, json + "\n").map_err(|e| e.into()),
+                    None => {
+                        println!("{}", json);
+                        Ok(())
+                    }
+                })
+        }
+        (ExportFormat::Csv, Some(dir)) => export.write_csv_dir(dir),
+        (ExportFormat::Csv, None) => {
+            eprintln!("Error: CSV export needs --output DIRECTORY");
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Error writing export: {}", e);
+        std::process::exit(1);
+    }
+
+    if let Some(path) = &args.output {
+        eprintln!(
+            "Exported {} answers, {} decks, {} operations and {} review items to {}",
+            export.answers.len(),
+            export.decks.len(),
+            export.operations.len(),
+            export.review_items.len(),
+            path.display()
+        );
+    }
+}
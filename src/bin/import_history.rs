@@ -0,0 +1,107 @@
+use clap::Parser;
+use memory_practice::database::{DEFAULT_USER_ID, Database, UsersRepository};
+use memory_practice::export::HistoryExport;
+use memory_practice::import::{
+    ImportSummary, import_history, merge_database, open_merge_source, read_csv_dir, read_json,
+};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Imports exported history, or merges another database, into a practice database
+#[derive(Parser, Debug)]
+#[command(name = "Import History")]
+#[command(about = "Imports a JSON/CSV export or merges another database; importing twice adds nothing", long_about = None)]
+struct Args {
+    /// Path to the SQLite database receiving the history
+    #[arg(
+        value_name = "DATABASE_FILE",
+        help = "Path to the SQLite database receiving the history"
+    )]
+    database_file: PathBuf,
+
+    /// JSON export, CSV export directory, or another database with `--merge-db`
+    #[arg(
+        value_name = "SOURCE",
+        help = "JSON export file or CSV export directory"
+    )]
+    source: Option<PathBuf>,
+
+    /// Another practice database to merge in
+    #[arg(
+        long,
+        value_name = "DATABASE_FILE",
+        conflicts_with = "source",
+        help = "Merge another memory_practice database into DATABASE_FILE, profile by profile"
+    )]
+    merge_db: Option<PathBuf>,
+
+    /// Profile receiving an imported export
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with = "merge_db",
+        help = "Import into this profile, creating it if needed (default: the default profile)"
+    )]
+    user: Option<String>,
+}
+
+fn read_source(source: &Path) -> Result<HistoryExport, Box<dyn Error>> {
+    if source.is_dir() {
+        read_csv_dir(source)
+    } else {
+        read_json(source)
+    }
+}
+
+fn import(args: &Args, db: &Database) -> Result<ImportSummary, Box<dyn Error>> {
+    match (&args.source, &args.merge_db) {
+        (_, Some(other)) => {
+            if !other.exists() {
+                return Err(format!("{} does not exist", other.display()).into());
+            }
+            let other = open_merge_source(other)?;
+            merge_database(&db.conn, &other, db.get_current_time())
+        }
+        (Some(source), None) => {
+            let history = read_source(source)?;
+            let user_id = match &args.user {
+                Some(name) => UsersRepository::new(&db.conn).get_or_create(name)?,
+                None => DEFAULT_USER_ID,
+            };
+            import_history(&db.conn, user_id, &history)
+        }
+        (None, None) => Err("Expected an export SOURCE or --merge-db DATABASE_FILE".into()),
+    }
+}
+
+fn print_summary(summary: &ImportSummary, target: &Path) {
+    println!("Imported into {}:", target.display());
+    println!("  Operations added:     {}", summary.operations_added);
+    println!("  Decks added:          {}", summary.decks_added);
+    println!(
+        "  Answers added:        {} ({} already present)",
+        summary.answers_added, summary.answers_skipped
+    );
+    println!("  Review items added:   {}", summary.review_items_added);
+    println!("  Review items updated: {}", summary.review_items_updated);
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let db = match Database::new(&args.database_file.to_string_lossy()) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("Error opening database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match import(&args, &db) {
+        Ok(summary) => print_summary(&summary, &args.database_file),
+        Err(e) => {
+            eprintln!("Error importing history: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
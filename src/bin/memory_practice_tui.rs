@@ -0,0 +1,20 @@
+use memory_practice::database_factory::DatabaseFactory;
+use memory_practice::tui;
+use std::sync::Arc;
+
+#[allow(clippy::arc_with_non_send_sync)]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Logs go to stderr; keep RUST_LOG unset for a clean terminal
+    env_logger::builder().format_timestamp_millis().init();
+
+    // Same arguments as the GUI: --test, --db-path <PATH>, --override-date <YYYY-MM-DD>, ...
+    let config = DatabaseFactory::detect_config();
+    let is_test_mode = config.is_test_mode;
+
+    let db = Arc::new(DatabaseFactory::create(config)?);
+
+    // Run the terminal front end; needs no display, so it works over SSH
+    tui::run_terminal(db, is_test_mode)?;
+
+    Ok(())
+}
@@ -1,13 +1,21 @@
-use chrono::Utc;
 use clap::Parser;
 use colored::Colorize;
+use memory_practice::answer_evaluator_service::AnswerEvaluatorService;
+use memory_practice::database::analytics::decline::describe_status;
 use memory_practice::database::analytics::{
-    AccuracyRepository, StreakRepository, TimeStatisticsRepository,
+    AccuracyRepository, DECLINE_DISCLAIMER, DeclineCriteria, DeclineReport, DeclineRepository,
+    DeclineStatus, ErrorsRepository, MasteryCell, MasteryGrid, MasteryLevel, MasteryRepository,
+    StreakRepository, TimeStatisticsRepository, TimingOutlier,
 };
-use memory_practice::database::{Analytics, Database};
+use memory_practice::database::{Analytics, DEFAULT_USER_ID, Database, UsersRepository};
+use memory_practice::error_analysis::describe_counts;
 use memory_practice::spaced_repetition::AnswerTimedEvaluator;
+use memory_practice::timing_estimator::TimingEstimator;
 use std::path::PathBuf;
 
+/// Timing outliers listed individually; older ones are only counted
+const MAX_LISTED_OUTLIERS: usize = 20;
+
 /// Analyzes performance statistics across different time periods
 #[derive(Parser, Debug)]
 #[command(name = "Performance Stats")]
@@ -23,6 +31,31 @@ struct Args {
     /// Disable colored output
     #[arg(long, help = "Disable colored output")]
     no_color: bool,
+
+    /// Largest operand shown in the multiplication mastery heatmap
+    #[arg(
+        long,
+        default_value_t = 12,
+        value_parser = clap::value_parser!(i32).range(1..=20),
+        help = "Largest operand shown in the multiplication mastery heatmap"
+    )]
+    grid_size: i32,
+
+    /// Write the early-warning report to this file
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the early-warning report to this file"
+    )]
+    decline_report: Option<PathBuf>,
+
+    /// Profile whose statistics are shown
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Show this profile's statistics (default: the default profile)"
+    )]
+    user: Option<String>,
 }
 
 fn main() {
@@ -38,9 +71,27 @@ fn main() {
         }
     };
 
+    let user_id = match &args.user {
+        None => DEFAULT_USER_ID,
+        Some(name) => match UsersRepository::new(&db.conn).find_by_name(name) {
+            Ok(Some(user)) => user.id,
+            Ok(None) => {
+                eprintln!("Error: no profile named '{}'", name);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error reading profiles: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let now = db.get_current_time();
+
     // Fetch all statistics in 3 database queries (one per time period)
-    let analytics = Analytics::new(&db.conn);
-    let global_stats = match TimeStatisticsRepository::new(analytics.conn).all_operations() {
+    let analytics = Analytics::new(&db.conn, user_id);
+    let global_stats = match TimeStatisticsRepository::new(analytics.conn, user_id).all_operations()
+    {
         Ok(stats) => stats,
         Err(e) => {
             eprintln!("Error fetching global statistics: {}", e);
@@ -53,35 +104,38 @@ fn main() {
         return;
     }
 
-    let last_30_days_stats =
-        match TimeStatisticsRepository::new(analytics.conn).all_operations_last_30_days() {
-            Ok(stats) => stats,
-            Err(e) => {
-                eprintln!("Error fetching last 30 days statistics: {}", e);
-                std::process::exit(1);
-            }
-        };
-
-    let last_10_decks_stats =
-        match TimeStatisticsRepository::new(analytics.conn).all_operations_last_10_decks() {
-            Ok(stats) => stats,
-            Err(e) => {
-                eprintln!("Error fetching last 10 decks statistics: {}", e);
-                std::process::exit(1);
-            }
-        };
+    let last_30_days_stats = match TimeStatisticsRepository::new(analytics.conn, user_id)
+        .all_operations_last_30_days(now)
+    {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("Error fetching last 30 days statistics: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    // Fetch accuracy statistics
-    let global_accuracy_stats = match AccuracyRepository::new(analytics.conn).all_operations() {
+    let last_10_decks_stats = match TimeStatisticsRepository::new(analytics.conn, user_id)
+        .all_operations_last_10_decks()
+    {
         Ok(stats) => stats,
         Err(e) => {
-            eprintln!("Error fetching global accuracy statistics: {}", e);
+            eprintln!("Error fetching last 10 decks statistics: {}", e);
             std::process::exit(1);
         }
     };
 
+    // Fetch accuracy statistics
+    let global_accuracy_stats =
+        match AccuracyRepository::new(analytics.conn, user_id).all_operations() {
+            Ok(stats) => stats,
+            Err(e) => {
+                eprintln!("Error fetching global accuracy statistics: {}", e);
+                std::process::exit(1);
+            }
+        };
+
     let last_30_days_accuracy_stats =
-        match AccuracyRepository::new(analytics.conn).all_operations_last_30_days() {
+        match AccuracyRepository::new(analytics.conn, user_id).all_operations_last_30_days(now) {
             Ok(stats) => stats,
             Err(e) => {
                 eprintln!("Error fetching last 30 days accuracy statistics: {}", e);
@@ -89,8 +143,8 @@ fn main() {
             }
         };
 
-    let analytics = Analytics::new(&db.conn);
-    let result = AccuracyRepository::new(analytics.conn).all_operations_last_10_decks();
+    let analytics = Analytics::new(&db.conn, user_id);
+    let result = AccuracyRepository::new(analytics.conn, user_id).all_operations_last_10_decks();
     let last_10_decks_accuracy_stats = match result {
         Ok(stats) => stats,
         Err(e) => {
@@ -99,8 +153,16 @@ fn main() {
         }
     };
 
+    let mistakes = match ErrorsRepository::new(analytics.conn, user_id).all_operations() {
+        Ok(mistakes) => mistakes,
+        Err(e) => {
+            eprintln!("Error fetching mistakes by cause: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Fetch total accuracy
-    let total_accuracy = match AccuracyRepository::new(analytics.conn).total_accuracy() {
+    let total_accuracy = match AccuracyRepository::new(analytics.conn, user_id).total_accuracy() {
         Ok(stats) => stats,
         Err(e) => {
             eprintln!("Error fetching total accuracy: {}", e);
@@ -108,27 +170,38 @@ fn main() {
         }
     };
 
-    let total_accuracy_last_30_days = AccuracyRepository::new(analytics.conn)
-        .total_accuracy_last_30_days()
+    let total_accuracy_last_30_days = AccuracyRepository::new(analytics.conn, user_id)
+        .total_accuracy_last_30_days(now)
         .unwrap_or((0, 0, 0.0));
-    let total_accuracy_last_10_decks = AccuracyRepository::new(analytics.conn)
+    let total_accuracy_last_10_decks = AccuracyRepository::new(analytics.conn, user_id)
         .total_accuracy_last_10_decks()
         .unwrap_or((0, 0, 0.0));
 
     // Calculate consecutive days streak
-    let consecutive_days_streak = StreakRepository::new(analytics.conn)
-        .calculate_consecutive_days()
+    let consecutive_days_streak = StreakRepository::new(analytics.conn, user_id)
+        .calculate_consecutive_days(now)
         .unwrap_or(0);
 
     // Get days with and without answers in the last 10 days
-    let now = Utc::now();
-    let days_with_answers = StreakRepository::new(analytics.conn)
+    let days_with_answers = StreakRepository::new(analytics.conn, user_id)
         .get_days_with_answers(now)
         .unwrap_or_default();
-    let missing_days = StreakRepository::new(analytics.conn)
+    let missing_days = StreakRepository::new(analytics.conn, user_id)
         .get_missing_days(10, now)
         .unwrap_or_default();
 
+    let mastery_grid = match MasteryRepository::new(analytics.conn, user_id).grid(
+        "MULTIPLY",
+        1..=args.grid_size,
+        1..=args.grid_size,
+    ) {
+        Ok(grid) => grid,
+        Err(e) => {
+            eprintln!("Error fetching multiplication mastery: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let header = if use_color {
         "Performance Analysis Report".cyan().bold().to_string()
     } else {
@@ -193,6 +266,13 @@ fn main() {
         print_accuracy_stats("Global (all time)", &global_accuracy, use_color);
         print_accuracy_stats("Last 30 days", &last_30_accuracy, use_color);
         print_accuracy_stats("Last 10 decks", &last_10_accuracy, use_color);
+        let op_mistakes = mistakes.for_operation(&op_type);
+        if !op_mistakes.is_empty() {
+            println!(
+                "  Mistakes by likely cause: {}",
+                describe_counts(op_mistakes)
+            );
+        }
 
         println!();
         let time_label = if use_color {
@@ -295,6 +375,208 @@ fn main() {
             );
         }
     }
+
+    // Answers left out of the baselines that grade answer times: same estimator, judged per
+    // difficulty bucket or per operation type as the grading does
+    let estimator = AnswerEvaluatorService::new(analytics.conn, user_id).timing_estimator();
+    let timing_outliers = match TimeStatisticsRepository::new(analytics.conn, user_id)
+        .with_estimator(estimator)
+        .outliers()
+    {
+        Ok(outliers) => outliers,
+        Err(e) => {
+            eprintln!("Error finding timing outliers: {}", e);
+            std::process::exit(1);
+        }
+    };
+    print_timing_outliers(&timing_outliers, estimator, use_color);
+
+    print_mastery_heatmap(&mastery_grid, use_color);
+
+    let decline_report = match DeclineRepository::new(analytics.conn, user_id)
+        .report(now, DeclineCriteria::default())
+    {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error computing early-warning report: {}", e);
+            std::process::exit(1);
+        }
+    };
+    print_decline_summary(&decline_report, use_color);
+
+    if let Some(path) = &args.decline_report {
+        if let Err(e) = std::fs::write(path, decline_report.to_text()) {
+            eprintln!("Error writing early-warning report: {}", e);
+            std::process::exit(1);
+        }
+        println!();
+        println!("Early-warning report written to {}", path.display());
+    }
+}
+
+/// Print how many correct answers have their times left out of the baselines used for grading,
+/// listing the most recent ones
+fn print_timing_outliers(outliers: &[TimingOutlier], estimator: TimingEstimator, use_color: bool) {
+    println!();
+    let title = format!("Timing Outliers ({})", estimator);
+    if use_color {
+        println!("{}", title.cyan().bold());
+    } else {
+        println!("{}", title);
+    }
+    println!("{}", "=".repeat(title.len()));
+
+    if outliers.is_empty() {
+        println!("No answers excluded from timing baselines");
+        return;
+    }
+    println!(
+        "{} answer(s) excluded from timing baselines",
+        outliers.len()
+    );
+    if outliers.len() > MAX_LISTED_OUTLIERS {
+        println!("Most recent {}:", MAX_LISTED_OUTLIERS);
+    }
+    for outlier in outliers.iter().take(MAX_LISTED_OUTLIERS) {
+        let operation = &outlier.operation;
+        let question = format!(
+            "{} {} {} = {}",
+            operation.operand1,
+            operation.operation_type.symbol(),
+            operation.operand2,
+            operation.result
+        );
+        let time = format!("{:.1}s", outlier.time_spent);
+        if use_color {
+            println!("  {} answered in {}", question, time.red());
+        } else {
+            println!("  {} answered in {}", question, time);
+        }
+    }
+}
+
+/// Print one early-warning line per operation type, followed by the disclaimer
+fn print_decline_summary(report: &DeclineReport, use_color: bool) {
+    println!();
+    let title = "Early-Warning Check";
+    if use_color {
+        println!("{}", title.cyan().bold());
+    } else {
+        println!("{}", title);
+    }
+    println!("===================");
+
+    for op in &report.operations {
+        let status = describe_status(op, &report.criteria);
+        if use_color {
+            let status_colored = match op.status {
+                DeclineStatus::SustainedDecline => status.red().bold(),
+                DeclineStatus::NoSustainedChange => status.green(),
+                DeclineStatus::InsufficientData => status.yellow(),
+            };
+            println!("  {} - {}", op.operation_type.magenta(), status_colored);
+        } else {
+            println!("  {} - {}", op.operation_type, status);
+        }
+    }
+    println!("Note: {}", DECLINE_DISCLAIMER);
+}
+
+/// Print the multiplication table as accuracy, median time and interval grids
+fn print_mastery_heatmap(grid: &MasteryGrid, use_color: bool) {
+    println!();
+    let title = format!(
+        "Multiplication Mastery ({}-{} x {}-{})",
+        grid.rows.start(),
+        grid.rows.end(),
+        grid.columns.start(),
+        grid.columns.end()
+    );
+    let title_underline = "=".repeat(title.len());
+    if use_color {
+        println!("{}", title.cyan().bold());
+    } else {
+        println!("{}", title);
+    }
+    println!("{}", title_underline);
+
+    if grid
+        .cells
+        .iter()
+        .all(|cell| cell.total == 0 && cell.interval.is_none())
+    {
+        println!("No multiplication facts practiced yet");
+        return;
+    }
+
+    print_mastery_table(grid, "Accuracy (%):", use_color, |cell| {
+        cell.accuracy().map(|accuracy| format!("{:.0}", accuracy))
+    });
+    print_mastery_table(grid, "Median time (s):", use_color, |cell| {
+        cell.median_time.map(|time| format!("{:.1}", time))
+    });
+    print_mastery_table(grid, "Interval (days):", use_color, |cell| {
+        cell.interval.map(|interval| interval.to_string())
+    });
+
+    println!();
+    if use_color {
+        println!(
+            "Legend: {} {} {} {}",
+            "mastered".green(),
+            "learning".yellow(),
+            "struggling".red(),
+            "- not practiced".dimmed()
+        );
+    } else {
+        println!(
+            "Legend: mastered >= 90% and interval >= 7 days, learning >= 70%, struggling below"
+        );
+    }
+}
+
+/// Print one grid of the heatmap, coloring each cell by its mastery level
+fn print_mastery_table(
+    grid: &MasteryGrid,
+    label: &str,
+    use_color: bool,
+    value: impl Fn(&MasteryCell) -> Option<String>,
+) {
+    println!();
+    if use_color {
+        println!("{}", label.blue().bold());
+    } else {
+        println!("{}", label);
+    }
+
+    let header: String = grid
+        .columns
+        .clone()
+        .map(|column| format!("{:>5}", column))
+        .collect();
+    println!("{:>4} {}", "x", header);
+
+    for row in grid.rows.clone() {
+        let mut line = format!("{:>4} ", row);
+        for column in grid.columns.clone() {
+            let Some(cell) = grid.get(row, column) else {
+                continue;
+            };
+            let text = format!("{:>5}", value(cell).unwrap_or_else(|| "-".to_string()));
+            if use_color {
+                let colored = match cell.level() {
+                    MasteryLevel::Mastered => text.green(),
+                    MasteryLevel::Learning => text.yellow(),
+                    MasteryLevel::Struggling => text.red(),
+                    MasteryLevel::Unseen => text.dimmed(),
+                };
+                line.push_str(&colored.to_string());
+            } else {
+                line.push_str(&text);
+            }
+        }
+        println!("{}", line);
+    }
 }
 
 /// Print statistics for a given time period
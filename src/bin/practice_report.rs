@@ -0,0 +1,149 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use clap::Parser;
+use memory_practice::database::analytics::{ReportFormat, ReportRepository};
+use memory_practice::database::{DEFAULT_USER_ID, Database, UsersRepository};
+use std::path::PathBuf;
+
+/// Writes a printable practice summary for family members and therapists
+#[derive(Parser, Debug)]
+#[command(name = "Practice Report")]
+#[command(about = "Writes a printable HTML or Markdown practice summary for a date range", long_about = None)]
+struct Args {
+    /// Path to the SQLite database file
+    #[arg(
+        value_name = "DATABASE_FILE",
+        help = "Path to the SQLite database file"
+    )]
+    database_file: PathBuf,
+
+    /// Output format
+    #[arg(
+        long,
+        default_value = "html",
+        value_parser = parse_format,
+        help = "Output format (html, markdown)"
+    )]
+    format: ReportFormat,
+
+    /// File receiving the report
+    #[arg(
+        long,
+        short,
+        value_name = "FILE",
+        help = "File receiving the report (default: standard output)"
+    )]
+    output: Option<PathBuf>,
+
+    /// First day of the report
+    #[arg(
+        long,
+        value_name = "YYYY-MM-DD",
+        value_parser = parse_date,
+        help = "First day of the report (inclusive; default: 29 days before --until)"
+    )]
+    since: Option<NaiveDate>,
+
+    /// Last day of the report
+    #[arg(
+        long,
+        value_name = "YYYY-MM-DD",
+        value_parser = parse_date,
+        help = "Last day of the report (inclusive; default: today)"
+    )]
+    until: Option<NaiveDate>,
+
+    /// Profile the report is about
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Report on this profile (default: the default profile)"
+    )]
+    user: Option<String>,
+}
+
+fn parse_format(value: &str) -> Result<ReportFormat, String> {
+    ReportFormat::from(value).ok_or_else(|| {
+        format!(
+            "Unknown format '{}'. Expected one of: html, markdown",
+            value
+        )
+    })
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}'. Expected YYYY-MM-DD", value))
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+fn main() {
+    let args = Args::parse();
+    let db_path = args.database_file.to_string_lossy();
+
+    let db = match Database::new(&db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("Error opening database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let user_id = match &args.user {
+        None => DEFAULT_USER_ID,
+        Some(name) => match UsersRepository::new(&db.conn).find_by_name(name) {
+            Ok(Some(user)) => user.id,
+            Ok(None) => {
+                eprintln!("Error: no profile named '{}'", name);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error reading profiles: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let now = db.get_current_time();
+    let until = args.until.unwrap_or_else(|| now.date_naive());
+    let since = args
+        .since
+        .unwrap_or_else(|| until.checked_sub_days(Days::new(29)).unwrap_or(until));
+    if since > until {
+        eprintln!("Error: --since must not be after --until");
+        std::process::exit(1);
+    }
+    let end = until
+        .checked_add_days(Days::new(1))
+        .map(start_of_day)
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+    let report =
+        match ReportRepository::new(&db.conn, user_id).report(start_of_day(since), end, now) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Error reading history: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+    let text = report.render(args.format);
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, text) {
+                eprintln!("Error writing report: {}", e);
+                std::process::exit(1);
+            }
+            eprintln!(
+                "Wrote report for {} ({} to {}) to {}",
+                report.profile_name,
+                since,
+                until,
+                path.display()
+            );
+        }
+        None => print!("{}", text),
+    }
+}
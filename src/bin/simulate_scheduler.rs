@@ -0,0 +1,399 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use clap::Parser;
+use memory_practice::database::settings::SCHEDULER_KEY;
+use memory_practice::database::{
+    Database, DecksRepository, OperationsRepository, ReviewItemsRepository, SettingsRepository,
+};
+use memory_practice::date_provider::SimulatedDateProvider;
+use memory_practice::operations::{Operation, OperationType, generate_question_block};
+use memory_practice::quiz_service::QuizService;
+use memory_practice::spaced_repetition::SchedulerKind;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Accuracy and speed used when the learner model has no entry for an operation type
+const DEFAULT_ACCURACY: f64 = 0.9;
+const DEFAULT_SECONDS: f64 = 3.0;
+
+/// Upper bounds (inclusive, in days) of the interval distribution buckets
+const INTERVAL_BUCKETS: [i32; 6] = [1, 3, 7, 14, 30, 90];
+
+/// Simulates days of practice against a copy of a database to tune scheduling parameters
+#[derive(Parser, Debug)]
+#[command(name = "Simulate Scheduler")]
+#[command(about = "Simulates days of practice with a synthetic learner against a database copy", long_about = None)]
+struct Args {
+    /// Database to copy; the original file is never modified
+    #[arg(
+        value_name = "DATABASE_FILE",
+        help = "Path to the SQLite database file to copy"
+    )]
+    database_file: PathBuf,
+
+    /// Where to write the simulated copy
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Path of the simulated database copy (default: system temp directory)"
+    )]
+    output: Option<PathBuf>,
+
+    /// Number of days to simulate
+    #[arg(long, default_value_t = 30, help = "Number of days to simulate")]
+    days: u32,
+
+    /// Questions per deck; new questions fill the deck after due reviews
+    #[arg(long, default_value_t = 10, help = "Questions per deck")]
+    block_size: usize,
+
+    /// Decks practised each simulated day
+    #[arg(long, default_value_t = 1, help = "Decks practised per day")]
+    decks_per_day: u32,
+
+    /// First simulated day
+    #[arg(
+        long,
+        value_name = "YYYY-MM-DD",
+        help = "First simulated day (default: today)"
+    )]
+    start_date: Option<String>,
+
+    /// Scheduler to use in the copy instead of the stored one
+    #[arg(
+        long,
+        value_name = "ALGORITHM",
+        help = "Spaced repetition scheduler (sm2, fsrs)"
+    )]
+    scheduler: Option<String>,
+
+    /// Synthetic learner per operation type, e.g. MULTIPLY=0.8:4.5
+    #[arg(
+        long = "learner",
+        value_name = "OP=ACCURACY:SECONDS",
+        value_parser = parse_learner,
+        help = "Learner accuracy (0-1) and mean answer time in seconds for an operation type, e.g. MULTIPLY=0.8:4.5"
+    )]
+    learners: Vec<(String, LearnerProfile)>,
+
+    /// Seed for the learner's random answers
+    #[arg(long, help = "Seed for the synthetic learner")]
+    seed: Option<u64>,
+}
+
+/// How the synthetic learner performs on one operation type
+#[derive(Debug, Clone, Copy)]
+struct LearnerProfile {
+    /// Probability of answering correctly
+    accuracy: f64,
+    /// Mean time spent per answer, in seconds
+    seconds: f64,
+}
+
+impl Default for LearnerProfile {
+    fn default() -> Self {
+        Self {
+            accuracy: DEFAULT_ACCURACY,
+            seconds: DEFAULT_SECONDS,
+        }
+    }
+}
+
+fn parse_learner(value: &str) -> Result<(String, LearnerProfile), String> {
+    let (op, profile) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected OP=ACCURACY:SECONDS, got '{}'", value))?;
+    let op = op.to_uppercase();
+    if !OperationType::all().iter().any(|t| t.as_str() == op) {
+        return Err(format!(
+            "Unknown operation type '{}'. Expected one of: ADD, SUBTRACT, MULTIPLY, DIVIDE",
+            op
+        ));
+    }
+
+    let (accuracy, seconds) = profile
+        .split_once(':')
+        .ok_or_else(|| format!("Expected ACCURACY:SECONDS, got '{}'", profile))?;
+    let accuracy: f64 = accuracy
+        .parse()
+        .map_err(|_| format!("Invalid accuracy '{}'", accuracy))?;
+    let seconds: f64 = seconds
+        .parse()
+        .map_err(|_| format!("Invalid seconds '{}'", seconds))?;
+    if !(0.0..=1.0).contains(&accuracy) {
+        return Err(format!(
+            "Accuracy must be between 0 and 1, got {}",
+            accuracy
+        ));
+    }
+    if seconds <= 0.0 {
+        return Err(format!("Seconds must be positive, got {}", seconds));
+    }
+
+    Ok((op, LearnerProfile { accuracy, seconds }))
+}
+
+/// Synthetic learner answering questions according to per-operation profiles
+struct Learner {
+    profiles: HashMap<String, LearnerProfile>,
+    rng: StdRng,
+}
+
+impl Learner {
+    /// Returns (user_answer, time_spent)
+    fn answer(&mut self, question: &Operation) -> (i32, f64) {
+        let profile = self
+            .profiles
+            .get(question.operation_type.as_str())
+            .copied()
+            .unwrap_or_default();
+
+        let answer = if self.rng.gen_bool(profile.accuracy) {
+            question.result
+        } else {
+            question.result + self.rng.gen_range(1..=3)
+        };
+        let time_spent = profile.seconds * self.rng.gen_range(0.5..1.5);
+        (answer, time_spent)
+    }
+}
+
+/// Review load and outcome of one simulated day
+struct DayReport {
+    date: DateTime<Utc>,
+    reviews: usize,
+    reviews_correct: usize,
+    new_questions: usize,
+}
+
+#[allow(clippy::arc_with_non_send_sync)]
+fn main() {
+    let args = Args::parse();
+
+    let scheduler = args.scheduler.as_deref().map(|name| {
+        SchedulerKind::from(name).unwrap_or_else(|| {
+            eprintln!(
+                "Error: Unknown scheduler '{}'. Expected one of: sm2, fsrs",
+                name
+            );
+            std::process::exit(1);
+        })
+    });
+    let start = match &args.start_date {
+        Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date.and_hms_opt(12, 0, 0).unwrap().and_utc(),
+            Err(_) => {
+                eprintln!("Error: Invalid start date '{}'. Expected YYYY-MM-DD", date);
+                std::process::exit(1);
+            }
+        },
+        None => Utc::now(),
+    };
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join("memory_practice_simulation.db"));
+    if let Err(e) = std::fs::copy(&args.database_file, &output) {
+        eprintln!(
+            "Error copying {} to {}: {}",
+            args.database_file.display(),
+            output.display(),
+            e
+        );
+        std::process::exit(1);
+    }
+
+    let clock = Arc::new(SimulatedDateProvider::new(start));
+    let db = match Database::with_date_provider(&output.to_string_lossy(), clock.clone()) {
+        Ok(db) => Arc::new(db),
+        Err(e) => {
+            eprintln!("Error opening database copy: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(scheduler) = scheduler
+        && let Err(e) = SettingsRepository::new(&db.conn).set(SCHEDULER_KEY, scheduler.as_str())
+    {
+        eprintln!("Error selecting scheduler: {}", e);
+        std::process::exit(1);
+    }
+
+    let mut learner = Learner {
+        profiles: args.learners.iter().cloned().collect(),
+        rng: match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        },
+    };
+
+    let service = QuizService::new(&db.conn, db.clone());
+    println!(
+        "Simulating {} day(s) from {} with the {} scheduler on {}",
+        args.days,
+        start.format("%Y-%m-%d"),
+        service.scheduler_kind().as_str(),
+        output.display()
+    );
+    println!();
+    println!(
+        "{:<6} {:<12} {:>8} {:>8} {:>10}",
+        "Day", "Date", "Reviews", "New", "Retention"
+    );
+
+    let mut reports = Vec::new();
+    for day in 1..=args.days {
+        let report = simulate_day(&db, &service, &mut learner, &args);
+        println!(
+            "{:<6} {:<12} {:>8} {:>8} {:>10}",
+            day,
+            report.date.format("%Y-%m-%d"),
+            report.reviews,
+            report.new_questions,
+            format_retention(report.reviews_correct, report.reviews)
+        );
+        reports.push(report);
+        clock.advance(Duration::days(1));
+    }
+
+    print_summary(&db, &reports);
+}
+
+fn simulate_day(
+    db: &Database,
+    service: &QuizService,
+    learner: &mut Learner,
+    args: &Args,
+) -> DayReport {
+    let mut report = DayReport {
+        date: db.get_current_time(),
+        reviews: 0,
+        reviews_correct: 0,
+        new_questions: 0,
+    };
+
+    for _ in 0..args.decks_per_day {
+        let mut questions = due_questions(db);
+        let profile = service.difficulty_profile();
+        let mut new_questions =
+            generate_question_block(args.block_size.saturating_sub(questions.len()), &profile);
+        questions.append(&mut new_questions);
+
+        let decks_repo =
+            DecksRepository::new(&db.conn, db.user_id(), Box::new(|| db.get_current_time()));
+        let deck_id = match decks_repo.create() {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("Error creating deck: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let results: Vec<_> = questions
+            .iter()
+            .map(|question| {
+                let (answer, time_spent) = learner.answer(question);
+                service.process_answer(question, answer, time_spent)
+            })
+            .collect();
+        let results = match service.save_deck(deck_id, &results) {
+            Ok(results) => results,
+            Err(e) => {
+                eprintln!("Error saving deck: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        for result in &results {
+            if result.is_review {
+                report.reviews += 1;
+                report.reviews_correct += result.is_correct as usize;
+            } else {
+                report.new_questions += 1;
+            }
+        }
+    }
+
+    report
+}
+
+/// Questions for every review item due at the simulated time
+fn due_questions(db: &Database) -> Vec<Operation> {
+    let now = db.get_current_time();
+    let review_items = ReviewItemsRepository::new(&db.conn, db.user_id(), Box::new(move || now));
+    let operations = OperationsRepository::new(&db.conn);
+
+    review_items
+        .get_due(now)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|item| {
+            let record = operations.get(item.operation_id).ok()??;
+            let op_type = OperationType::all()
+                .into_iter()
+                .find(|t| t.as_str() == record.operation_type)?;
+            let mut operation = Operation::new(op_type, record.operand1, record.operand2);
+            operation.id = Some(record.id);
+            Some(operation)
+        })
+        .collect()
+}
+
+fn format_retention(correct: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", correct as f64 / total as f64 * 100.0)
+    }
+}
+
+fn print_summary(db: &Database, reports: &[DayReport]) {
+    let total_reviews: usize = reports.iter().map(|r| r.reviews).sum();
+    let total_correct: usize = reports.iter().map(|r| r.reviews_correct).sum();
+    let peak = reports.iter().map(|r| r.reviews).max().unwrap_or(0);
+    let average = if reports.is_empty() {
+        0.0
+    } else {
+        total_reviews as f64 / reports.len() as f64
+    };
+
+    println!();
+    println!("Summary");
+    println!("  Average daily reviews: {:.1}", average);
+    println!("  Peak daily reviews:    {}", peak);
+    println!(
+        "  Review retention:      {}",
+        format_retention(total_correct, total_reviews)
+    );
+
+    let intervals: Vec<i32> = match ReviewItemsRepository::new(
+        &db.conn,
+        db.user_id(),
+        Box::new(|| db.get_current_time()),
+    )
+    .get_all()
+    {
+        Ok(items) => items.iter().map(|item| item.interval).collect(),
+        Err(e) => {
+            eprintln!("Error reading review items: {}", e);
+            return;
+        }
+    };
+
+    println!();
+    println!("Interval distribution ({} review items)", intervals.len());
+    let mut lower = 0;
+    for upper in INTERVAL_BUCKETS {
+        let count = intervals
+            .iter()
+            .filter(|&&i| i >= lower && i <= upper)
+            .count();
+        println!("  {:>3}-{:<3} days: {}", lower, upper, count);
+        lower = upper + 1;
+    }
+    let count = intervals.iter().filter(|&&i| i >= lower).count();
+    println!("  {:>3}+    days: {}", lower, count);
+}
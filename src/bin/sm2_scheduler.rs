@@ -46,6 +46,8 @@ fn main() {
         ease_factor: args.ease_factor,
         next_review_date: now,
         last_reviewed_date: None,
+        fsrs_stability: None,
+        fsrs_difficulty: None,
     };
 
     println!(
@@ -54,6 +56,8 @@ fn main() {
     );
 
     compute_and_print(&scheduler, &review_item, now, Quality::Grade0);
+    compute_and_print(&scheduler, &review_item, now, Quality::Grade1);
+    compute_and_print(&scheduler, &review_item, now, Quality::Grade2);
     compute_and_print(&scheduler, &review_item, now, Quality::Grade3);
     compute_and_print(&scheduler, &review_item, now, Quality::Grade4);
     compute_and_print(&scheduler, &review_item, now, Quality::Grade5);
@@ -65,7 +69,8 @@ fn compute_and_print(
     now: DateTime<Utc>,
     quality: Quality,
 ) {
-    let (reps, interval, ease, next_date) = review_scheduler.process_review(review_item, quality);
+    let (reps, interval, ease, next_date) =
+        review_scheduler.process_review(review_item, quality, now);
 
     let grade_description = QuizService::quality_to_string(quality);
 
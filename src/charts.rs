@@ -0,0 +1,194 @@
+use crate::database::analytics::{ChartRange, Trends};
+use chrono::{DateTime, NaiveTime, Utc};
+use eframe::egui;
+
+const CHART_HEIGHT: f32 = 140.0;
+const OPERATION_COLORS: [egui::Color32; 4] = [
+    egui::Color32::LIGHT_BLUE,
+    egui::Color32::GOLD,
+    egui::Color32::LIGHT_GREEN,
+    egui::Color32::LIGHT_RED,
+];
+
+/// One line of a chart; x values are Unix timestamps in seconds
+struct Series {
+    name: String,
+    color: egui::Color32,
+    points: Vec<[f64; 2]>,
+}
+
+/// Buttons to pick the chart range; returns true if the selection changed
+pub fn show_range_selector(ui: &mut egui::Ui, range: &mut ChartRange) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Range:");
+        for option in ChartRange::all() {
+            changed |= ui.selectable_value(range, option, option.label()).changed();
+        }
+    });
+    changed
+}
+
+/// Per-deck and weekly per-operation accuracy and time charts
+pub fn show_trends(ui: &mut egui::Ui, trends: &Trends) {
+    if trends.decks.is_empty() {
+        ui.label(format!(
+            "No completed decks in range ({}).",
+            trends.range.label()
+        ));
+        return;
+    }
+
+    let deck_x = |at: DateTime<Utc>| at.timestamp() as f64;
+    let deck_accuracy = Series {
+        name: "Deck accuracy".to_string(),
+        color: egui::Color32::LIGHT_GREEN,
+        points: trends
+            .decks
+            .iter()
+            .map(|point| [deck_x(point.completed_at), point.accuracy])
+            .collect(),
+    };
+    let deck_time = Series {
+        name: "Deck average time".to_string(),
+        color: egui::Color32::LIGHT_BLUE,
+        points: trends
+            .decks
+            .iter()
+            .map(|point| [deck_x(point.completed_at), point.average_time])
+            .collect(),
+    };
+    show_line_chart(ui, "Accuracy per deck (%)", &[deck_accuracy], Some(100.0));
+    show_line_chart(ui, "Average time per deck (s)", &[deck_time], None);
+
+    let week_x =
+        |week: chrono::NaiveDate| week.and_time(NaiveTime::MIN).and_utc().timestamp() as f64;
+    let mut weekly_accuracy = Vec::new();
+    let mut weekly_time = Vec::new();
+    for (index, (operation_type, points)) in trends.weekly.iter().enumerate() {
+        let color = OPERATION_COLORS[index % OPERATION_COLORS.len()];
+        weekly_accuracy.push(Series {
+            name: operation_type.clone(),
+            color,
+            points: points
+                .iter()
+                .map(|point| [week_x(point.week_start), point.accuracy])
+                .collect(),
+        });
+        weekly_time.push(Series {
+            name: operation_type.clone(),
+            color,
+            points: points
+                .iter()
+                .filter_map(|point| Some([week_x(point.week_start), point.average_time?]))
+                .collect(),
+        });
+    }
+    show_line_chart(
+        ui,
+        "Weekly accuracy by operation (%)",
+        &weekly_accuracy,
+        Some(100.0),
+    );
+    show_line_chart(
+        ui,
+        "Weekly average time by operation (s)",
+        &weekly_time,
+        None,
+    );
+}
+
+/// Draw a simple line chart; the y axis starts at zero and ends at `y_max` or the largest value
+fn show_line_chart(ui: &mut egui::Ui, title: &str, series: &[Series], y_max: Option<f64>) {
+    ui.add_space(8.0);
+    ui.label(egui::RichText::new(title).strong());
+
+    let all_points = || series.iter().flat_map(|s| s.points.iter());
+    let Some(x_min) = all_points().map(|p| p[0]).reduce(f64::min) else {
+        ui.label("No data");
+        return;
+    };
+    let x_max = all_points().map(|p| p[0]).fold(x_min, f64::max);
+    let y_max = y_max
+        .unwrap_or_else(|| all_points().map(|p| p[1]).fold(0.0, f64::max))
+        .max(f64::EPSILON);
+
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width(), CHART_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let frame = response.rect;
+    let plot = egui::Rect::from_min_max(
+        frame.min + egui::vec2(40.0, 6.0),
+        frame.max - egui::vec2(8.0, 18.0),
+    );
+    let text_color = ui.visuals().weak_text_color();
+    let grid_stroke = egui::Stroke::new(0.5, ui.visuals().widgets.noninteractive.bg_stroke.color);
+
+    // Horizontal grid lines at 0, 50% and 100% of the y range
+    for fraction in [0.0, 0.5, 1.0] {
+        let y = plot.bottom() - plot.height() * fraction;
+        painter.hline(plot.x_range(), y, grid_stroke);
+        painter.text(
+            egui::pos2(plot.left() - 4.0, y),
+            egui::Align2::RIGHT_CENTER,
+            format!("{:.0}", y_max * fraction as f64),
+            egui::FontId::proportional(11.0),
+            text_color,
+        );
+    }
+
+    // First and last date under the x axis
+    for (x, align) in [
+        (x_min, egui::Align2::LEFT_TOP),
+        (x_max, egui::Align2::RIGHT_TOP),
+    ] {
+        if let Some(date) = DateTime::from_timestamp(x as i64, 0) {
+            let anchor = if align == egui::Align2::LEFT_TOP {
+                plot.left_bottom()
+            } else {
+                plot.right_bottom()
+            };
+            painter.text(
+                anchor + egui::vec2(0.0, 2.0),
+                align,
+                date.format("%Y-%m-%d").to_string(),
+                egui::FontId::proportional(11.0),
+                text_color,
+            );
+        }
+    }
+
+    let to_screen = |point: &[f64; 2]| {
+        let x_fraction = if x_max > x_min {
+            (point[0] - x_min) / (x_max - x_min)
+        } else {
+            0.5
+        };
+        let y_fraction = (point[1] / y_max).clamp(0.0, 1.0);
+        egui::pos2(
+            plot.left() + plot.width() * x_fraction as f32,
+            plot.bottom() - plot.height() * y_fraction as f32,
+        )
+    };
+    for line in series {
+        let points: Vec<egui::Pos2> = line.points.iter().map(to_screen).collect();
+        if points.len() > 1 {
+            painter.add(egui::Shape::line(
+                points.clone(),
+                egui::Stroke::new(2.0, line.color),
+            ));
+        }
+        for point in points {
+            painter.circle_filled(point, 2.5, line.color);
+        }
+    }
+
+    if series.len() > 1 {
+        ui.horizontal(|ui| {
+            for line in series {
+                ui.label(egui::RichText::new(format!("— {}", line.name)).color(line.color));
+            }
+        });
+    }
+}
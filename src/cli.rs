@@ -1,9 +1,12 @@
+use crate::difficulty::DifficultyProfile;
+use crate::spaced_repetition::{PartialCreditPolicy, SchedulerKind};
+use crate::timing_estimator::TimingEstimator;
 use chrono::NaiveDate;
 use clap::Parser;
 use std::path::PathBuf;
 
 /// Mental math practice application using spaced repetition learning
-#[derive(Parser, Debug, Clone)]
+#[derive(Parser, Debug, Clone, Default)]
 #[command(name = "Memory Practice")]
 #[command(about = "Exercise mental math with spaced repetition", long_about = None)]
 #[command(version)]
@@ -23,6 +26,54 @@ pub struct Args {
         help = "Override current date (YYYY-MM-DD format)"
     )]
     pub override_date: Option<String>,
+
+    /// Difficulty profile for new questions, remembered in the database once selected
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        help = "Difficulty profile for new questions (mixed, single-digit, two-by-one, three-digit-carry, adaptive)"
+    )]
+    pub difficulty: Option<String>,
+
+    /// Spaced repetition algorithm, remembered in the database once selected
+    #[arg(
+        long,
+        value_name = "ALGORITHM",
+        help = "Spaced repetition scheduler (sm2, fsrs)"
+    )]
+    pub scheduler: Option<String>,
+
+    /// Treat swapped operands of addition and multiplication as the same fact
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help = "Review 8 × 7 against the schedule of 7 × 8 (true, false)"
+    )]
+    pub commutative_facts: Option<bool>,
+
+    /// How wrong answers are graded, remembered in the database once selected
+    #[arg(
+        long,
+        value_name = "POLICY",
+        help = "Credit for recognisable wrong answers (off, near-miss, lenient)"
+    )]
+    pub partial_credit: Option<String>,
+
+    /// How answer-time baselines treat outliers, remembered in the database once selected
+    #[arg(
+        long,
+        value_name = "ESTIMATOR",
+        help = "Answer-time baseline estimator (mean, mad[:CUTOFF], trimmed[:FRACTION])"
+    )]
+    pub timing_estimator: Option<String>,
+
+    /// Practice profile to use, created on first use; without it the GUI asks at startup
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Practice profile to use, created if it does not exist yet"
+    )]
+    pub user: Option<String>,
 }
 
 impl Args {
@@ -45,6 +96,64 @@ impl Args {
             None => Ok(None),
         }
     }
+
+    /// Validate the difficulty argument if provided
+    pub fn validate_difficulty(&self) -> Result<Option<DifficultyProfile>, String> {
+        match &self.difficulty {
+            Some(name) => DifficultyProfile::by_name(name).map(Some).ok_or_else(|| {
+                format!(
+                    "Unknown difficulty profile: '{}'. Expected one of: {}",
+                    name,
+                    DifficultyProfile::names().join(", ")
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Validate the scheduler argument if provided
+    pub fn validate_scheduler(&self) -> Result<Option<SchedulerKind>, String> {
+        match &self.scheduler {
+            Some(name) => SchedulerKind::from(name).map(Some).ok_or_else(|| {
+                format!("Unknown scheduler: '{}'. Expected one of: sm2, fsrs", name)
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Validate the partial credit argument if provided
+    pub fn validate_partial_credit(&self) -> Result<Option<PartialCreditPolicy>, String> {
+        match &self.partial_credit {
+            Some(name) => PartialCreditPolicy::from(name).map(Some).ok_or_else(|| {
+                format!(
+                    "Unknown partial credit policy: '{}'. Expected one of: off, near-miss, lenient",
+                    name
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Validate the timing estimator argument if provided
+    pub fn validate_timing_estimator(&self) -> Result<Option<TimingEstimator>, String> {
+        match &self.timing_estimator {
+            Some(name) => TimingEstimator::from(name).map(Some).ok_or_else(|| {
+                format!(
+                    "Unknown timing estimator: '{}'. Expected one of: mean, mad[:CUTOFF], trimmed[:FRACTION]",
+                    name
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Validate the user argument if provided, trimming surrounding whitespace
+    pub fn validate_user(&self) -> Result<Option<String>, String> {
+        match self.user.as_deref().map(str::trim) {
+            Some("") => Err("Profile name for --user must not be empty".to_string()),
+            name => Ok(name.map(str::to_string)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -57,6 +166,7 @@ mod tests {
             test: false,
             db_path: None,
             override_date: None,
+            ..Default::default()
         };
         assert!(!args.test);
         assert!(args.db_path.is_none());
@@ -69,6 +179,7 @@ mod tests {
             test: true,
             db_path: None,
             override_date: None,
+            ..Default::default()
         };
         assert!(args.test);
     }
@@ -79,6 +190,7 @@ mod tests {
             test: false,
             db_path: Some(PathBuf::from("/tmp/test.db")),
             override_date: None,
+            ..Default::default()
         };
         assert_eq!(
             args.db_path.as_deref(),
@@ -92,6 +204,7 @@ mod tests {
             test: false,
             db_path: None,
             override_date: Some("2024-01-15".to_string()),
+            ..Default::default()
         };
         assert_eq!(args.override_date, Some("2024-01-15".to_string()));
     }
@@ -102,6 +215,7 @@ mod tests {
             test: false,
             db_path: None,
             override_date: Some("2024-01-15".to_string()),
+            ..Default::default()
         };
         let result = args.validate_override_date();
         assert!(result.is_ok());
@@ -115,6 +229,7 @@ mod tests {
             test: false,
             db_path: None,
             override_date: Some("2024/01/15".to_string()),
+            ..Default::default()
         };
         let result = args.validate_override_date();
         assert!(result.is_err());
@@ -127,6 +242,7 @@ mod tests {
             test: false,
             db_path: None,
             override_date: Some("2024-13-01".to_string()),
+            ..Default::default()
         };
         let result = args.validate_override_date();
         assert!(result.is_err());
@@ -138,6 +254,7 @@ mod tests {
             test: false,
             db_path: None,
             override_date: None,
+            ..Default::default()
         };
         let result = args.validate_override_date();
         assert!(result.is_ok());
@@ -150,6 +267,7 @@ mod tests {
             test: true,
             db_path: Some(PathBuf::from("/tmp/test.db")),
             override_date: Some("2024-06-15".to_string()),
+            ..Default::default()
         };
         assert!(args.test);
         assert_eq!(
@@ -165,10 +283,118 @@ mod tests {
             test: true,
             db_path: Some(PathBuf::from("/tmp/test.db")),
             override_date: Some("2024-12-31".to_string()),
+            ..Default::default()
         };
         let result = args.validate_override_date();
         assert!(result.is_ok());
         let date = result.unwrap();
         assert_eq!(date, Some(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()));
     }
+
+    #[test]
+    fn test_validate_difficulty_valid() {
+        let args = Args {
+            difficulty: Some("single-digit".to_string()),
+            ..Default::default()
+        };
+        let profile = args.validate_difficulty().unwrap().unwrap();
+        assert_eq!(profile.name, "single-digit");
+    }
+
+    #[test]
+    fn test_validate_difficulty_unknown() {
+        let args = Args {
+            difficulty: Some("impossible".to_string()),
+            ..Default::default()
+        };
+        let result = args.validate_difficulty();
+        assert!(result.unwrap_err().contains("Unknown difficulty profile"));
+    }
+
+    #[test]
+    fn test_validate_difficulty_none() {
+        let args = Args::default();
+        assert_eq!(args.validate_difficulty().unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_scheduler() {
+        let mut args = Args {
+            scheduler: Some("fsrs".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.validate_scheduler().unwrap(),
+            Some(SchedulerKind::Fsrs)
+        );
+
+        args.scheduler = Some("anki".to_string());
+        assert!(
+            args.validate_scheduler()
+                .unwrap_err()
+                .contains("Unknown scheduler")
+        );
+
+        args.scheduler = None;
+        assert_eq!(args.validate_scheduler().unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_partial_credit() {
+        let mut args = Args {
+            partial_credit: Some("off".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.validate_partial_credit().unwrap(),
+            Some(PartialCreditPolicy::Off)
+        );
+
+        args.partial_credit = Some("half".to_string());
+        assert!(
+            args.validate_partial_credit()
+                .unwrap_err()
+                .contains("Unknown partial credit policy")
+        );
+
+        args.partial_credit = None;
+        assert_eq!(args.validate_partial_credit().unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_timing_estimator() {
+        let mut args = Args {
+            timing_estimator: Some("mad:3".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.validate_timing_estimator().unwrap(),
+            Some(TimingEstimator::Mad { cutoff: 3.0 })
+        );
+
+        args.timing_estimator = Some("trimmed:0.9".to_string());
+        assert!(
+            args.validate_timing_estimator()
+                .unwrap_err()
+                .contains("Unknown timing estimator")
+        );
+
+        args.timing_estimator = None;
+        assert_eq!(args.validate_timing_estimator().unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_user() {
+        let mut args = Args {
+            user: Some(" Alice ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(args.validate_user(), Ok(Some("Alice".to_string())));
+
+        args.user = Some("  ".to_string());
+        assert!(args.validate_user().is_err());
+
+        args.user = None;
+        assert_eq!(args.validate_user(), Ok(None));
+    }
 }
@@ -1,23 +1,43 @@
+use chrono::{DateTime, Duration, Utc};
 use rusqlite::Connection;
 use rusqlite::Result;
 use std::collections::HashMap;
 
-// SQL WHERE clause constants for template method filters
-const LAST_30_DAYS_WHERE: &str = "a.created_at >= datetime('now', '-30 days')";
+// SQL WHERE clause constants for template method filters; ?1 is the profile's user id
 const LAST_10_DECKS_WHERE: &str = r#"d.id IN (
     SELECT id FROM decks
-    WHERE status = 'completed'
+    WHERE status = 'completed' AND user_id = ?1
     ORDER BY completed_at DESC
     LIMIT 10
 )"#;
 
+/// WHERE clause selecting answers given at or after `since`
+pub(crate) fn since_where(since: DateTime<Utc>) -> String {
+    format!("a.created_at >= '{}'", since.to_rfc3339())
+}
+
+/// WHERE clause selecting answers given at or after `start` and before `end`
+pub(crate) fn between_where(start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    format!(
+        "a.created_at >= '{}' AND a.created_at < '{}'",
+        start.to_rfc3339(),
+        end.to_rfc3339()
+    )
+}
+
+/// WHERE clause selecting answers given in the 30 days before `now`
+pub(crate) fn last_30_days_where(now: DateTime<Utc>) -> String {
+    since_where(now - Duration::days(30))
+}
+
 pub struct AccuracyRepository<'a> {
     conn: &'a Connection,
+    user_id: i64,
 }
 
 impl<'a> AccuracyRepository<'a> {
-    pub fn new(conn: &'a Connection) -> Self {
-        AccuracyRepository { conn }
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        AccuracyRepository { conn, user_id }
     }
 
     /// Template method for computing accuracy statistics per operation type with custom WHERE clauses
@@ -34,7 +54,8 @@ impl<'a> AccuracyRepository<'a> {
             FROM answers a
             INNER JOIN operations o ON a.operation_id = o.id
             INNER JOIN decks d ON a.deck_id = d.id
-            WHERE d.status = 'completed'"#
+            WHERE d.status = 'completed'
+            AND d.user_id = ?1"#
             .to_string();
 
         if !additional_where.is_empty() {
@@ -50,7 +71,7 @@ impl<'a> AccuracyRepository<'a> {
 
         let mut stmt = self.conn.prepare(&query)?;
         let mut result = HashMap::new();
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map([self.user_id], |row| {
             let op_type: String = row.get(0)?;
             let correct_count: i64 = row.get(1)?;
             let total_count: i64 = row.get(2)?;
@@ -75,7 +96,8 @@ impl<'a> AccuracyRepository<'a> {
                 COUNT(a.id) * 100.0 as accuracy_percentage
             FROM answers a
             INNER JOIN decks d ON a.deck_id = d.id
-            WHERE d.status = 'completed'"#
+            WHERE d.status = 'completed'
+            AND d.user_id = ?1"#
             .to_string();
 
         if !additional_where.is_empty() {
@@ -84,7 +106,7 @@ impl<'a> AccuracyRepository<'a> {
         }
 
         let mut stmt = self.conn.prepare(&query)?;
-        let result = stmt.query_row([], |row| {
+        let result = stmt.query_row([self.user_id], |row| {
             let correct_count: i64 = row.get(0)?;
             let total_count: i64 = row.get(1)?;
             let accuracy: f64 = row.get(2)?;
@@ -106,16 +128,19 @@ impl<'a> AccuracyRepository<'a> {
         self.compute_total_accuracy_template("")
     }
 
-    /// Compute accuracy statistics for all operation types in the last 30 days
+    /// Compute accuracy statistics for all operation types in the 30 days before `now`
     /// Returns a map of operation_type -> (correct_count, total_count, accuracy_percentage)
-    pub fn all_operations_last_30_days(&self) -> Result<HashMap<String, (i64, i64, f64)>> {
-        self.compute_all_operations_template(LAST_30_DAYS_WHERE)
+    pub fn all_operations_last_30_days(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<HashMap<String, (i64, i64, f64)>> {
+        self.compute_all_operations_template(&last_30_days_where(now))
     }
 
-    /// Compute total accuracy for all operations in the last 30 days
+    /// Compute total accuracy for all operations in the 30 days before `now`
     /// Returns (correct_count, total_count, accuracy_percentage)
-    pub fn total_accuracy_last_30_days(&self) -> Result<(i64, i64, f64)> {
-        self.compute_total_accuracy_template(LAST_30_DAYS_WHERE)
+    pub fn total_accuracy_last_30_days(&self, now: DateTime<Utc>) -> Result<(i64, i64, f64)> {
+        self.compute_total_accuracy_template(&last_30_days_where(now))
     }
 
     /// Compute accuracy statistics for all operation types from the last 10 completed decks
@@ -129,6 +154,26 @@ impl<'a> AccuracyRepository<'a> {
     pub fn total_accuracy_last_10_decks(&self) -> Result<(i64, i64, f64)> {
         self.compute_total_accuracy_template(LAST_10_DECKS_WHERE)
     }
+
+    /// Compute accuracy statistics for all operation types from answers given at or after `since`
+    /// Returns a map of operation_type -> (correct_count, total_count, accuracy_percentage)
+    pub fn all_operations_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<HashMap<String, (i64, i64, f64)>> {
+        self.compute_all_operations_template(&since_where(since))
+    }
+
+    /// Compute accuracy statistics for all operation types from answers given at or after `start`
+    /// and before `end`
+    /// Returns a map of operation_type -> (correct_count, total_count, accuracy_percentage)
+    pub fn all_operations_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<HashMap<String, (i64, i64, f64)>> {
+        self.compute_all_operations_template(&between_where(start, end))
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +183,7 @@ mod tests {
     use crate::database::connection::init_connection;
     use crate::database::decks::DecksRepository;
     use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
 
     fn create_test_db() -> rusqlite::Connection {
         init_connection(":memory:").expect("Failed to create test database")
@@ -146,7 +192,7 @@ mod tests {
     #[test]
     fn test_compute_accuracy_all_operations_empty_database() {
         let conn = create_test_db();
-        let repo = AccuracyRepository::new(&conn);
+        let repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
         let result = repo.all_operations().unwrap();
         assert!(result.is_empty());
     }
@@ -155,9 +201,9 @@ mod tests {
     fn test_compute_accuracy_all_operations_single_type() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| chrono::Utc::now()));
-        let accuracy_repo = AccuracyRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -179,9 +225,9 @@ mod tests {
     fn test_compute_accuracy_all_operations_multiple_types() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| chrono::Utc::now()));
-        let accuracy_repo = AccuracyRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
 
@@ -223,13 +269,136 @@ mod tests {
         assert!((mult_accuracy - 50.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_compute_accuracy_includes_subtraction_and_division() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id1 = ops_repo.insert("SUBTRACT", 9, 4, 5, Some(deck_id)).unwrap();
+        answers_repo
+            .insert(op_id1, 5, true, 1.0, Some(deck_id))
+            .unwrap();
+        let op_id2 = ops_repo.insert("DIVIDE", 56, 8, 7, Some(deck_id)).unwrap();
+        answers_repo
+            .insert(op_id2, 6, false, 2.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        let result = accuracy_repo.all_operations().unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.get("SUBTRACT").unwrap().0, 1);
+        assert_eq!(result.get("DIVIDE").unwrap().0, 0);
+        assert_eq!(result.get("DIVIDE").unwrap().1, 1);
+    }
+
+    #[test]
+    fn test_last_30_days_is_relative_to_given_now() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
+
+        let answered_at = chrono::TimeZone::with_ymd_and_hms(&Utc, 2025, 1, 1, 12, 0, 0).unwrap();
+        let date_fn = move || answered_at;
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(date_fn));
+        let answers_repo =
+            AnswersRepository::new_with_date_provider(&conn, DEFAULT_USER_ID, &date_fn);
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        answers_repo
+            .insert(op_id, 5, true, 1.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        let within = answered_at + Duration::days(10);
+        assert_eq!(
+            accuracy_repo.total_accuracy_last_30_days(within).unwrap(),
+            (1, 1, 100.0)
+        );
+        assert_eq!(
+            accuracy_repo
+                .all_operations_last_30_days(within)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let after = answered_at + Duration::days(31);
+        assert!(
+            accuracy_repo
+                .all_operations_last_30_days(after)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_compute_accuracy_all_operations_since() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
+
+        let now = chrono::Utc::now();
+        let old = now - chrono::Duration::days(5);
+        let old_fn = move || old;
+        let old_answers =
+            AnswersRepository::new_with_date_provider(&conn, DEFAULT_USER_ID, &old_fn);
+        let new_answers = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        old_answers
+            .insert(op_id, 4, false, 1.0, Some(deck_id))
+            .unwrap();
+        new_answers
+            .insert(op_id, 5, true, 1.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        let result = accuracy_repo
+            .all_operations_since(now - chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(result.get("ADD"), Some(&(1, 1, 100.0)));
+    }
+
+    #[test]
+    fn test_compute_accuracy_all_operations_between() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
+
+        let now = chrono::Utc::now();
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        for (days_ago, is_correct) in [(10, false), (5, true), (0, false)] {
+            let when = now - Duration::days(days_ago);
+            let clock = move || when;
+            AnswersRepository::new_with_date_provider(&conn, DEFAULT_USER_ID, &clock)
+                .insert(op_id, 5, is_correct, 1.0, Some(deck_id))
+                .unwrap();
+        }
+        decks_repo.complete(deck_id).unwrap();
+
+        // The end of the range is exclusive
+        let result = accuracy_repo
+            .all_operations_between(now - Duration::days(7), now)
+            .unwrap();
+        assert_eq!(result.get("ADD"), Some(&(1, 1, 100.0)));
+    }
+
     #[test]
     fn test_compute_total_accuracy_single_deck() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| chrono::Utc::now()));
-        let accuracy_repo = AccuracyRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let accuracy_repo = AccuracyRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -0,0 +1,585 @@
+use crate::row_factories::parse_datetime_with_timezone;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use rusqlite::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Shown with every early-warning result
+pub const DECLINE_DISCLAIMER: &str = "This report is not a diagnosis. It only compares recent practice \
+with your own earlier practice. Slower or less accurate answers have many everyday causes \
+(sleep, stress, medication, distractions, a new device). If a warning persists, consider \
+discussing it with a health professional.";
+
+/// Thresholds for flagging a sustained deterioration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeclineCriteria {
+    /// Length of each rolling window compared against the baseline
+    pub window_days: i64,
+    /// Number of consecutive daily windows that must all deteriorate
+    pub sustained_days: i64,
+    /// Standard errors the window must lie away from the baseline
+    pub z_threshold: f64,
+    /// Baseline standard deviations the window's mean answer time must also have shifted by, so
+    /// that a tiny drift over many answers is not reported however significant it is
+    pub min_shift_sd: f64,
+    pub min_baseline_answers: i64,
+    pub min_window_answers: i64,
+}
+
+impl Default for DeclineCriteria {
+    fn default() -> Self {
+        Self {
+            window_days: 7,
+            sustained_days: 14,
+            z_threshold: 3.0,
+            min_shift_sd: 2.0,
+            min_baseline_answers: 30,
+            min_window_answers: 10,
+        }
+    }
+}
+
+impl DeclineCriteria {
+    /// Answers given before this instant form the baseline
+    pub fn baseline_end(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(self.window_days + self.sustained_days)
+    }
+}
+
+/// Accuracy and timing of correct answers over a set of answers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodSample {
+    pub correct: i64,
+    pub total: i64,
+    pub mean_time: Option<f64>,
+    /// Sample standard deviation of correct answer times
+    pub std_dev_time: Option<f64>,
+}
+
+impl PeriodSample {
+    fn from_answers<'a>(answers: impl Iterator<Item = &'a Answer>) -> Self {
+        let mut correct = 0;
+        let mut total = 0;
+        let mut times = Vec::new();
+        for answer in answers {
+            total += 1;
+            if answer.is_correct {
+                correct += 1;
+                times.extend(answer.time_spent);
+            }
+        }
+        let mean_time = (!times.is_empty()).then(|| times.iter().sum::<f64>() / times.len() as f64);
+        let std_dev_time = mean_time.filter(|_| times.len() > 1).map(|mean| {
+            let variance =
+                times.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (times.len() - 1) as f64;
+            variance.sqrt()
+        });
+        Self {
+            correct,
+            total,
+            mean_time,
+            std_dev_time,
+        }
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.correct as f64 / self.total as f64 * 100.0)
+    }
+}
+
+/// Comparison of one rolling window with the baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowComparison {
+    pub window_end: DateTime<Utc>,
+    pub sample: PeriodSample,
+    /// Shift of the mean correct answer time in baseline standard deviations
+    pub time_shift_sd: Option<f64>,
+    /// Standard errors by which the window mean time exceeds the baseline (positive = slower)
+    pub time_z: Option<f64>,
+    /// Two-proportion z score of the window accuracy against the baseline (negative = worse)
+    pub accuracy_z: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclineStatus {
+    /// Not enough baseline or recent answers to compare
+    InsufficientData,
+    NoSustainedChange,
+    /// Deterioration held for every window of the sustained period
+    SustainedDecline,
+}
+
+/// Early-warning result for one operation type
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationDecline {
+    pub operation_type: String,
+    pub baseline: PeriodSample,
+    /// Most recent window first
+    pub windows: Vec<WindowComparison>,
+    /// Consecutive most recent windows with a significant slowdown of at least `min_shift_sd`
+    pub slowdown_days: i64,
+    /// Consecutive most recent windows with a significant accuracy drop
+    pub accuracy_drop_days: i64,
+    pub status: DeclineStatus,
+}
+
+impl OperationDecline {
+    pub fn sustained_slowdown(&self, criteria: &DeclineCriteria) -> bool {
+        self.slowdown_days >= criteria.sustained_days
+    }
+
+    pub fn sustained_accuracy_drop(&self, criteria: &DeclineCriteria) -> bool {
+        self.accuracy_drop_days >= criteria.sustained_days
+    }
+}
+
+/// Early-warning results for every operation type
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclineReport {
+    pub generated_at: DateTime<Utc>,
+    pub criteria: DeclineCriteria,
+    pub operations: Vec<OperationDecline>,
+}
+
+impl DeclineReport {
+    pub fn has_warnings(&self) -> bool {
+        self.operations
+            .iter()
+            .any(|op| op.status == DeclineStatus::SustainedDecline)
+    }
+
+    /// Plain-text version of the report, suitable for saving or sharing
+    pub fn to_text(&self) -> String {
+        let criteria = &self.criteria;
+        let mut text = String::new();
+        let _ = writeln!(text, "Early-Warning Report");
+        let _ = writeln!(text, "====================");
+        let _ = writeln!(
+            text,
+            "Generated: {}",
+            self.generated_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        let _ = writeln!(
+            text,
+            "Baseline: answers before {}; compared with {}-day rolling windows over the last {} days",
+            criteria.baseline_end(self.generated_at).format("%Y-%m-%d"),
+            criteria.window_days,
+            criteria.sustained_days
+        );
+        let _ = writeln!(text);
+        let _ = writeln!(text, "{}", DECLINE_DISCLAIMER);
+
+        for op in &self.operations {
+            let _ = writeln!(text);
+            let _ = writeln!(text, "Operation Type: {}", op.operation_type);
+            let _ = writeln!(text, "  Baseline: {}", describe_sample(&op.baseline));
+            match op.windows.first() {
+                Some(latest) => {
+                    let _ = writeln!(
+                        text,
+                        "  Last {} days: {}",
+                        criteria.window_days,
+                        describe_sample(&latest.sample)
+                    );
+                    if let (Some(shift), Some(z)) = (latest.time_shift_sd, latest.time_z) {
+                        let _ = writeln!(
+                            text,
+                            "  Response time: {:+.2} SD from baseline (z = {:.1}), slower on {} of {} days",
+                            shift, z, op.slowdown_days, criteria.sustained_days
+                        );
+                    }
+                    if let Some(z) = latest.accuracy_z {
+                        let _ = writeln!(
+                            text,
+                            "  Accuracy: z = {:.1}, lower on {} of {} days",
+                            z, op.accuracy_drop_days, criteria.sustained_days
+                        );
+                    }
+                }
+                None => {
+                    let _ = writeln!(text, "  Last {} days: no answers", criteria.window_days);
+                }
+            }
+            let _ = writeln!(text, "  Result: {}", describe_status(op, criteria));
+        }
+        text
+    }
+}
+
+/// One-line description of the outcome for an operation type
+pub fn describe_status(op: &OperationDecline, criteria: &DeclineCriteria) -> String {
+    match op.status {
+        DeclineStatus::InsufficientData => format!(
+            "Not enough data yet (needs {} baseline and {} recent answers)",
+            criteria.min_baseline_answers, criteria.min_window_answers
+        ),
+        DeclineStatus::NoSustainedChange => "No sustained change".to_string(),
+        DeclineStatus::SustainedDecline => {
+            let mut signals = Vec::new();
+            if op.sustained_slowdown(criteria) {
+                signals.push("slower answers");
+            }
+            if op.sustained_accuracy_drop(criteria) {
+                signals.push("lower accuracy");
+            }
+            format!(
+                "WARNING: sustained {} for at least {} days",
+                signals.join(" and "),
+                criteria.sustained_days
+            )
+        }
+    }
+}
+
+fn describe_sample(sample: &PeriodSample) -> String {
+    let Some(accuracy) = sample.accuracy() else {
+        return "no answers".to_string();
+    };
+    let timing = match (sample.mean_time, sample.std_dev_time) {
+        (Some(mean), Some(sd)) => format!(", {:.2}s ± {:.2}s", mean, sd),
+        (Some(mean), None) => format!(", {:.2}s", mean),
+        _ => String::new(),
+    };
+    format!(
+        "{} answers, {:.1}% correct{}",
+        sample.total, accuracy, timing
+    )
+}
+
+struct Answer {
+    created_at: DateTime<Utc>,
+    is_correct: bool,
+    /// None when the answer's timing was interrupted
+    time_spent: Option<f64>,
+}
+
+pub struct DeclineRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> DeclineRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        DeclineRepository { conn, user_id }
+    }
+
+    /// Compare rolling windows ending at `now` with the baseline for every operation type
+    /// Only answers from completed decks are considered
+    pub fn report(&self, now: DateTime<Utc>, criteria: DeclineCriteria) -> Result<DeclineReport> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT o.operation_type, a.created_at, a.is_correct,
+                CASE WHEN a.timing_interrupted = 0 THEN a.time_spent_seconds END
+            FROM answers a
+            INNER JOIN operations o ON a.operation_id = o.id
+            INNER JOIN decks d ON a.deck_id = d.id
+            WHERE d.status = 'completed'
+            AND d.user_id = ?1"#,
+        )?;
+        let rows = stmt.query_map([self.user_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Answer {
+                    created_at: parse_datetime_with_timezone(&row.get::<_, String>(1)?),
+                    is_correct: row.get::<_, i32>(2)? != 0,
+                    time_spent: row.get(3)?,
+                },
+            ))
+        })?;
+
+        let mut by_operation: BTreeMap<String, Vec<Answer>> = BTreeMap::new();
+        for row in rows {
+            let (operation_type, answer) = row?;
+            if answer.created_at <= now {
+                by_operation.entry(operation_type).or_default().push(answer);
+            }
+        }
+
+        let operations = by_operation
+            .into_iter()
+            .map(|(operation_type, answers)| {
+                evaluate_operation(operation_type, &answers, now, &criteria)
+            })
+            .collect();
+
+        Ok(DeclineReport {
+            generated_at: now,
+            criteria,
+            operations,
+        })
+    }
+}
+
+fn evaluate_operation(
+    operation_type: String,
+    answers: &[Answer],
+    now: DateTime<Utc>,
+    criteria: &DeclineCriteria,
+) -> OperationDecline {
+    let baseline_end = criteria.baseline_end(now);
+    let baseline =
+        PeriodSample::from_answers(answers.iter().filter(|a| a.created_at < baseline_end));
+
+    let windows: Vec<WindowComparison> = (0..criteria.sustained_days)
+        .map(|days_ago| {
+            let window_end = now - Duration::days(days_ago);
+            let window_start = window_end - Duration::days(criteria.window_days);
+            let sample = PeriodSample::from_answers(
+                answers
+                    .iter()
+                    .filter(|a| a.created_at > window_start && a.created_at <= window_end),
+            );
+            compare(window_end, &baseline, sample, criteria)
+        })
+        .collect();
+
+    let run = |flagged: &dyn Fn(&WindowComparison) -> bool| {
+        windows.iter().take_while(|w| flagged(w)).count() as i64
+    };
+    let threshold = criteria.z_threshold;
+    let slowdown_days = run(&|w| {
+        w.time_z.is_some_and(|z| z >= threshold)
+            && w.time_shift_sd
+                .is_some_and(|shift| shift >= criteria.min_shift_sd)
+    });
+    let accuracy_drop_days = run(&|w| w.accuracy_z.is_some_and(|z| z <= -threshold));
+
+    let enough_data = baseline.total >= criteria.min_baseline_answers
+        && windows
+            .first()
+            .is_some_and(|w| w.sample.total >= criteria.min_window_answers);
+    let status = if slowdown_days >= criteria.sustained_days
+        || accuracy_drop_days >= criteria.sustained_days
+    {
+        DeclineStatus::SustainedDecline
+    } else if !enough_data {
+        DeclineStatus::InsufficientData
+    } else {
+        DeclineStatus::NoSustainedChange
+    };
+
+    OperationDecline {
+        operation_type,
+        baseline,
+        windows,
+        slowdown_days,
+        accuracy_drop_days,
+        status,
+    }
+}
+
+fn compare(
+    window_end: DateTime<Utc>,
+    baseline: &PeriodSample,
+    sample: PeriodSample,
+    criteria: &DeclineCriteria,
+) -> WindowComparison {
+    let comparable = baseline.total >= criteria.min_baseline_answers
+        && sample.total >= criteria.min_window_answers;
+
+    let mut time_shift_sd = None;
+    let mut time_z = None;
+    if comparable
+        && let (Some(base_mean), Some(base_sd), Some(mean)) =
+            (baseline.mean_time, baseline.std_dev_time, sample.mean_time)
+        && base_sd > 0.0
+    {
+        time_shift_sd = Some((mean - base_mean) / base_sd);
+        time_z = Some((mean - base_mean) / (base_sd / (sample.correct as f64).sqrt()));
+    }
+
+    let mut accuracy_z = None;
+    if comparable {
+        let (c1, n1) = (baseline.correct as f64, baseline.total as f64);
+        let (c2, n2) = (sample.correct as f64, sample.total as f64);
+        let pooled = (c1 + c2) / (n1 + n2);
+        let standard_error = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+        if standard_error > 0.0 {
+            accuracy_z = Some((c2 / n2 - c1 / n1) / standard_error);
+        }
+    }
+
+    WindowComparison {
+        window_end,
+        sample,
+        time_shift_sd,
+        time_z,
+        accuracy_z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::AnswersRepository;
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use chrono::TimeZone;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    /// Record one completed deck of five answers on each of the given days before `now`
+    fn practice_daily(
+        conn: &Connection,
+        now: DateTime<Utc>,
+        days_ago: std::ops::RangeInclusive<i64>,
+        answer: impl Fn(i64, i64) -> (bool, f64),
+    ) {
+        let ops_repo = OperationsRepository::new(conn);
+        let op_id = match ops_repo.find("ADD", 2, 3, false).unwrap() {
+            Some(op) => op.id,
+            None => ops_repo.insert("ADD", 2, 3, 5, None).unwrap(),
+        };
+        for day in days_ago.rev() {
+            let when = now - Duration::days(day) - Duration::hours(1);
+            let clock = move || when;
+            let decks_repo = DecksRepository::new(conn, DEFAULT_USER_ID, Box::new(clock));
+            let answers_repo =
+                AnswersRepository::new_with_date_provider(conn, DEFAULT_USER_ID, &clock);
+            let deck_id = decks_repo.create().unwrap();
+            for i in 0..5 {
+                let (is_correct, time) = answer(day, i);
+                answers_repo
+                    .insert(op_id, 5, is_correct, time, Some(deck_id))
+                    .unwrap();
+            }
+            decks_repo.complete(deck_id).unwrap();
+        }
+    }
+
+    fn steady(_day: i64, i: i64) -> (bool, f64) {
+        (true, 2.0 + (i % 3) as f64 * 0.2)
+    }
+
+    #[test]
+    fn test_no_answers_gives_empty_report() {
+        let conn = create_test_db();
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(Utc::now(), DeclineCriteria::default())
+            .unwrap();
+        assert!(report.operations.is_empty());
+        assert!(!report.has_warnings());
+        assert!(report.to_text().contains(DECLINE_DISCLAIMER));
+    }
+
+    #[test]
+    fn test_short_history_is_insufficient() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        practice_daily(&conn, now, 0..=10, steady);
+
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now, DeclineCriteria::default())
+            .unwrap();
+        assert_eq!(report.operations[0].status, DeclineStatus::InsufficientData);
+    }
+
+    #[test]
+    fn test_stable_practice_is_not_flagged() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        practice_daily(&conn, now, 0..=60, steady);
+
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now, DeclineCriteria::default())
+            .unwrap();
+        let add = &report.operations[0];
+        assert_eq!(add.status, DeclineStatus::NoSustainedChange);
+        assert_eq!(add.slowdown_days, 0);
+        assert_eq!(add.windows.len(), 14);
+        assert!(!report.has_warnings());
+    }
+
+    #[test]
+    fn test_sustained_slowdown_is_flagged() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        // Answers slow down from 25 days ago onwards
+        practice_daily(&conn, now, 0..=60, |day, i| {
+            let (is_correct, time) = steady(day, i);
+            (is_correct, if day <= 25 { time + 2.0 } else { time })
+        });
+
+        let criteria = DeclineCriteria::default();
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now, criteria)
+            .unwrap();
+        let add = &report.operations[0];
+        assert_eq!(add.status, DeclineStatus::SustainedDecline);
+        assert!(add.sustained_slowdown(&criteria));
+        assert!(!add.sustained_accuracy_drop(&criteria));
+        assert!(add.windows[0].time_shift_sd.unwrap() > 2.0);
+        assert!(report.has_warnings());
+        assert!(
+            report
+                .to_text()
+                .contains("WARNING: sustained slower answers")
+        );
+    }
+
+    #[test]
+    fn test_short_slowdown_is_not_sustained() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        // Only the last 5 days are slow
+        practice_daily(&conn, now, 0..=60, |day, i| {
+            let (is_correct, time) = steady(day, i);
+            (is_correct, if day <= 4 { time + 2.0 } else { time })
+        });
+
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now, DeclineCriteria::default())
+            .unwrap();
+        let add = &report.operations[0];
+        assert_eq!(add.status, DeclineStatus::NoSustainedChange);
+        assert!(add.slowdown_days > 0 && add.slowdown_days < 14);
+    }
+
+    #[test]
+    fn test_small_shift_over_many_answers_is_not_flagged() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        // A drift of a fraction of a standard deviation, from before the compared windows
+        practice_daily(&conn, now, 0..=120, |day, i| {
+            let (is_correct, time) = steady(day, i);
+            (is_correct, if day <= 44 { time + 0.05 } else { time })
+        });
+
+        let criteria = DeclineCriteria {
+            window_days: 30,
+            ..DeclineCriteria::default()
+        };
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now, criteria)
+            .unwrap();
+        let add = &report.operations[0];
+        // Statistically significant with this many answers, but far too small to matter
+        assert!(add.windows[0].time_z.unwrap() >= criteria.z_threshold);
+        assert!(add.windows[0].time_shift_sd.unwrap() < 0.5);
+        assert_eq!(add.slowdown_days, 0);
+        assert_eq!(add.status, DeclineStatus::NoSustainedChange);
+    }
+
+    #[test]
+    fn test_sustained_accuracy_drop_is_flagged() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        // From 25 days ago, three answers in five are wrong
+        practice_daily(&conn, now, 0..=60, |day, i| {
+            let (_, time) = steady(day, i);
+            (day > 25 || i < 2, time)
+        });
+
+        let criteria = DeclineCriteria::default();
+        let report = DeclineRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now, criteria)
+            .unwrap();
+        let add = &report.operations[0];
+        assert!(add.sustained_accuracy_drop(&criteria));
+        assert!(report.to_text().contains("lower accuracy"));
+    }
+}
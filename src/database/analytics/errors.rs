@@ -0,0 +1,232 @@
+use super::accuracy::{between_where, last_30_days_where};
+use crate::error_analysis::{ErrorKind, classify};
+use crate::operations::{Operation, OperationType};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use rusqlite::Result;
+use std::collections::{BTreeMap, HashMap};
+
+/// Wrong answers counted by likely cause
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorBreakdown {
+    /// Per operation type, most frequent cause first
+    pub by_operation: BTreeMap<String, Vec<(ErrorKind, i64)>>,
+}
+
+impl ErrorBreakdown {
+    /// Causes over all operation types, most frequent first
+    pub fn totals(&self) -> Vec<(ErrorKind, i64)> {
+        let mut totals: HashMap<ErrorKind, i64> = HashMap::new();
+        for (kind, count) in self.by_operation.values().flatten() {
+            *totals.entry(*kind).or_default() += count;
+        }
+        sorted_by_count(totals.into_iter().collect())
+    }
+
+    pub fn for_operation(&self, operation_type: &str) -> &[(ErrorKind, i64)] {
+        self.by_operation
+            .get(operation_type)
+            .map_or(&[], |counts| counts.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_operation.is_empty()
+    }
+}
+
+fn sorted_by_count(mut counts: Vec<(ErrorKind, i64)>) -> Vec<(ErrorKind, i64)> {
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts
+}
+
+pub struct ErrorsRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> ErrorsRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        ErrorsRepository { conn, user_id }
+    }
+
+    /// Template method for counting wrong answers by cause with custom WHERE clauses
+    ///
+    /// Wrong answers stored without a label, i.e. before mistakes were classified, are
+    /// classified here rather than rewritten, so reading statistics never writes.
+    fn compute_breakdown_template(&self, additional_where: &str) -> Result<ErrorBreakdown> {
+        let mut query = r#"SELECT o.operation_type, a.error_kind, o.operand1, o.operand2,
+                a.user_answer, COUNT(a.id)
+            FROM answers a
+            INNER JOIN operations o ON a.operation_id = o.id
+            INNER JOIN decks d ON a.deck_id = d.id
+            WHERE d.status = 'completed'
+            AND d.user_id = ?1
+            AND a.is_correct = 0"#
+            .to_string();
+
+        if !additional_where.is_empty() {
+            query.push_str("\n            AND ");
+            query.push_str(additional_where);
+        }
+
+        query.push_str(
+            r#"
+            GROUP BY o.operation_type, a.error_kind, o.operand1, o.operand2, a.user_answer"#,
+        );
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map([self.user_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i32>(2)?,
+                row.get::<_, i32>(3)?,
+                row.get::<_, i32>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+
+        let mut counts: BTreeMap<String, HashMap<ErrorKind, i64>> = BTreeMap::new();
+        for row in rows {
+            let (operation_type, label, operand1, operand2, user_answer, count) = row?;
+            let kind = match label {
+                // Labels written by a newer version are skipped rather than failing the query
+                Some(label) => ErrorKind::from(&label),
+                None => OperationType::from_str(&operation_type)
+                    .and_then(|t| classify(&Operation::new(t, operand1, operand2), user_answer)),
+            };
+            if let Some(kind) = kind {
+                *counts
+                    .entry(operation_type)
+                    .or_default()
+                    .entry(kind)
+                    .or_default() += count;
+            }
+        }
+
+        let by_operation = counts
+            .into_iter()
+            .map(|(operation_type, counts)| {
+                (
+                    operation_type,
+                    sorted_by_count(counts.into_iter().collect()),
+                )
+            })
+            .collect();
+        Ok(ErrorBreakdown { by_operation })
+    }
+
+    /// Count wrong answers by likely cause per operation type (global)
+    pub fn all_operations(&self) -> Result<ErrorBreakdown> {
+        self.compute_breakdown_template("")
+    }
+
+    /// Count wrong answers by likely cause per operation type in the 30 days before `now`
+    pub fn all_operations_last_30_days(&self, now: DateTime<Utc>) -> Result<ErrorBreakdown> {
+        self.compute_breakdown_template(&last_30_days_where(now))
+    }
+
+    /// Count wrong answers by likely cause per operation type from answers given at or after
+    /// `start` and before `end`
+    pub fn all_operations_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<ErrorBreakdown> {
+        self.compute_breakdown_template(&between_where(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::{AnswersRepository, NewAnswer};
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use crate::error_analysis::classify;
+    use crate::operations::{Operation, OperationType};
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    #[test]
+    fn test_breakdown_counts_causes_per_operation() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+
+        let deck_id = decks_repo.create().unwrap();
+        let answers = [
+            (OperationType::Multiplication, 7, 8, 49),
+            (OperationType::Multiplication, 7, 8, 64),
+            (OperationType::Multiplication, 7, 8, 57),
+            (OperationType::Multiplication, 7, 8, 56),
+            (OperationType::Addition, 27, 15, 32),
+        ];
+        for (operation_type, a, b, answer) in answers {
+            let operation = Operation::new(operation_type, a, b);
+            let op_id = match ops_repo
+                .find(operation.operation_type.as_str(), a, b, false)
+                .unwrap()
+            {
+                Some(op) => op.id,
+                None => ops_repo
+                    .insert(
+                        operation.operation_type.as_str(),
+                        a,
+                        b,
+                        operation.result,
+                        None,
+                    )
+                    .unwrap(),
+            };
+            answers_repo
+                .insert_answer(&NewAnswer {
+                    operation_id: op_id,
+                    user_answer: answer,
+                    is_correct: answer == operation.result,
+                    time_spent_seconds: 2.0,
+                    deck_id: Some(deck_id),
+                    error_kind: classify(&operation, answer),
+                    ..NewAnswer::default()
+                })
+                .unwrap();
+        }
+
+        // Stored before mistakes were classified
+        let op_id = ops_repo.find("MULTIPLY", 7, 8, false).unwrap().unwrap().id;
+        answers_repo
+            .insert(op_id, 48, false, 2.0, Some(deck_id))
+            .unwrap();
+
+        let repo = ErrorsRepository::new(&conn, DEFAULT_USER_ID);
+        // Answers of decks still in progress are not counted
+        assert!(repo.all_operations().unwrap().is_empty());
+
+        decks_repo.complete(deck_id).unwrap();
+        let breakdown = repo.all_operations().unwrap();
+        assert_eq!(
+            breakdown.for_operation("MULTIPLY"),
+            &[(ErrorKind::TableNeighbour, 3), (ErrorKind::OffByOne, 1)]
+        );
+        assert_eq!(
+            breakdown.for_operation("ADD"),
+            &[(ErrorKind::DroppedCarry, 1)]
+        );
+        assert_eq!(breakdown.totals()[0], (ErrorKind::TableNeighbour, 3));
+        // Counting does not write the label
+        assert_eq!(
+            AnswersRepository::new(&conn, DEFAULT_USER_ID)
+                .get(6)
+                .unwrap()
+                .unwrap()
+                .error_kind,
+            None
+        );
+        assert!(breakdown.for_operation("DIVIDE").is_empty());
+    }
+}
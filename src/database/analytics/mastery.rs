@@ -0,0 +1,299 @@
+use crate::timing_estimator::median;
+use rusqlite::Connection;
+use rusqlite::Result;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Accuracy (percent) from which a fact is no longer considered a struggle
+const LEARNING_ACCURACY: f64 = 70.0;
+/// Accuracy (percent) required for a fact to count as mastered
+const MASTERED_ACCURACY: f64 = 90.0;
+/// Review interval (days) required for a fact to count as mastered
+const MASTERED_INTERVAL: i32 = 7;
+
+/// How well a single fact is known, used to color heatmap cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MasteryLevel {
+    /// Never answered in a completed deck
+    Unseen,
+    Struggling,
+    Learning,
+    Mastered,
+}
+
+/// Statistics for one operand pair of the grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasteryCell {
+    pub operand1: i32,
+    pub operand2: i32,
+    pub correct: i64,
+    pub total: i64,
+    /// Median time of correct answers, in seconds
+    pub median_time: Option<f64>,
+    /// Current review interval in days (None if the fact has no review item)
+    pub interval: Option<i32>,
+}
+
+impl MasteryCell {
+    fn new(operand1: i32, operand2: i32) -> Self {
+        Self {
+            operand1,
+            operand2,
+            correct: 0,
+            total: 0,
+            median_time: None,
+            interval: None,
+        }
+    }
+
+    /// Accuracy in percent (None if never answered)
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.correct as f64 / self.total as f64 * 100.0)
+    }
+
+    pub fn level(&self) -> MasteryLevel {
+        match self.accuracy() {
+            None => MasteryLevel::Unseen,
+            Some(accuracy)
+                if accuracy >= MASTERED_ACCURACY
+                    && self.interval.unwrap_or(0) >= MASTERED_INTERVAL =>
+            {
+                MasteryLevel::Mastered
+            }
+            Some(accuracy) if accuracy >= LEARNING_ACCURACY => MasteryLevel::Learning,
+            Some(_) => MasteryLevel::Struggling,
+        }
+    }
+}
+
+/// Per-fact statistics for every operand pair in a rectangular grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasteryGrid {
+    pub operation_type: String,
+    pub rows: RangeInclusive<i32>,
+    pub columns: RangeInclusive<i32>,
+    /// Cells in row-major order
+    pub cells: Vec<MasteryCell>,
+}
+
+impl MasteryGrid {
+    /// Cell for `operand1` (row) and `operand2` (column), if inside the grid
+    pub fn get(&self, operand1: i32, operand2: i32) -> Option<&MasteryCell> {
+        if !self.rows.contains(&operand1) || !self.columns.contains(&operand2) {
+            return None;
+        }
+        let width = self.columns.end() - self.columns.start() + 1;
+        let index = (operand1 - self.rows.start()) * width + (operand2 - self.columns.start());
+        self.cells.get(index as usize)
+    }
+}
+
+pub struct MasteryRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> MasteryRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        MasteryRepository { conn, user_id }
+    }
+
+    /// Compute accuracy, median time and current interval for every operand pair of the grid
+    /// Only answers from completed decks are counted
+    pub fn grid(
+        &self,
+        operation_type: &str,
+        rows: RangeInclusive<i32>,
+        columns: RangeInclusive<i32>,
+    ) -> Result<MasteryGrid> {
+        let bounds = (
+            operation_type,
+            *rows.start(),
+            *rows.end(),
+            *columns.start(),
+            *columns.end(),
+            self.user_id,
+        );
+
+        let mut answers: HashMap<(i32, i32), (i64, i64, Vec<f64>)> = HashMap::new();
+        let mut stmt = self.conn.prepare(
+            r#"SELECT o.operand1, o.operand2, a.is_correct, a.time_spent_seconds, a.timing_interrupted
+            FROM answers a
+            INNER JOIN operations o ON a.operation_id = o.id
+            INNER JOIN decks d ON a.deck_id = d.id
+            WHERE d.status = 'completed'
+            AND d.user_id = ?6
+            AND o.operation_type = ?1
+            AND o.operand1 BETWEEN ?2 AND ?3
+            AND o.operand2 BETWEEN ?4 AND ?5"#,
+        )?;
+        let rows_iter = stmt.query_map(bounds, |row| {
+            Ok((
+                (row.get::<_, i32>(0)?, row.get::<_, i32>(1)?),
+                row.get::<_, i32>(2)? != 0,
+                row.get::<_, f64>(3)?,
+                row.get::<_, bool>(4)?,
+            ))
+        })?;
+        for row in rows_iter {
+            let (pair, is_correct, time_spent, timing_interrupted) = row?;
+            let entry = answers.entry(pair).or_default();
+            entry.1 += 1;
+            if is_correct {
+                entry.0 += 1;
+                if !timing_interrupted {
+                    entry.2.push(time_spent);
+                }
+            }
+        }
+
+        let mut intervals: HashMap<(i32, i32), i32> = HashMap::new();
+        let mut stmt = self.conn.prepare(
+            r#"SELECT o.operand1, o.operand2, r.interval
+            FROM review_items r
+            INNER JOIN operations o ON r.operation_id = o.id
+            WHERE r.user_id = ?6
+            AND o.operation_type = ?1
+            AND o.operand1 BETWEEN ?2 AND ?3
+            AND o.operand2 BETWEEN ?4 AND ?5"#,
+        )?;
+        let rows_iter = stmt.query_map(bounds, |row| {
+            Ok(((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?), row.get(2)?))
+        })?;
+        for row in rows_iter {
+            let (pair, interval) = row?;
+            intervals.insert(pair, interval);
+        }
+
+        let mut cells = Vec::new();
+        for operand1 in rows.clone() {
+            for operand2 in columns.clone() {
+                let mut cell = MasteryCell::new(operand1, operand2);
+                if let Some((correct, total, times)) = answers.remove(&(operand1, operand2)) {
+                    cell.correct = correct;
+                    cell.total = total;
+                    cell.median_time = median(&times);
+                }
+                cell.interval = intervals.get(&(operand1, operand2)).copied();
+                cells.push(cell);
+            }
+        }
+
+        Ok(MasteryGrid {
+            operation_type: operation_type.to_string(),
+            rows,
+            columns,
+            cells,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::AnswersRepository;
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::review_items::ReviewItemsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+
+    fn create_test_db() -> rusqlite::Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    #[test]
+    fn test_empty_grid_has_every_cell_unseen() {
+        let conn = create_test_db();
+        let grid = MasteryRepository::new(&conn, DEFAULT_USER_ID)
+            .grid("MULTIPLY", 1..=12, 1..=12)
+            .unwrap();
+
+        assert_eq!(grid.cells.len(), 144);
+        assert!(
+            grid.cells
+                .iter()
+                .all(|cell| cell.level() == MasteryLevel::Unseen)
+        );
+        assert_eq!(grid.get(7, 8).unwrap().operand2, 8);
+        assert!(grid.get(13, 1).is_none());
+    }
+
+    #[test]
+    fn test_grid_cell_statistics() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let review_repo =
+            ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo
+            .insert("MULTIPLY", 7, 8, 56, Some(deck_id))
+            .unwrap();
+        for (answer, time) in [(56, 2.0), (56, 4.0), (54, 1.0), (56, 3.0)] {
+            answers_repo
+                .insert(op_id, answer, answer == 56, time, Some(deck_id))
+                .unwrap();
+        }
+        // Outside the grid
+        let outside = ops_repo
+            .insert("MULTIPLY", 20, 3, 60, Some(deck_id))
+            .unwrap();
+        answers_repo
+            .insert(outside, 60, true, 1.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        review_repo.insert(op_id, chrono::Utc::now()).unwrap();
+        let mut item = review_repo.get(op_id).unwrap().unwrap();
+        item.interval = 6;
+        review_repo.update(&item).unwrap();
+
+        let grid = MasteryRepository::new(&conn, DEFAULT_USER_ID)
+            .grid("MULTIPLY", 1..=12, 1..=12)
+            .unwrap();
+        let cell = grid.get(7, 8).unwrap();
+        assert_eq!((cell.correct, cell.total), (3, 4));
+        assert_eq!(cell.accuracy(), Some(75.0));
+        assert_eq!(cell.median_time, Some(3.0));
+        assert_eq!(cell.interval, Some(6));
+        assert_eq!(cell.level(), MasteryLevel::Learning);
+        assert_eq!(grid.get(8, 7).unwrap().level(), MasteryLevel::Unseen);
+        assert_eq!(grid.cells.iter().map(|c| c.total).sum::<i64>(), 4);
+    }
+
+    #[test]
+    fn test_incomplete_decks_are_ignored() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo
+            .insert("MULTIPLY", 3, 4, 12, Some(deck_id))
+            .unwrap();
+        answers_repo
+            .insert(op_id, 12, true, 1.0, Some(deck_id))
+            .unwrap();
+
+        let grid = MasteryRepository::new(&conn, DEFAULT_USER_ID)
+            .grid("MULTIPLY", 1..=12, 1..=12)
+            .unwrap();
+        assert_eq!(grid.get(3, 4).unwrap().total, 0);
+    }
+
+    #[test]
+    fn test_mastery_levels() {
+        let mut cell = MasteryCell::new(2, 3);
+        cell.total = 10;
+        cell.correct = 5;
+        assert_eq!(cell.level(), MasteryLevel::Struggling);
+        cell.correct = 10;
+        assert_eq!(cell.level(), MasteryLevel::Learning);
+        cell.interval = Some(MASTERED_INTERVAL);
+        assert_eq!(cell.level(), MasteryLevel::Mastered);
+    }
+}
@@ -1,20 +1,45 @@
 pub mod accuracy;
+pub mod decline;
+pub mod errors;
+pub mod mastery;
+pub mod report;
 pub mod streak;
+pub mod summary;
 pub mod time_statistics;
+pub mod trends;
 
+use chrono::{DateTime, Utc};
 use rusqlite::Connection;
+use rusqlite::Result;
 
 pub use accuracy::AccuracyRepository;
+pub use decline::{
+    DECLINE_DISCLAIMER, DeclineCriteria, DeclineReport, DeclineRepository, DeclineStatus,
+};
+pub use errors::{ErrorBreakdown, ErrorsRepository};
+pub use mastery::{MasteryCell, MasteryGrid, MasteryLevel, MasteryRepository};
+pub use report::{
+    FactResult, OperationProgress, PeriodResult, PracticeReport, ReportFormat, ReportRepository,
+    ReviewBacklog,
+};
 pub use streak::StreakRepository;
-pub use time_statistics::TimeStatisticsRepository;
+pub use summary::{OperationStatistics, PeriodStatistics, StatisticsSummary};
+pub use time_statistics::{TimeStatisticsRepository, TimingBaselines, TimingOutlier};
+pub use trends::{ChartRange, DeckPoint, Trends, TrendsRepository, WeeklyPoint};
 
 /// Analytics facade providing high-level analytics operations
 pub struct Analytics<'a> {
     pub conn: &'a Connection,
+    user_id: i64,
 }
 
 impl<'a> Analytics<'a> {
-    pub fn new(conn: &'a Connection) -> Self {
-        Analytics { conn }
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        Analytics { conn, user_id }
+    }
+
+    /// Accuracy and timing per operation type and period, streak and reviews due today
+    pub fn summary(&self, now: DateTime<Utc>) -> Result<StatisticsSummary> {
+        summary::summarize(self.conn, self.user_id, now)
     }
 }
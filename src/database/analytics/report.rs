@@ -0,0 +1,695 @@
+use super::{AccuracyRepository, ErrorsRepository, StreakRepository, TimeStatisticsRepository};
+use crate::database::decks::DecksRepository;
+use crate::database::review_items::ReviewItemsRepository;
+use crate::database::users::UsersRepository;
+use crate::error_analysis::ErrorKind;
+use crate::operations::{Operation, OperationType};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use rusqlite::{Result, params};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+/// Number of facts listed as the hardest of the period
+pub const HARDEST_FACTS_LIMIT: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn from(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "html" => Some(ReportFormat::Html),
+            "markdown" | "md" => Some(ReportFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Accuracy and speed of correct answers for one operation type over a period
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PeriodResult {
+    pub correct: i64,
+    pub total: i64,
+    /// Average time of correct answers in seconds
+    pub mean_time: Option<f64>,
+}
+
+impl PeriodResult {
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.correct as f64 / self.total as f64 * 100.0)
+    }
+}
+
+/// One operation type in the report period compared with the period before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationProgress {
+    pub operation_type: String,
+    pub current: PeriodResult,
+    pub previous: PeriodResult,
+}
+
+impl OperationProgress {
+    /// Change in accuracy, in percentage points
+    pub fn accuracy_change(&self) -> Option<f64> {
+        Some(self.current.accuracy()? - self.previous.accuracy()?)
+    }
+
+    /// Change in average correct answer time, in seconds (negative = faster)
+    pub fn time_change(&self) -> Option<f64> {
+        Some(self.current.mean_time? - self.previous.mean_time?)
+    }
+}
+
+/// A fact answered in the report period, with how it went
+#[derive(Debug, Clone, PartialEq)]
+pub struct FactResult {
+    pub operation: Operation,
+    pub correct: i64,
+    pub total: i64,
+    pub mean_time: Option<f64>,
+}
+
+/// Reviews waiting when the report was generated
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReviewBacklog {
+    pub due: i64,
+    /// Due for more than a week
+    pub overdue_week: i64,
+    pub scheduled: i64,
+}
+
+/// Printable summary of one profile's practice over a date range, for family members and
+/// therapists
+#[derive(Debug, Clone, PartialEq)]
+pub struct PracticeReport {
+    pub profile_name: String,
+    /// Inclusive start of the period
+    pub start: DateTime<Utc>,
+    /// Exclusive end of the period; the previous period has the same length and ends at `start`
+    pub end: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub decks_completed: usize,
+    pub previous_decks_completed: usize,
+    pub days_practised: usize,
+    pub practice_seconds: f64,
+    /// Consecutive days with answers up to when the report was generated
+    pub streak_days: i32,
+    pub operations: Vec<OperationProgress>,
+    /// Lowest accuracy first, then slowest
+    pub hardest_facts: Vec<FactResult>,
+    /// Wrong answers in the period by likely cause, most frequent first
+    pub mistakes: Vec<(ErrorKind, i64)>,
+    pub backlog: ReviewBacklog,
+}
+
+impl PracticeReport {
+    fn period_label(&self) -> String {
+        format!(
+            "{} to {}",
+            self.start.format("%Y-%m-%d"),
+            (self.end - Duration::seconds(1)).format("%Y-%m-%d")
+        )
+    }
+
+    /// Markdown version of the report
+    pub fn to_markdown(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "# Practice Report: {}", self.profile_name);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Period: {}  ", self.period_label());
+        let _ = writeln!(
+            text,
+            "Generated: {}",
+            self.generated_at.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Sessions");
+        let _ = writeln!(text);
+        for (label, value) in self.session_lines() {
+            let _ = writeln!(text, "- {}: {}", label, value);
+        }
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Accuracy and Speed");
+        let _ = writeln!(text);
+        if self.operations.is_empty() {
+            let _ = writeln!(text, "No answers in this or the previous period.");
+        } else {
+            let _ = writeln!(
+                text,
+                "| Operation | Answers | Accuracy | vs previous | Avg time (correct) | vs previous |"
+            );
+            let _ = writeln!(text, "|---|---:|---:|---|---:|---|");
+            for op in &self.operations {
+                let cells = operation_cells(op);
+                let _ = writeln!(text, "| {} |", cells.join(" | "));
+            }
+        }
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Hardest Facts");
+        let _ = writeln!(text);
+        if self.hardest_facts.is_empty() {
+            let _ = writeln!(text, "No answers in this period.");
+        } else {
+            let _ = writeln!(text, "| Fact | Correct | Avg time (correct) |");
+            let _ = writeln!(text, "|---|---:|---:|");
+            for fact in &self.hardest_facts {
+                let cells = fact_cells(fact);
+                let _ = writeln!(text, "| {} |", cells.join(" | "));
+            }
+        }
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Common Mistakes");
+        let _ = writeln!(text);
+        if self.mistakes.is_empty() {
+            let _ = writeln!(text, "No mistakes in this period.");
+        } else {
+            for (label, value) in self.mistake_lines() {
+                let _ = writeln!(text, "- {}: {}", label, value);
+            }
+        }
+
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Review Backlog");
+        let _ = writeln!(text);
+        for (label, value) in self.backlog_lines() {
+            let _ = writeln!(text, "- {}: {}", label, value);
+        }
+        text
+    }
+
+    /// Self-contained HTML version of the report, styled for printing
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let title = format!("Practice Report: {}", self.profile_name);
+        let _ = writeln!(html, "<!DOCTYPE html>");
+        let _ = writeln!(html, "<html lang=\"en\">");
+        let _ = writeln!(html, "<head>");
+        let _ = writeln!(html, "<meta charset=\"utf-8\">");
+        let _ = writeln!(html, "<title>{}</title>", escape_html(&title));
+        let _ = writeln!(
+            html,
+            "<style>\n\
+             body {{ font-family: sans-serif; max-width: 50em; margin: 2em auto; color: #222; }}\n\
+             table {{ border-collapse: collapse; width: 100%; }}\n\
+             th, td {{ border: 1px solid #bbb; padding: 0.3em 0.6em; text-align: left; }}\n\
+             th {{ background: #eee; }}\n\
+             .meta {{ color: #555; }}\n\
+             </style>"
+        );
+        let _ = writeln!(html, "</head>");
+        let _ = writeln!(html, "<body>");
+        let _ = writeln!(html, "<h1>{}</h1>", escape_html(&title));
+        let _ = writeln!(
+            html,
+            "<p class=\"meta\">Period: {}<br>Generated: {}</p>",
+            self.period_label(),
+            self.generated_at.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        let _ = writeln!(html, "<h2>Sessions</h2>");
+        write_html_list(&mut html, &self.session_lines());
+
+        let _ = writeln!(html, "<h2>Accuracy and Speed</h2>");
+        if self.operations.is_empty() {
+            let _ = writeln!(html, "<p>No answers in this or the previous period.</p>");
+        } else {
+            write_html_table(
+                &mut html,
+                &[
+                    "Operation",
+                    "Answers",
+                    "Accuracy",
+                    "vs previous",
+                    "Avg time (correct)",
+                    "vs previous",
+                ],
+                self.operations.iter().map(operation_cells),
+            );
+        }
+
+        let _ = writeln!(html, "<h2>Hardest Facts</h2>");
+        if self.hardest_facts.is_empty() {
+            let _ = writeln!(html, "<p>No answers in this period.</p>");
+        } else {
+            write_html_table(
+                &mut html,
+                &["Fact", "Correct", "Avg time (correct)"],
+                self.hardest_facts.iter().map(fact_cells),
+            );
+        }
+
+        let _ = writeln!(html, "<h2>Common Mistakes</h2>");
+        if self.mistakes.is_empty() {
+            let _ = writeln!(html, "<p>No mistakes in this period.</p>");
+        } else {
+            write_html_list(&mut html, &self.mistake_lines());
+        }
+
+        let _ = writeln!(html, "<h2>Review Backlog</h2>");
+        write_html_list(&mut html, &self.backlog_lines());
+        let _ = writeln!(html, "</body>");
+        let _ = writeln!(html, "</html>");
+        html
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Html => self.to_html(),
+            ReportFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    fn session_lines(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "Sessions completed",
+                format!(
+                    "{} (previous period: {})",
+                    self.decks_completed, self.previous_decks_completed
+                ),
+            ),
+            ("Days practised", self.days_practised.to_string()),
+            (
+                "Practice time",
+                format!("{:.0} minutes", self.practice_seconds / 60.0),
+            ),
+            ("Current streak", format!("{} days", self.streak_days)),
+        ]
+    }
+
+    fn mistake_lines(&self) -> Vec<(&'static str, String)> {
+        self.mistakes
+            .iter()
+            .map(|(kind, count)| (kind.label(), count.to_string()))
+            .collect()
+    }
+
+    fn backlog_lines(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Reviews due now", self.backlog.due.to_string()),
+            (
+                "Due for more than a week",
+                self.backlog.overdue_week.to_string(),
+            ),
+            ("Facts being reviewed", self.backlog.scheduled.to_string()),
+        ]
+    }
+}
+
+fn operation_label(operation_type: &str) -> String {
+    match OperationType::from_str(&operation_type.to_string()) {
+        Some(OperationType::Addition) => "Addition".to_string(),
+        Some(OperationType::Subtraction) => "Subtraction".to_string(),
+        Some(OperationType::Multiplication) => "Multiplication".to_string(),
+        Some(OperationType::Division) => "Division".to_string(),
+        None => operation_type.to_string(),
+    }
+}
+
+fn operation_cells(op: &OperationProgress) -> Vec<String> {
+    let accuracy = op
+        .current
+        .accuracy()
+        .map_or("-".to_string(), |a| format!("{:.1}%", a));
+    let accuracy_change = match op.accuracy_change() {
+        Some(change) => format!("{:+.1} pts", change),
+        None => "-".to_string(),
+    };
+    let time = op
+        .current
+        .mean_time
+        .map_or("-".to_string(), |t| format!("{:.2}s", t));
+    let time_change = match op.time_change() {
+        Some(change) if change < 0.0 => format!("{:.2}s faster", -change),
+        Some(change) if change > 0.0 => format!("{:.2}s slower", change),
+        Some(_) => "same".to_string(),
+        None => "-".to_string(),
+    };
+    vec![
+        operation_label(&op.operation_type),
+        op.current.total.to_string(),
+        accuracy,
+        accuracy_change,
+        time,
+        time_change,
+    ]
+}
+
+fn fact_cells(fact: &FactResult) -> Vec<String> {
+    let operation = &fact.operation;
+    vec![
+        format!(
+            "{} {} {} = {}",
+            operation.operand1,
+            operation.operation_type.symbol(),
+            operation.operand2,
+            operation.result
+        ),
+        format!("{} of {}", fact.correct, fact.total),
+        fact.mean_time
+            .map_or("-".to_string(), |t| format!("{:.2}s", t)),
+    ]
+}
+
+fn write_html_list(html: &mut String, lines: &[(&str, String)]) {
+    let _ = writeln!(html, "<ul>");
+    for (label, value) in lines {
+        let _ = writeln!(
+            html,
+            "<li>{}: {}</li>",
+            escape_html(label),
+            escape_html(value)
+        );
+    }
+    let _ = writeln!(html, "</ul>");
+}
+
+fn write_html_table(html: &mut String, headers: &[&str], rows: impl Iterator<Item = Vec<String>>) {
+    let _ = writeln!(html, "<table>");
+    let header: String = headers
+        .iter()
+        .map(|h| format!("<th>{}</th>", escape_html(h)))
+        .collect();
+    let _ = writeln!(html, "<tr>{}</tr>", header);
+    for row in rows {
+        let cells: String = row
+            .iter()
+            .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+            .collect();
+        let _ = writeln!(html, "<tr>{}</tr>", cells);
+    }
+    let _ = writeln!(html, "</table>");
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub struct ReportRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> ReportRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        ReportRepository { conn, user_id }
+    }
+
+    /// Summarise practice from `start` (inclusive) to `end` (exclusive) against the period of the
+    /// same length before it; streak and review backlog are as of `now`
+    /// Only answers from completed decks are considered
+    pub fn report(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<PracticeReport> {
+        let previous_start = start - (end - start);
+        let profile_name = UsersRepository::new(self.conn)
+            .get(self.user_id)?
+            .map_or_else(|| format!("Profile {}", self.user_id), |user| user.name);
+
+        let decks = DecksRepository::new(self.conn, self.user_id, Box::new(move || now));
+        let completed = decks.get_completed_between(start, end)?;
+        let previous_decks_completed = decks.get_completed_between(previous_start, start)?.len();
+        let days_practised = completed
+            .iter()
+            .filter_map(|deck| deck.completed_at.map(|at| at.date_naive()))
+            .collect::<HashSet<_>>()
+            .len();
+        let practice_seconds = completed.iter().map(|deck| deck.total_time_seconds).sum();
+
+        let streak_days =
+            StreakRepository::new(self.conn, self.user_id).calculate_consecutive_days(now)?;
+
+        let operations = self.operation_progress(previous_start, start, end)?;
+        let hardest_facts = self.hardest_facts(start, end)?;
+        let mistakes = ErrorsRepository::new(self.conn, self.user_id)
+            .all_operations_between(start, end)?
+            .totals();
+
+        let reviews = ReviewItemsRepository::new(self.conn, self.user_id, Box::new(move || now));
+        let backlog = ReviewBacklog {
+            due: reviews.count_due(now)?,
+            overdue_week: reviews.count_due(now - Duration::days(7))?,
+            scheduled: reviews.get_all()?.len() as i64,
+        };
+
+        Ok(PracticeReport {
+            profile_name,
+            start,
+            end,
+            generated_at: now,
+            decks_completed: completed.len(),
+            previous_decks_completed,
+            days_practised,
+            practice_seconds,
+            streak_days,
+            operations,
+            hardest_facts,
+            mistakes,
+            backlog,
+        })
+    }
+
+    fn operation_progress(
+        &self,
+        previous_start: DateTime<Utc>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<OperationProgress>> {
+        let accuracy = AccuracyRepository::new(self.conn, self.user_id);
+        let times = TimeStatisticsRepository::new(self.conn, self.user_id);
+        let current_accuracy = accuracy.all_operations_between(start, end)?;
+        let previous_accuracy = accuracy.all_operations_between(previous_start, start)?;
+        let current_times = times.all_operations_between(start, end)?;
+        let previous_times = times.all_operations_between(previous_start, start)?;
+
+        let result = |accuracy: Option<&(i64, i64, f64)>, time: Option<f64>| {
+            let (correct, total, _) = accuracy.copied().unwrap_or_default();
+            PeriodResult {
+                correct,
+                total,
+                mean_time: time,
+            }
+        };
+
+        let operation_types: BTreeSet<&String> = current_accuracy
+            .keys()
+            .chain(previous_accuracy.keys())
+            .collect();
+        Ok(operation_types
+            .into_iter()
+            .map(|operation_type| OperationProgress {
+                operation_type: operation_type.clone(),
+                current: result(
+                    current_accuracy.get(operation_type),
+                    current_times.get(operation_type).map(|t| t.average),
+                ),
+                previous: result(
+                    previous_accuracy.get(operation_type),
+                    previous_times.get(operation_type).map(|t| t.average),
+                ),
+            })
+            .collect())
+    }
+
+    fn hardest_facts(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<FactResult>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT o.operation_type, o.operand1, o.operand2,
+                COUNT(CASE WHEN a.is_correct = 1 THEN 1 END) as correct_count,
+                COUNT(a.id) as total_count,
+                AVG(CASE WHEN a.is_correct = 1 AND a.timing_interrupted = 0
+                    THEN a.time_spent_seconds END) as mean_time
+            FROM answers a
+            INNER JOIN operations o ON a.operation_id = o.id
+            INNER JOIN decks d ON a.deck_id = d.id
+            WHERE d.status = 'completed'
+            AND d.user_id = ?1
+            AND a.created_at >= ?2 AND a.created_at < ?3
+            GROUP BY o.id
+            ORDER BY CAST(correct_count AS FLOAT) / total_count ASC,
+                     mean_time IS NOT NULL, mean_time DESC, total_count DESC
+            LIMIT ?4"#,
+        )?;
+        let rows = stmt.query_map(
+            params![
+                self.user_id,
+                start.to_rfc3339(),
+                end.to_rfc3339(),
+                HARDEST_FACTS_LIMIT
+            ],
+            |row| {
+                let operation_type: String = row.get(0)?;
+                let operation_type = OperationType::from_str(&operation_type).ok_or_else(|| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        0,
+                        rusqlite::types::Type::Text,
+                        format!("unknown operation type '{}'", operation_type).into(),
+                    )
+                })?;
+                Ok(FactResult {
+                    operation: Operation::new(operation_type, row.get(1)?, row.get(2)?),
+                    correct: row.get(3)?,
+                    total: row.get(4)?,
+                    mean_time: row.get(5)?,
+                })
+            },
+        )?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::{AnswersRepository, NewAnswer};
+    use crate::database::connection::init_connection;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use chrono::TimeZone;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    /// Record a completed deck at `when` with the given (operand2, is_correct, time) answers to 7 × n
+    fn practice(conn: &Connection, when: DateTime<Utc>, answers: &[(i32, bool, f64)]) {
+        let ops_repo = OperationsRepository::new(conn);
+        let clock = move || when;
+        let decks_repo = DecksRepository::new(conn, DEFAULT_USER_ID, Box::new(clock));
+        let answers_repo = AnswersRepository::new_with_date_provider(conn, DEFAULT_USER_ID, &clock);
+        let deck_id = decks_repo.create().unwrap();
+        for &(operand2, is_correct, time) in answers {
+            let op_id = match ops_repo.find("MULTIPLY", 7, operand2, false).unwrap() {
+                Some(op) => op.id,
+                None => ops_repo
+                    .insert("MULTIPLY", 7, operand2, 7 * operand2, None)
+                    .unwrap(),
+            };
+            // Wrong answers are off by one
+            let answer = if is_correct {
+                7 * operand2
+            } else {
+                7 * operand2 + 1
+            };
+            answers_repo
+                .insert_answer(&NewAnswer {
+                    operation_id: op_id,
+                    user_answer: answer,
+                    is_correct,
+                    time_spent_seconds: time,
+                    deck_id: Some(deck_id),
+                    error_kind: (!is_correct).then_some(ErrorKind::OffByOne),
+                    ..NewAnswer::default()
+                })
+                .unwrap();
+        }
+        decks_repo.complete(deck_id).unwrap();
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let report = ReportRepository::new(&conn, DEFAULT_USER_ID)
+            .report(now - Duration::days(7), now, now)
+            .unwrap();
+        assert_eq!(report.profile_name, "Default");
+        assert_eq!(report.decks_completed, 0);
+        assert!(report.operations.is_empty());
+        assert!(report.hardest_facts.is_empty());
+        assert!(report.to_markdown().contains("No answers in this period."));
+    }
+
+    #[test]
+    fn test_report_compares_with_previous_period() {
+        let conn = create_test_db();
+        let start = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 5, 8, 0, 0, 0).unwrap();
+        let now = end + Duration::hours(10);
+
+        // Previous week: half right, slow
+        practice(
+            &conn,
+            start - Duration::days(3),
+            &[(8, true, 4.0), (6, false, 5.0)],
+        );
+        // This week: two sessions on different days, faster and more accurate
+        practice(&conn, start + Duration::hours(9), &[(8, true, 2.0)]);
+        practice(
+            &conn,
+            start + Duration::days(2),
+            &[(6, false, 3.0), (9, true, 2.0), (6, true, 2.5)],
+        );
+        // After the period; not counted
+        practice(&conn, end + Duration::hours(1), &[(6, false, 9.0)]);
+
+        let report = ReportRepository::new(&conn, DEFAULT_USER_ID)
+            .report(start, end, now)
+            .unwrap();
+        assert_eq!(report.decks_completed, 2);
+        assert_eq!(report.previous_decks_completed, 1);
+        assert_eq!(report.days_practised, 2);
+
+        let multiply = &report.operations[0];
+        assert_eq!(multiply.operation_type, "MULTIPLY");
+        assert_eq!((multiply.current.correct, multiply.current.total), (3, 4));
+        assert_eq!(multiply.accuracy_change(), Some(25.0));
+        assert!(multiply.time_change().unwrap() < 0.0);
+
+        // 7 × 6 was missed once in two tries, the others were always right
+        let hardest = &report.hardest_facts[0];
+        assert_eq!(hardest.operation.operand2, 6);
+        assert_eq!((hardest.correct, hardest.total), (1, 2));
+        assert_eq!(report.hardest_facts.len(), 3);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("Period: 2025-05-01 to 2025-05-07"));
+        assert!(markdown.contains("| Multiplication | 4 | 75.0% | +25.0 pts |"));
+        assert!(markdown.contains("| 7 × 6 = 42 | 1 of 2 |"));
+        assert_eq!(report.mistakes, vec![(ErrorKind::OffByOne, 1)]);
+        assert!(markdown.contains("- Off by one: 1"));
+    }
+
+    #[test]
+    fn test_report_is_scoped_to_profile() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        practice(&conn, now - Duration::days(1), &[(8, true, 2.0)]);
+        let alice = UsersRepository::new(&conn).create("Alice").unwrap();
+
+        let report = ReportRepository::new(&conn, alice)
+            .report(now - Duration::days(7), now, now)
+            .unwrap();
+        assert_eq!(report.profile_name, "Alice");
+        assert_eq!(report.decks_completed, 0);
+        assert!(report.operations.is_empty());
+    }
+
+    #[test]
+    fn test_html_escapes_profile_name() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let user_id = UsersRepository::new(&conn).create("<Sam & Jo>").unwrap();
+        let html = ReportRepository::new(&conn, user_id)
+            .report(now - Duration::days(7), now, now)
+            .unwrap()
+            .to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Practice Report: &lt;Sam &amp; Jo&gt;"));
+        assert!(!html.contains("<Sam"));
+    }
+}
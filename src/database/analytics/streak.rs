@@ -1,29 +1,32 @@
 use chrono::{DateTime, Utc};
 use rusqlite::Connection;
-use rusqlite::Result;
+use rusqlite::{Result, params};
 use std::collections::HashSet;
 
 pub struct StreakRepository<'a> {
     conn: &'a Connection,
+    user_id: i64,
 }
 
 impl<'a> StreakRepository<'a> {
-    pub fn new(conn: &'a Connection) -> Self {
-        StreakRepository { conn }
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        StreakRepository { conn, user_id }
     }
 
-    /// Calculate the number of consecutive days the user has completed answers
+    /// Calculate the number of consecutive days, ending today or yesterday relative to `now`,
+    /// on which the user has completed answers
     /// Returns the streak count (0 if no answers exist)
-    pub fn calculate_consecutive_days(&self) -> Result<i32> {
+    pub fn calculate_consecutive_days(&self, now: DateTime<Utc>) -> Result<i32> {
         // Get all unique dates when answers were completed, ordered by date descending
         let mut stmt = self.conn.prepare(
             r#"SELECT DISTINCT DATE(a.created_at) as answer_date
             FROM answers a
+            WHERE a.user_id = ?1
             ORDER BY answer_date DESC"#,
         )?;
 
         let dates: Vec<String> = stmt
-            .query_map([], |row| row.get(0))?
+            .query_map([self.user_id], |row| row.get(0))?
             .collect::<Result<Vec<String>, _>>()?;
 
         if dates.is_empty() {
@@ -35,8 +38,8 @@ impl<'a> StreakRepository<'a> {
         let mut current_date_idx = 0;
 
         // Get today's date and yesterday's date to handle cases where last activity wasn't today
-        let today = Utc::now().format("%Y-%m-%d").to_string();
-        let yesterday = (Utc::now() - chrono::Duration::days(1))
+        let today = now.format("%Y-%m-%d").to_string();
+        let yesterday = (now - chrono::Duration::days(1))
             .format("%Y-%m-%d")
             .to_string();
 
@@ -88,13 +91,15 @@ impl<'a> StreakRepository<'a> {
             r#"SELECT DISTINCT DATE(a.created_at) as answer_date
             FROM answers a
             WHERE DATE(a.created_at) >= ?1 AND DATE(a.created_at) <= ?2
+            AND a.user_id = ?3
             ORDER BY answer_date DESC"#,
         )?;
 
         let dates_with_answers: Vec<String> = stmt
-            .query_map([ten_days_ago.to_string(), today.to_string()], |row| {
-                row.get(0)
-            })?
+            .query_map(
+                params![ten_days_ago.to_string(), today.to_string(), self.user_id],
+                |row| row.get(0),
+            )?
             .collect::<Result<Vec<String>, _>>()?;
 
         Ok(dates_with_answers)
@@ -123,13 +128,15 @@ impl<'a> StreakRepository<'a> {
             r#"SELECT DISTINCT DATE(a.created_at) as answer_date
             FROM answers a
             WHERE DATE(a.created_at) >= ?1 AND DATE(a.created_at) <= ?2
+            AND a.user_id = ?3
             ORDER BY answer_date DESC"#,
         )?;
 
         let dates_with_answers: Vec<String> = stmt
-            .query_map([ten_days_ago.to_string(), today.to_string()], |row| {
-                row.get(0)
-            })?
+            .query_map(
+                params![ten_days_ago.to_string(), today.to_string(), self.user_id],
+                |row| row.get(0),
+            )?
             .collect::<Result<Vec<String>, _>>()?;
 
         // Find dates without answers
@@ -150,6 +157,7 @@ mod tests {
     use crate::database::connection::init_connection;
     use crate::database::decks::DecksRepository;
     use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
 
     fn create_test_db() -> rusqlite::Connection {
         init_connection(":memory:").expect("Failed to create test database")
@@ -158,8 +166,8 @@ mod tests {
     #[test]
     fn test_consecutive_days_streak_no_answers() {
         let conn = create_test_db();
-        let streak_repo = StreakRepository::new(&conn);
-        let streak = streak_repo.calculate_consecutive_days().unwrap();
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
+        let streak = streak_repo.calculate_consecutive_days(Utc::now()).unwrap();
         assert_eq!(streak, 0);
     }
 
@@ -167,9 +175,9 @@ mod tests {
     fn test_consecutive_days_streak_single_day() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
-        let streak_repo = StreakRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -178,14 +186,46 @@ mod tests {
             .unwrap();
         decks_repo.complete(deck_id).unwrap();
 
-        let streak = streak_repo.calculate_consecutive_days().unwrap();
+        let streak = streak_repo.calculate_consecutive_days(Utc::now()).unwrap();
         assert_eq!(streak, 1);
     }
 
+    #[test]
+    fn test_consecutive_days_streak_honors_given_now() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
+        let last_day = chrono::TimeZone::with_ymd_and_hms(&Utc, 2025, 3, 10, 12, 0, 0).unwrap();
+
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, None).unwrap();
+        for days_ago in 0..3 {
+            let answered_at = last_day - chrono::Duration::days(days_ago);
+            let date_fn = move || answered_at;
+            let answers_repo =
+                AnswersRepository::new_with_date_provider(&conn, DEFAULT_USER_ID, &date_fn);
+            answers_repo.insert(op_id, 5, true, 1.0, None).unwrap();
+        }
+
+        assert_eq!(streak_repo.calculate_consecutive_days(last_day).unwrap(), 3);
+        let next_day = last_day + chrono::Duration::days(1);
+        assert_eq!(streak_repo.calculate_consecutive_days(next_day).unwrap(), 3);
+        let two_days_later = last_day + chrono::Duration::days(2);
+        assert_eq!(
+            streak_repo
+                .calculate_consecutive_days(two_days_later)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            streak_repo.calculate_consecutive_days(Utc::now()).unwrap(),
+            0
+        );
+    }
+
     #[test]
     fn test_get_days_with_answers_empty() {
         let conn = create_test_db();
-        let streak_repo = StreakRepository::new(&conn);
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
         // No answers in the database
         let days_with_answers = streak_repo.get_days_with_answers(Utc::now()).unwrap();
         assert_eq!(days_with_answers.len(), 0);
@@ -195,9 +235,9 @@ mod tests {
     fn test_get_days_with_answers_with_recent_answer() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
-        let streak_repo = StreakRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -213,7 +253,7 @@ mod tests {
     #[test]
     fn test_get_missing_days_in_streak_empty() {
         let conn = create_test_db();
-        let streak_repo = StreakRepository::new(&conn);
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
         // No answers in the database
         let missing_days = streak_repo.get_missing_days(10, Utc::now()).unwrap();
         // All days in the last 10 days should be missing since there are no answers
@@ -225,9 +265,9 @@ mod tests {
     fn test_get_missing_days_in_streak_with_recent_answer() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
-        let streak_repo = StreakRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -245,9 +285,9 @@ mod tests {
     fn test_get_missing_days_ignores_max_days_parameter() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
-        let streak_repo = StreakRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let streak_repo = StreakRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -0,0 +1,188 @@
+use super::{AccuracyRepository, ErrorsRepository, StreakRepository, TimeStatisticsRepository};
+use crate::database::review_items::ReviewItemsRepository;
+use crate::error_analysis::ErrorKind;
+use crate::spaced_repetition::AnswerTimedEvaluator;
+use chrono::{DateTime, Days, Utc};
+use rusqlite::Connection;
+use rusqlite::Result;
+use std::collections::{BTreeSet, HashMap};
+
+/// Accuracy and timing of one operation type over one period
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PeriodStatistics {
+    /// (correct_count, total_count, accuracy_percentage)
+    pub accuracy: Option<(i64, i64, f64)>,
+    /// Timing of correct answers
+    pub timing: Option<AnswerTimedEvaluator>,
+}
+
+/// Statistics of one operation type over the periods shown in reports
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationStatistics {
+    pub operation_type: String,
+    pub global: PeriodStatistics,
+    pub last_30_days: PeriodStatistics,
+    pub last_10_decks: PeriodStatistics,
+    /// Wrong answers in the last 30 days by likely cause, most frequent first
+    pub mistakes_last_30_days: Vec<(ErrorKind, i64)>,
+}
+
+/// Everything the statistics screen shows, gathered in one pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatisticsSummary {
+    /// Sorted by operation type
+    pub operations: Vec<OperationStatistics>,
+    pub total_global: Option<(i64, i64, f64)>,
+    pub total_last_30_days: Option<(i64, i64, f64)>,
+    pub total_last_10_decks: Option<(i64, i64, f64)>,
+    pub streak_days: i32,
+    /// Review items due before the end of the current (UTC) day
+    pub due_today: i64,
+}
+
+/// Build the statistics summary of a profile as of `now`
+pub(crate) fn summarize(
+    conn: &Connection,
+    user_id: i64,
+    now: DateTime<Utc>,
+) -> Result<StatisticsSummary> {
+    let accuracy = AccuracyRepository::new(conn, user_id);
+    let timing = TimeStatisticsRepository::new(conn, user_id);
+
+    let global_accuracy = accuracy.all_operations()?;
+    let last_30_days_accuracy = accuracy.all_operations_last_30_days(now)?;
+    let last_10_decks_accuracy = accuracy.all_operations_last_10_decks()?;
+    let global_timing = timing.all_operations()?;
+    let last_30_days_timing = timing.all_operations_last_30_days(now)?;
+    let last_10_decks_timing = timing.all_operations_last_10_decks()?;
+    let mistakes = ErrorsRepository::new(conn, user_id).all_operations_last_30_days(now)?;
+
+    let operation_types: BTreeSet<&String> =
+        global_accuracy.keys().chain(global_timing.keys()).collect();
+    let period = |accuracy: &HashMap<String, (i64, i64, f64)>,
+                  timing: &HashMap<String, AnswerTimedEvaluator>,
+                  op_type: &str| PeriodStatistics {
+        accuracy: accuracy.get(op_type).copied(),
+        timing: timing.get(op_type).copied(),
+    };
+    let operations = operation_types
+        .into_iter()
+        .map(|op_type| OperationStatistics {
+            operation_type: op_type.clone(),
+            global: period(&global_accuracy, &global_timing, op_type),
+            last_30_days: period(&last_30_days_accuracy, &last_30_days_timing, op_type),
+            last_10_decks: period(&last_10_decks_accuracy, &last_10_decks_timing, op_type),
+            mistakes_last_30_days: mistakes.for_operation(op_type).to_vec(),
+        })
+        .collect();
+
+    // Totals over no answers have no accuracy to report
+    let non_empty = |total: Result<(i64, i64, f64)>| total.ok().filter(|(_, count, _)| *count > 0);
+
+    let end_of_day = now
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+        .unwrap_or(now);
+
+    Ok(StatisticsSummary {
+        operations,
+        total_global: non_empty(accuracy.total_accuracy()),
+        total_last_30_days: non_empty(accuracy.total_accuracy_last_30_days(now)),
+        total_last_10_decks: non_empty(accuracy.total_accuracy_last_10_decks()),
+        streak_days: StreakRepository::new(conn, user_id).calculate_consecutive_days(now)?,
+        due_today: ReviewItemsRepository::new(conn, user_id, Box::new(move || now))
+            .count_due(end_of_day)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::{AnswersRepository, NewAnswer};
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use chrono::{Duration, TimeZone};
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    #[test]
+    fn test_summary_of_empty_database() {
+        let conn = create_test_db();
+        let summary = summarize(&conn, DEFAULT_USER_ID, Utc::now()).unwrap();
+
+        assert!(summary.operations.is_empty());
+        assert_eq!(summary.total_global, None);
+        assert_eq!(summary.streak_days, 0);
+        assert_eq!(summary.due_today, 0);
+    }
+
+    #[test]
+    fn test_summary_collects_periods_streak_and_due_items() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 9, 0, 0).unwrap();
+        let ops_repo = OperationsRepository::new(&conn);
+        let clock = move || now;
+        let answers_repo =
+            AnswersRepository::new_with_date_provider(&conn, DEFAULT_USER_ID, &clock);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(move || now));
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(move || now));
+
+        let deck_id = decks_repo.create().unwrap();
+        let add = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        let multiply = ops_repo
+            .insert("MULTIPLY", 3, 4, 12, Some(deck_id))
+            .unwrap();
+        answers_repo
+            .insert(add, 5, true, 2.0, Some(deck_id))
+            .unwrap();
+        answers_repo
+            .insert_answer(&NewAnswer {
+                operation_id: multiply,
+                user_answer: 11,
+                time_spent_seconds: 4.0,
+                deck_id: Some(deck_id),
+                error_kind: Some(ErrorKind::OffByOne),
+                ..NewAnswer::default()
+            })
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        // Due later today, and tomorrow
+        review_repo.insert(add, now + Duration::hours(5)).unwrap();
+        review_repo
+            .insert(multiply, now + Duration::days(1))
+            .unwrap();
+
+        let summary = summarize(&conn, DEFAULT_USER_ID, now).unwrap();
+        let types: Vec<&str> = summary
+            .operations
+            .iter()
+            .map(|op| op.operation_type.as_str())
+            .collect();
+        assert_eq!(types, vec!["ADD", "MULTIPLY"]);
+
+        let add_stats = &summary.operations[0];
+        assert_eq!(add_stats.global.accuracy, Some((1, 1, 100.0)));
+        assert_eq!(add_stats.last_30_days.accuracy, Some((1, 1, 100.0)));
+        assert_eq!(add_stats.global.timing.unwrap().average, 2.0);
+
+        let multiply_stats = &summary.operations[1];
+        assert_eq!(multiply_stats.last_10_decks.accuracy, Some((0, 1, 0.0)));
+        assert_eq!(multiply_stats.global.timing, None);
+        assert_eq!(
+            multiply_stats.mistakes_last_30_days,
+            vec![(ErrorKind::OffByOne, 1)]
+        );
+        assert!(add_stats.mistakes_last_30_days.is_empty());
+
+        assert_eq!(summary.total_global, Some((1, 2, 50.0)));
+        assert_eq!(summary.streak_days, 1);
+        assert_eq!(summary.due_today, 1);
+    }
+}
@@ -1,64 +1,223 @@
+use super::accuracy::{between_where, last_30_days_where, since_where};
+use crate::difficulty::DifficultyBucket;
+use crate::operations::{Operation, OperationType};
 use crate::spaced_repetition::AnswerTimedEvaluator;
+use crate::timing_estimator::TimingEstimator;
+use chrono::{DateTime, Utc};
 use rusqlite::Connection;
 use rusqlite::Result;
 use std::collections::HashMap;
 
-// SQL WHERE clause constants for template method filters
-const LAST_30_DAYS_WHERE: &str = "a.created_at >= datetime('now', '-30 days')";
+// SQL WHERE clause constants for template method filters; ?1 is the profile's user id
 const LAST_10_DECKS_WHERE: &str = r#"d.id IN (
     SELECT id FROM decks
-    WHERE status = 'completed'
+    WHERE status = 'completed' AND user_id = ?1
     ORDER BY completed_at DESC
     LIMIT 10
 )"#;
 
+/// Fewest correct answers in a difficulty bucket before its own timing baseline is trusted
+pub const MIN_BUCKET_ANSWERS: usize = 5;
+
+/// Correct answer whose time was left out of a baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingOutlier {
+    pub operation: Operation,
+    pub time_spent: f64,
+}
+
+/// Timing baselines of one operation type, for the whole type and for each difficulty bucket
+/// with at least `MIN_BUCKET_ANSWERS` answers
+#[derive(Debug, Clone, Default)]
+pub struct TimingBaselines {
+    by_bucket: HashMap<DifficultyBucket, AnswerTimedEvaluator>,
+    overall: Option<AnswerTimedEvaluator>,
+}
+
+impl TimingBaselines {
+    /// Baseline for `operation`: its difficulty bucket's when there is one, otherwise the
+    /// operation type's
+    pub fn for_operation(&self, operation: &Operation) -> Option<AnswerTimedEvaluator> {
+        self.by_bucket
+            .get(&DifficultyBucket::of(operation))
+            .copied()
+            .or(self.overall)
+    }
+}
+
+/// Correct answer counted towards timing baselines
+struct TimedAnswer {
+    id: i64,
+    operand1: i32,
+    operand2: i32,
+    time_spent: f64,
+}
+
 pub struct TimeStatisticsRepository<'a> {
     conn: &'a Connection,
+    user_id: i64,
+    estimator: TimingEstimator,
 }
 
 impl<'a> TimeStatisticsRepository<'a> {
-    pub fn new(conn: &'a Connection) -> Self {
-        TimeStatisticsRepository { conn }
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        TimeStatisticsRepository {
+            conn,
+            user_id,
+            estimator: TimingEstimator::Mean,
+        }
     }
 
-    /// Compute time statistics for correct answers of a specific operation type
+    /// Estimate baselines with `estimator` instead of the plain mean
     ///
-    /// Returns (average_time, standard_deviation) for correct answers of the given operation type
-    /// Only considers correct answers from completed decks
-    pub fn for_operation_type(&self, operation_type: &str) -> Result<Option<AnswerTimedEvaluator>> {
-        // First, compute count, sum, and sum of squares for correct answers
+    /// Applies to `for_operation_type`, `for_difficulty_bucket` and `outliers`; the
+    /// `all_operations*` summaries always report the plain mean.
+    pub fn with_estimator(mut self, estimator: TimingEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Operands and times of correct answers of an operation type from completed decks, without
+    /// answers whose timing was interrupted
+    fn correct_answer_times(&self, operation_type: &str) -> Result<Vec<TimedAnswer>> {
         let mut stmt = self.conn.prepare(
-            "SELECT
-                COUNT(a.time_spent_seconds) as count,
-                AVG(a.time_spent_seconds) as average,
-                SUM(a.time_spent_seconds * a.time_spent_seconds) as sum_squares,
-                SUM(a.time_spent_seconds) as total_sum
+            "SELECT a.id, o.operand1, o.operand2, a.time_spent_seconds
             FROM answers a
             INNER JOIN operations o ON a.operation_id = o.id
             INNER JOIN decks d ON a.deck_id = d.id
             WHERE o.operation_type = ?1
             AND a.is_correct = 1
-            AND d.status = 'completed'",
+            AND a.timing_interrupted = 0
+            AND d.status = 'completed'
+            AND d.user_id = ?2
+            ORDER BY a.id",
         )?;
+        let rows = stmt.query_map(rusqlite::params![operation_type, self.user_id], |row| {
+            Ok(TimedAnswer {
+                id: row.get(0)?,
+                operand1: row.get(1)?,
+                operand2: row.get(2)?,
+                time_spent: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    }
 
-        let result = stmt.query_row([operation_type], |row| {
-            let count: i64 = row.get(0)?;
-            if count == 0 {
-                Ok(None)
-            } else {
-                let average: f64 = row.get(1)?;
-                let sum_squares: f64 = row.get(2)?;
-                let total_sum: f64 = row.get(3)?;
+    /// Compute time statistics for correct answers of a specific operation type
+    ///
+    /// Returns (average_time, standard_deviation) for correct answers of the given operation type
+    /// Only considers correct answers from completed decks
+    pub fn for_operation_type(&self, operation_type: &str) -> Result<Option<AnswerTimedEvaluator>> {
+        let times: Vec<f64> = self
+            .correct_answer_times(operation_type)?
+            .into_iter()
+            .map(|answer| answer.time_spent)
+            .collect();
+        Ok(self.estimator.estimate(&times))
+    }
 
-                // Calculate standard deviation: sqrt(sum(x²)/n - (sum(x)/n)²)
-                let variance = (sum_squares / count as f64) - (total_sum / count as f64).powi(2);
-                let stdev = variance.sqrt().max(0.0);
+    /// Compute time statistics for correct answers to questions in the same difficulty bucket
+    /// as `operation` (see `DifficultyBucket`)
+    ///
+    /// Returns None below `MIN_BUCKET_ANSWERS` answers, so callers can fall back to
+    /// `for_operation_type`. Only considers correct answers from completed decks.
+    pub fn for_difficulty_bucket(
+        &self,
+        operation: &Operation,
+    ) -> Result<Option<AnswerTimedEvaluator>> {
+        Ok(self
+            .baselines(&operation.operation_type)?
+            .by_bucket
+            .get(&DifficultyBucket::of(operation))
+            .copied())
+    }
+
+    /// Baselines of an operation type for every difficulty bucket at once, reading its answers a
+    /// single time. Only considers correct answers from completed decks.
+    pub fn baselines(&self, operation_type: &OperationType) -> Result<TimingBaselines> {
+        let answers = self.correct_answer_times(operation_type.as_str())?;
+        let mut bucket_times: HashMap<DifficultyBucket, Vec<f64>> = HashMap::new();
+        for answer in &answers {
+            let answered = Operation::new(operation_type.clone(), answer.operand1, answer.operand2);
+            bucket_times
+                .entry(DifficultyBucket::of(&answered))
+                .or_default()
+                .push(answer.time_spent);
+        }
+
+        let times: Vec<f64> = answers.iter().map(|answer| answer.time_spent).collect();
+        Ok(TimingBaselines {
+            by_bucket: bucket_times
+                .into_iter()
+                .filter(|(_, times)| times.len() >= MIN_BUCKET_ANSWERS)
+                .filter_map(|(bucket, times)| Some((bucket, self.estimator.estimate(&times)?)))
+                .collect(),
+            overall: self.estimator.estimate(&times),
+        })
+    }
 
-                Ok(Some(AnswerTimedEvaluator::new(average, stdev)))
+    /// Correct answers the estimator leaves out of the baselines answers are graded against,
+    /// most recent first
+    ///
+    /// Like `AnswerEvaluatorService::get_evaluator`, each answer is judged within its difficulty
+    /// bucket once the bucket has `MIN_BUCKET_ANSWERS` answers, otherwise within all answers of
+    /// its operation type.
+    pub fn outliers(&self) -> Result<Vec<TimingOutlier>> {
+        let mut outliers = Vec::new();
+        for operation_type in OperationType::all() {
+            let answers = self.correct_answer_times(operation_type.as_str())?;
+            let operations: Vec<Operation> = answers
+                .iter()
+                .map(|answer| {
+                    Operation::new(operation_type.clone(), answer.operand1, answer.operand2)
+                })
+                .collect();
+
+            let mut buckets: HashMap<DifficultyBucket, Vec<usize>> = HashMap::new();
+            for (i, operation) in operations.iter().enumerate() {
+                buckets
+                    .entry(DifficultyBucket::of(operation))
+                    .or_default()
+                    .push(i);
             }
-        })?;
 
-        Ok(result)
+            let mut excluded = Vec::new();
+            let mut in_small_buckets = Vec::new();
+            for members in buckets.values() {
+                if members.len() < MIN_BUCKET_ANSWERS {
+                    in_small_buckets.extend(members);
+                    continue;
+                }
+                let times: Vec<f64> = members.iter().map(|&i| answers[i].time_spent).collect();
+                excluded.extend(
+                    self.estimator
+                        .outliers(&times)
+                        .into_iter()
+                        .map(|k| members[k]),
+                );
+            }
+            if !in_small_buckets.is_empty() {
+                let times: Vec<f64> = answers.iter().map(|answer| answer.time_spent).collect();
+                excluded.extend(
+                    self.estimator
+                        .outliers(&times)
+                        .into_iter()
+                        .filter(|i| in_small_buckets.contains(i)),
+                );
+            }
+
+            for i in excluded {
+                outliers.push((
+                    answers[i].id,
+                    TimingOutlier {
+                        operation: operations[i].clone(),
+                        time_spent: answers[i].time_spent,
+                    },
+                ));
+            }
+        }
+        outliers.sort_by_key(|(id, _)| std::cmp::Reverse(*id));
+        Ok(outliers.into_iter().map(|(_, outlier)| outlier).collect())
     }
 
     /// Helper function to extract and calculate statistics from a row
@@ -84,9 +243,10 @@ impl<'a> TimeStatisticsRepository<'a> {
     /// Helper function to process statistics query and build HashMap
     fn process_statistics_query(
         stmt: &mut rusqlite::Statement,
+        user_id: i64,
     ) -> Result<HashMap<String, AnswerTimedEvaluator>> {
         let mut result = HashMap::new();
-        let rows = stmt.query_map([], Self::extract_row_statistics)?;
+        let rows = stmt.query_map([user_id], Self::extract_row_statistics)?;
         for row in rows {
             let (op_type, evaluator) = row?;
             result.insert(op_type, evaluator);
@@ -110,7 +270,9 @@ impl<'a> TimeStatisticsRepository<'a> {
             INNER JOIN operations o ON a.operation_id = o.id
             INNER JOIN decks d ON a.deck_id = d.id
             WHERE a.is_correct = 1
-            AND d.status = 'completed'"
+            AND a.timing_interrupted = 0
+            AND d.status = 'completed'
+            AND d.user_id = ?1"
             .to_string();
 
         if !additional_where.is_empty() {
@@ -126,7 +288,7 @@ impl<'a> TimeStatisticsRepository<'a> {
         );
 
         let mut stmt = self.conn.prepare(&query)?;
-        Self::process_statistics_query(&mut stmt)
+        Self::process_statistics_query(&mut stmt, self.user_id)
     }
 
     /// Compute time statistics for all operation types (global)
@@ -135,10 +297,13 @@ impl<'a> TimeStatisticsRepository<'a> {
         self.compute_all_operations_template("")
     }
 
-    /// Compute time statistics for all operation types in the last 30 days
+    /// Compute time statistics for all operation types in the 30 days before `now`
     /// Returns a map of operation_type -> AnswerTimedEvaluator
-    pub fn all_operations_last_30_days(&self) -> Result<HashMap<String, AnswerTimedEvaluator>> {
-        self.compute_all_operations_template(LAST_30_DAYS_WHERE)
+    pub fn all_operations_last_30_days(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<HashMap<String, AnswerTimedEvaluator>> {
+        self.compute_all_operations_template(&last_30_days_where(now))
     }
 
     /// Compute time statistics for all operation types from the last 10 completed decks
@@ -146,15 +311,36 @@ impl<'a> TimeStatisticsRepository<'a> {
     pub fn all_operations_last_10_decks(&self) -> Result<HashMap<String, AnswerTimedEvaluator>> {
         self.compute_all_operations_template(LAST_10_DECKS_WHERE)
     }
+
+    /// Compute time statistics for all operation types from answers given at or after `since`
+    /// Returns a map of operation_type -> AnswerTimedEvaluator
+    pub fn all_operations_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<HashMap<String, AnswerTimedEvaluator>> {
+        self.compute_all_operations_template(&since_where(since))
+    }
+
+    /// Compute time statistics for all operation types from answers given at or after `start`
+    /// and before `end`
+    /// Returns a map of operation_type -> AnswerTimedEvaluator
+    pub fn all_operations_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<HashMap<String, AnswerTimedEvaluator>> {
+        self.compute_all_operations_template(&between_where(start, end))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::answers::AnswersRepository;
+    use crate::database::answers::{AnswersRepository, NewAnswer};
     use crate::database::connection::init_connection;
     use crate::database::decks::DecksRepository;
     use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
 
     fn create_test_db() -> rusqlite::Connection {
         init_connection(":memory:").expect("Failed to create test database")
@@ -163,7 +349,7 @@ mod tests {
     #[test]
     fn test_compute_time_statistics_empty_database() {
         let conn = create_test_db();
-        let repo = TimeStatisticsRepository::new(&conn);
+        let repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
         let result = repo.for_operation_type("ADD").unwrap();
         assert!(result.is_none());
     }
@@ -172,9 +358,9 @@ mod tests {
     fn test_compute_time_statistics_single_answer() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| chrono::Utc::now()));
-        let time_stats_repo = TimeStatisticsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let time_stats_repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
         let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
@@ -191,10 +377,164 @@ mod tests {
         assert!(eval.standard_deviation < 0.001);
     }
 
+    #[test]
+    fn test_difficulty_bucket_baseline_ignores_easier_questions() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
+
+        let deck_id = decks_repo.create().unwrap();
+        let easy_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        let hard_id = ops_repo.insert("ADD", 87, 96, 183, Some(deck_id)).unwrap();
+        for _ in 0..MIN_BUCKET_ANSWERS {
+            answers_repo
+                .insert(easy_id, 5, true, 1.0, Some(deck_id))
+                .unwrap();
+        }
+        for time_spent in [6.0, 8.0, 10.0, 8.0] {
+            answers_repo
+                .insert(hard_id, 183, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        decks_repo.complete(deck_id).unwrap();
+
+        let hard = Operation::new(OperationType::Addition, 78, 69);
+        // Too few answers of its own
+        assert_eq!(repo.for_difficulty_bucket(&hard).unwrap(), None);
+
+        let deck_id = decks_repo.create().unwrap();
+        answers_repo
+            .insert(hard_id, 183, true, 8.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        let baseline = repo.for_difficulty_bucket(&hard).unwrap().unwrap();
+        assert!((baseline.average - 8.0).abs() < 0.001);
+        let easy = Operation::new(OperationType::Addition, 3, 4);
+        let baseline = repo.for_difficulty_bucket(&easy).unwrap().unwrap();
+        assert!((baseline.average - 1.0).abs() < 0.001);
+        // The per-type average mixes both
+        let overall = repo.for_operation_type("ADD").unwrap().unwrap();
+        assert!(overall.average > 1.0 && overall.average < 8.0);
+    }
+
+    #[test]
+    fn test_robust_estimator_reports_and_ignores_outliers() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        for time_spent in [2.5, 3.0, 3.5, 300.0, 3.0] {
+            answers_repo
+                .insert(op_id, 5, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        decks_repo.complete(deck_id).unwrap();
+
+        let plain = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
+        assert!(plain.for_operation_type("ADD").unwrap().unwrap().average > 60.0);
+        assert!(plain.outliers().unwrap().is_empty());
+
+        let robust = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID)
+            .with_estimator(TimingEstimator::default());
+        let baseline = robust.for_operation_type("ADD").unwrap().unwrap();
+        assert!((baseline.average - 3.0).abs() < 0.001);
+        assert_eq!(
+            robust.outliers().unwrap(),
+            vec![TimingOutlier {
+                operation: Operation::new(OperationType::Addition, 2, 3),
+                time_spent: 300.0,
+            }]
+        );
+        // Summaries keep the plain mean
+        assert!(robust.all_operations().unwrap()["ADD"].average > 60.0);
+    }
+
+    #[test]
+    fn test_outliers_are_judged_within_their_difficulty_bucket() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID)
+            .with_estimator(TimingEstimator::default());
+
+        let deck_id = decks_repo.create().unwrap();
+        let easy_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        let hard_id = ops_repo.insert("ADD", 87, 96, 183, Some(deck_id)).unwrap();
+        let rare_id = ops_repo.insert("ADD", 12, 34, 46, Some(deck_id)).unwrap();
+        for time_spent in [0.9, 1.0, 1.1, 1.0, 0.9, 1.1, 1.0, 1.2, 0.8, 1.0] {
+            answers_repo
+                .insert(easy_id, 5, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        // Slow next to the easy questions, but ordinary for their own bucket, except the last
+        for time_spent in [8.0, 9.0, 10.0, 8.0, 9.0, 60.0] {
+            answers_repo
+                .insert(hard_id, 183, true, time_spent, Some(deck_id))
+                .unwrap();
+        }
+        // Too few answers for its own baseline, so judged against every addition
+        answers_repo
+            .insert(rare_id, 46, true, 30.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        assert_eq!(
+            repo.outliers().unwrap(),
+            vec![
+                TimingOutlier {
+                    operation: Operation::new(OperationType::Addition, 12, 34),
+                    time_spent: 30.0,
+                },
+                TimingOutlier {
+                    operation: Operation::new(OperationType::Addition, 87, 96),
+                    time_spent: 60.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interrupted_answers_are_left_out() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, Some(deck_id)).unwrap();
+        answers_repo
+            .insert(op_id, 5, true, 2.0, Some(deck_id))
+            .unwrap();
+        answers_repo
+            .insert_answer(&NewAnswer {
+                operation_id: op_id,
+                user_answer: 5,
+                is_correct: true,
+                time_spent_seconds: 40.0,
+                deck_id: Some(deck_id),
+                error_kind: None,
+                timing_interrupted: true,
+            })
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        let baseline = repo.for_operation_type("ADD").unwrap().unwrap();
+        assert!((baseline.average - 2.0).abs() < 0.001);
+        assert!((repo.all_operations().unwrap()["ADD"].average - 2.0).abs() < 0.001);
+    }
+
     #[test]
     fn test_compute_time_statistics_all_operations_empty_database() {
         let conn = create_test_db();
-        let repo = TimeStatisticsRepository::new(&conn);
+        let repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
         let result = repo.all_operations().unwrap();
         assert!(result.is_empty());
     }
@@ -203,9 +543,9 @@ mod tests {
     fn test_compute_time_statistics_all_operations_multiple_types() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
-        let decks_repo = DecksRepository::new(&conn, Box::new(|| chrono::Utc::now()));
-        let time_stats_repo = TimeStatisticsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+        let decks_repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(chrono::Utc::now));
+        let time_stats_repo = TimeStatisticsRepository::new(&conn, DEFAULT_USER_ID);
 
         let deck_id = decks_repo.create().unwrap();
 
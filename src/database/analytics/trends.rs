@@ -0,0 +1,309 @@
+use super::accuracy::since_where;
+use crate::database::decks::DecksRepository;
+use crate::deck::{Deck, DeckStatus};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::Connection;
+use rusqlite::Result;
+use std::collections::BTreeMap;
+
+/// Most decks fetched for the per-deck chart
+const MAX_CHART_DECKS: i32 = 10_000;
+
+/// Time range selectable for trend charts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartRange {
+    #[default]
+    Last30Days,
+    Last90Days,
+    AllTime,
+}
+
+impl ChartRange {
+    pub fn all() -> [ChartRange; 3] {
+        [
+            ChartRange::Last30Days,
+            ChartRange::Last90Days,
+            ChartRange::AllTime,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChartRange::Last30Days => "30 days",
+            ChartRange::Last90Days => "90 days",
+            ChartRange::AllTime => "All time",
+        }
+    }
+
+    /// Start of the range relative to `now` (None for all time)
+    pub fn since(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ChartRange::Last30Days => Some(now - Duration::days(30)),
+            ChartRange::Last90Days => Some(now - Duration::days(90)),
+            ChartRange::AllTime => None,
+        }
+    }
+}
+
+/// Accuracy and average time of one completed deck
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeckPoint {
+    pub deck_id: i64,
+    pub completed_at: DateTime<Utc>,
+    pub accuracy: f64,
+    pub average_time: f64,
+}
+
+/// Accuracy and average time of correct answers for one operation type in one week
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeeklyPoint {
+    /// Monday of the week
+    pub week_start: NaiveDate,
+    pub correct: i64,
+    pub total: i64,
+    pub accuracy: f64,
+    /// None if no answer was correct that week
+    pub average_time: Option<f64>,
+}
+
+/// Chart data for one range, oldest points first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trends {
+    pub range: ChartRange,
+    pub decks: Vec<DeckPoint>,
+    /// Weekly points per operation type
+    pub weekly: BTreeMap<String, Vec<WeeklyPoint>>,
+}
+
+pub struct TrendsRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> TrendsRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        TrendsRepository { conn, user_id }
+    }
+
+    /// Per-deck and weekly per-operation trends for `range` ending at `now`
+    pub fn for_range(&self, range: ChartRange, now: DateTime<Utc>) -> Result<Trends> {
+        let since = range.since(now);
+        let recent = DecksRepository::new(self.conn, self.user_id, Box::new(move || now))
+            .get_recent(MAX_CHART_DECKS)?;
+        Ok(Trends {
+            range,
+            decks: deck_points(&recent, since),
+            weekly: self.weekly_by_operation(since)?,
+        })
+    }
+
+    /// Weekly accuracy and average correct-answer time per operation type
+    /// Only answers from completed decks given at or after `since` are counted
+    pub fn weekly_by_operation(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<BTreeMap<String, Vec<WeeklyPoint>>> {
+        let additional_where = since
+            .map(|since| format!("AND {}", since_where(since)))
+            .unwrap_or_default();
+        let query = format!(
+            r#"SELECT
+                o.operation_type,
+                DATE(a.created_at, 'weekday 0', '-6 days') as week_start,
+                SUM(CASE WHEN a.is_correct = 1 THEN 1 ELSE 0 END) as correct_count,
+                COUNT(*) as total_count,
+                AVG(CASE WHEN a.is_correct = 1 AND a.timing_interrupted = 0
+                    THEN a.time_spent_seconds END) as average_time
+            FROM answers a
+            INNER JOIN operations o ON a.operation_id = o.id
+            INNER JOIN decks d ON a.deck_id = d.id
+            WHERE d.status = 'completed'
+            AND d.user_id = ?1
+            {}
+            GROUP BY o.operation_type, week_start
+            ORDER BY o.operation_type, week_start"#,
+            additional_where
+        );
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map([self.user_id], |row| {
+            let week_start: String = row.get(1)?;
+            let correct: i64 = row.get(2)?;
+            let total: i64 = row.get(3)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                week_start,
+                correct,
+                total,
+                row.get::<_, Option<f64>>(4)?,
+            ))
+        })?;
+
+        let mut weekly: BTreeMap<String, Vec<WeeklyPoint>> = BTreeMap::new();
+        for row in rows {
+            let (operation_type, week_start, correct, total, average_time) = row?;
+            let Ok(week_start) = NaiveDate::parse_from_str(&week_start, "%Y-%m-%d") else {
+                continue;
+            };
+            weekly.entry(operation_type).or_default().push(WeeklyPoint {
+                week_start,
+                correct,
+                total,
+                accuracy: correct as f64 / total as f64 * 100.0,
+                average_time,
+            });
+        }
+        Ok(weekly)
+    }
+}
+
+/// Completed decks at or after `since`, oldest first
+pub fn deck_points(decks: &[Deck], since: Option<DateTime<Utc>>) -> Vec<DeckPoint> {
+    let mut points: Vec<DeckPoint> = decks
+        .iter()
+        .filter(|deck| deck.status == DeckStatus::Completed)
+        .filter_map(|deck| {
+            let completed_at = deck.completed_at?;
+            if since.is_some_and(|since| completed_at < since) {
+                return None;
+            }
+            Some(DeckPoint {
+                deck_id: deck.id,
+                completed_at,
+                accuracy: deck.accuracy_percentage?,
+                average_time: deck.average_time_seconds?,
+            })
+        })
+        .collect();
+    points.sort_by_key(|point| point.completed_at);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::AnswersRepository;
+    use crate::database::connection::init_connection;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use crate::deck::DeckSummary;
+    use chrono::TimeZone;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    /// Complete a deck at `when` with the given (operation_type, operand, is_correct, time) answers
+    fn complete_deck_at(
+        conn: &Connection,
+        when: DateTime<Utc>,
+        answers: &[(&str, i32, bool, f64)],
+    ) -> i64 {
+        let clock = move || when;
+        let decks_repo = DecksRepository::new(conn, DEFAULT_USER_ID, Box::new(clock));
+        let answers_repo = AnswersRepository::new_with_date_provider(conn, DEFAULT_USER_ID, &clock);
+        let ops_repo = OperationsRepository::new(conn);
+
+        let deck_id = decks_repo.create().unwrap();
+        for (op_type, operand, is_correct, time) in answers {
+            let op_id = match ops_repo.find(op_type, *operand, 1, false).unwrap() {
+                Some(op) => op.id,
+                None => ops_repo
+                    .insert(op_type, *operand, 1, *operand, Some(deck_id))
+                    .unwrap(),
+            };
+            answers_repo
+                .insert(op_id, 0, *is_correct, *time, Some(deck_id))
+                .unwrap();
+        }
+        let results: Vec<(bool, f64)> = answers.iter().map(|a| (a.2, a.3)).collect();
+        decks_repo
+            .update_summary(deck_id, &DeckSummary::from_results(&results))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+        deck_id
+    }
+
+    #[test]
+    fn test_chart_range_since() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            ChartRange::Last30Days.since(now),
+            Some(now - Duration::days(30))
+        );
+        assert_eq!(
+            ChartRange::Last90Days.since(now),
+            Some(now - Duration::days(90))
+        );
+        assert_eq!(ChartRange::AllTime.since(now), None);
+    }
+
+    #[test]
+    fn test_deck_trend_respects_range_and_order() {
+        let conn = create_test_db();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let old = complete_deck_at(
+            &conn,
+            now - Duration::days(60),
+            &[("ADD", 1, false, 4.0), ("ADD", 2, true, 2.0)],
+        );
+        let recent = complete_deck_at(&conn, now - Duration::days(2), &[("ADD", 1, true, 1.0)]);
+        // In-progress decks never show up
+        DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(move || now))
+            .create()
+            .unwrap();
+
+        let repo = TrendsRepository::new(&conn, DEFAULT_USER_ID);
+        let last_30 = repo.for_range(ChartRange::Last30Days, now).unwrap();
+        let ids: Vec<i64> = last_30.decks.iter().map(|p| p.deck_id).collect();
+        assert_eq!(ids, vec![recent]);
+
+        let all_time = repo.for_range(ChartRange::AllTime, now).unwrap();
+        let ids: Vec<i64> = all_time.decks.iter().map(|p| p.deck_id).collect();
+        assert_eq!(ids, vec![old, recent]);
+        assert_eq!(all_time.decks[0].accuracy, 50.0);
+        assert_eq!(all_time.decks[0].average_time, 3.0);
+    }
+
+    #[test]
+    fn test_weekly_trend_groups_by_operation_and_week() {
+        let conn = create_test_db();
+        // Wednesday and Sunday of the same week, then the following Monday
+        let wednesday = Utc.with_ymd_and_hms(2025, 5, 14, 10, 0, 0).unwrap();
+        let sunday = Utc.with_ymd_and_hms(2025, 5, 18, 10, 0, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2025, 5, 19, 10, 0, 0).unwrap();
+        complete_deck_at(
+            &conn,
+            wednesday,
+            &[("ADD", 1, true, 2.0), ("MULTIPLY", 3, false, 5.0)],
+        );
+        complete_deck_at(&conn, sunday, &[("ADD", 2, false, 6.0)]);
+        complete_deck_at(&conn, monday, &[("ADD", 3, true, 1.0)]);
+
+        let weekly = TrendsRepository::new(&conn, DEFAULT_USER_ID)
+            .weekly_by_operation(None)
+            .unwrap();
+        let add = &weekly["ADD"];
+        assert_eq!(add.len(), 2);
+        assert_eq!(
+            add[0].week_start,
+            NaiveDate::from_ymd_opt(2025, 5, 12).unwrap()
+        );
+        assert_eq!((add[0].correct, add[0].total), (1, 2));
+        assert_eq!(add[0].accuracy, 50.0);
+        assert_eq!(add[0].average_time, Some(2.0));
+        assert_eq!(
+            add[1].week_start,
+            NaiveDate::from_ymd_opt(2025, 5, 19).unwrap()
+        );
+
+        let multiply = &weekly["MULTIPLY"];
+        assert_eq!(multiply[0].average_time, None);
+
+        let since_monday = TrendsRepository::new(&conn, DEFAULT_USER_ID)
+            .weekly_by_operation(Some(monday))
+            .unwrap();
+        assert_eq!(since_monday["ADD"].len(), 1);
+        assert!(!since_monday.contains_key("MULTIPLY"));
+    }
+}
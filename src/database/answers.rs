@@ -1,3 +1,4 @@
+use crate::error_analysis::ErrorKind;
 use chrono::{DateTime, Utc};
 use rusqlite::{Connection, Result, params};
 
@@ -8,27 +9,50 @@ pub struct AnswerRecord {
     pub user_answer: i32,
     pub is_correct: bool,
     pub time_spent_seconds: f64,
+    /// Likely cause when the answer is wrong
+    pub error_kind: Option<ErrorKind>,
+    /// Whether the question was paused or left idle while it was answered
+    pub timing_interrupted: bool,
+}
+
+/// An answer about to be stored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewAnswer {
+    pub operation_id: i64,
+    pub user_answer: i32,
+    pub is_correct: bool,
+    /// Active time spent on the question, without pauses
+    pub time_spent_seconds: f64,
+    pub deck_id: Option<i64>,
+    pub error_kind: Option<ErrorKind>,
+    /// Whether the question was paused or left idle while it was answered
+    pub timing_interrupted: bool,
 }
 
 pub struct AnswersRepository<'a> {
     conn: &'a Connection,
     get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    user_id: i64,
 }
 
 impl<'a> AnswersRepository<'a> {
     pub fn new_with_date_provider(
         conn: &'a Connection,
+        user_id: i64,
         date_provider: &'a dyn Fn() -> DateTime<Utc>,
     ) -> Self {
         AnswersRepository {
             conn,
             get_current_time: Box::new(date_provider),
+            user_id,
         }
     }
-    pub fn new(conn: &'a Connection) -> Self {
+
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
         AnswersRepository {
             conn,
             get_current_time: Box::new(Utc::now),
+            user_id,
         }
     }
 
@@ -40,16 +64,31 @@ impl<'a> AnswersRepository<'a> {
         time_spent_seconds: f64,
         deck_id: Option<i64>,
     ) -> Result<()> {
+        self.insert_answer(&NewAnswer {
+            operation_id,
+            user_answer,
+            is_correct,
+            time_spent_seconds,
+            deck_id,
+            ..NewAnswer::default()
+        })
+    }
+
+    /// Insert an answer with its likely cause and timing flag
+    pub fn insert_answer(&self, answer: &NewAnswer) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO answers (operation_id, user_answer, is_correct, time_spent_seconds, deck_id, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO answers (operation_id, user_answer, is_correct, time_spent_seconds, deck_id, created_at, user_id, error_kind, timing_interrupted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
-                operation_id,
-                user_answer,
-                is_correct as i32,
-                time_spent_seconds,
-                deck_id,
+                answer.operation_id,
+                answer.user_answer,
+                answer.is_correct as i32,
+                answer.time_spent_seconds,
+                answer.deck_id,
                 (self.get_current_time)().to_rfc3339(),
+                self.user_id,
+                answer.error_kind.map(|kind| kind.as_str()),
+                answer.timing_interrupted as i32,
             ],
         )?;
         Ok(())
@@ -57,10 +96,12 @@ impl<'a> AnswersRepository<'a> {
 
     pub fn get(&self, answer_id: i64) -> Result<Option<AnswerRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, operation_id, user_answer, is_correct, time_spent_seconds FROM answers WHERE id = ?1"
+            "SELECT id, operation_id, user_answer, is_correct, time_spent_seconds, error_kind,
+                    timing_interrupted
+             FROM answers WHERE id = ?1 AND user_id = ?2",
         )?;
 
-        let mut rows = stmt.query([answer_id])?;
+        let mut rows = stmt.query([answer_id, self.user_id])?;
 
         if let Some(row) = rows.next()? {
             Ok(Some(AnswerRecord {
@@ -69,6 +110,10 @@ impl<'a> AnswersRepository<'a> {
                 user_answer: row.get(2)?,
                 is_correct: row.get::<_, i32>(3)? != 0,
                 time_spent_seconds: row.get(4)?,
+                error_kind: row
+                    .get::<_, Option<String>>(5)?
+                    .and_then(|kind| ErrorKind::from(&kind)),
+                timing_interrupted: row.get::<_, i32>(6)? != 0,
             }))
         } else {
             Ok(None)
@@ -76,9 +121,11 @@ impl<'a> AnswersRepository<'a> {
     }
 
     pub fn count(&self) -> Result<i64> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM answers", [], |row| row.get(0))?;
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM answers WHERE user_id = ?1",
+            [self.user_id],
+            |row| row.get(0),
+        )?;
         Ok(count)
     }
 }
@@ -88,6 +135,7 @@ mod tests {
     use super::*;
     use crate::database::connection::init_connection;
     use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
 
     fn create_test_db() -> Connection {
         init_connection(":memory:").expect("Failed to create test database")
@@ -97,7 +145,7 @@ mod tests {
     fn test_insert_answer() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
 
         let op_id = ops_repo.insert("MULTIPLY", 7, 8, 56, None).unwrap();
         answers_repo.insert(op_id, 56, true, 2.5, None).unwrap();
@@ -114,7 +162,7 @@ mod tests {
     fn test_insert_answer_incorrect() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
 
         let op_id = ops_repo.insert("ADD", 15, 25, 40, None).unwrap();
         answers_repo.insert(op_id, 35, false, 3.2, None).unwrap();
@@ -125,10 +173,55 @@ mod tests {
         assert_eq!(answer.time_spent_seconds, 3.2);
     }
 
+    #[test]
+    fn test_insert_answer_with_error_kind() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+
+        let op_id = ops_repo.insert("MULTIPLY", 7, 8, 56, None).unwrap();
+        answers_repo
+            .insert_answer(&NewAnswer {
+                operation_id: op_id,
+                user_answer: 49,
+                time_spent_seconds: 3.0,
+                error_kind: Some(ErrorKind::TableNeighbour),
+                ..NewAnswer::default()
+            })
+            .unwrap();
+
+        let answer = answers_repo.get(1).unwrap().unwrap();
+        assert_eq!(answer.error_kind, Some(ErrorKind::TableNeighbour));
+    }
+
+    #[test]
+    fn test_insert_interrupted_answer() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
+
+        let op_id = ops_repo.insert("MULTIPLY", 7, 8, 56, None).unwrap();
+        answers_repo
+            .insert_answer(&NewAnswer {
+                operation_id: op_id,
+                user_answer: 56,
+                is_correct: true,
+                time_spent_seconds: 4.0,
+                deck_id: None,
+                error_kind: None,
+                timing_interrupted: true,
+            })
+            .unwrap();
+        answers_repo.insert(op_id, 56, true, 2.0, None).unwrap();
+
+        assert!(answers_repo.get(1).unwrap().unwrap().timing_interrupted);
+        assert!(!answers_repo.get(2).unwrap().unwrap().timing_interrupted);
+    }
+
     #[test]
     fn test_get_nonexistent_answer() {
         let conn = create_test_db();
-        let answers_repo = AnswersRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
         let result = answers_repo.get(999).unwrap();
         assert!(result.is_none());
     }
@@ -137,7 +230,7 @@ mod tests {
     fn test_multiple_answers_for_operation() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
 
         let op_id = ops_repo.insert("ADD", 1, 2, 3, None).unwrap();
 
@@ -152,7 +245,7 @@ mod tests {
     fn test_answer_references_operation() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let answers_repo = AnswersRepository::new(&conn);
+        let answers_repo = AnswersRepository::new(&conn, DEFAULT_USER_ID);
 
         let op_id = ops_repo.insert("MULTIPLY", 3, 4, 12, None).unwrap();
         answers_repo.insert(op_id, 12, true, 1.5, None).unwrap();
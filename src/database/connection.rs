@@ -1,6 +1,6 @@
 use log::debug;
-use rusqlite::Connection;
 use rusqlite::Result;
+use rusqlite::{Connection, OpenFlags};
 
 // Embed migrations from the migrations directory
 refinery::embed_migrations!("migrations");
@@ -9,8 +9,15 @@ refinery::embed_migrations!("migrations");
 pub fn init_connection(db_path: &str) -> Result<Connection> {
     let mut conn = Connection::open(db_path)?;
 
-    // Run embedded migrations from the migrations folder
-    match migrations::runner().run(&mut conn) {
+    // Run embedded migrations from the migrations folder. Foreign key enforcement is off while
+    // they run, so tables can be rebuilt and columns added with a reference and a default; the
+    // pragma has no effect inside the migrations' own transactions. Every connection the
+    // application writes through then enforces foreign keys. Rows orphaned before an upgrade
+    // are kept: SQLite only checks references on rows being written.
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let migrated = migrations::runner().run(&mut conn);
+    conn.pragma_update(None, "foreign_keys", true)?;
+    match migrated {
         Ok(_) => {
             debug!("Migrations completed successfully");
         }
@@ -22,3 +29,178 @@ pub fn init_connection(db_path: &str) -> Result<Connection> {
 
     Ok(conn)
 }
+
+/// Opens an existing database without running migrations or writing to it
+pub fn open_read_only(db_path: &str) -> Result<Connection> {
+    Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+}
+
+/// Version of the newest migration embedded in this build
+pub fn latest_schema_version() -> u32 {
+    migrations::runner()
+        .get_migrations()
+        .iter()
+        .map(|migration| migration.version())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Version of the newest migration applied to `conn`, None if it was never migrated
+pub fn schema_version(conn: &Connection) -> Result<Option<u32>> {
+    let has_history: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master
+                        WHERE type = 'table' AND name = 'refinery_schema_history')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_history {
+        return Ok(None);
+    }
+    conn.query_row(
+        "SELECT MAX(version) FROM refinery_schema_history",
+        [],
+        |row| row.get(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use refinery::Target;
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_deduplicate_operations_migration_merges_history() {
+        let mut conn = Connection::open(":memory:").unwrap();
+        conn.pragma_update(None, "foreign_keys", false).unwrap();
+        migrations::runner()
+            .set_target(Target::Version(5))
+            .run(&mut conn)
+            .unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO operations (id, operation_type, operand1, operand2, result)
+                 VALUES (1, 'MULTIPLY', 7, 8, 56), (2, 'MULTIPLY', 7, 8, 56),
+                        (3, 'MULTIPLY', 7, 8, 56), (4, 'ADD', 1, 2, 3);
+             INSERT INTO answers (operation_id, user_answer, is_correct, time_spent_seconds)
+                 VALUES (1, 56, 1, 2.0), (2, 55, 0, 3.0), (3, 56, 1, 1.5), (4, 3, 1, 1.0);
+             INSERT INTO review_items (id, operation_id, repetitions, next_review_date, last_reviewed_date)
+                 VALUES (10, 1, 1, '2025-01-02T00:00:00+00:00', '2025-01-01T00:00:00+00:00'),
+                        (11, 2, 0, '2025-01-04T00:00:00+00:00', '2025-01-03T00:00:00+00:00'),
+                        (12, 3, 2, '2025-01-09T00:00:00+00:00', NULL),
+                        (13, 4, 0, '2025-01-02T00:00:00+00:00', NULL);
+             INSERT INTO review_log (operation_id, scheduler, quality, prev_repetitions,
+                                     prev_interval, prev_ease_factor, new_repetitions,
+                                     new_interval, new_ease_factor, next_review_date, reviewed_at)
+                 VALUES (3, 'sm2', 5, 0, 0, 2.5, 1, 1, 2.6, '2025-01-02T00:00:00+00:00',
+                         '2025-01-01T00:00:00+00:00');",
+        )
+        .unwrap();
+
+        migrations::runner().run(&mut conn).unwrap();
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM operations"), 2);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM answers WHERE operation_id = 1"),
+            3
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM review_log WHERE operation_id = 1"
+            ),
+            1
+        );
+        // The most recently reviewed item survives and belongs to the canonical operation
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM review_items"), 2);
+        assert_eq!(
+            count(&conn, "SELECT id FROM review_items WHERE operation_id = 1"),
+            11
+        );
+        assert!(
+            conn.execute(
+                "INSERT INTO operations (operation_type, operand1, operand2, result)
+                 VALUES ('MULTIPLY', 7, 8, 56)",
+                [],
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_users_migration_upgrades_database_with_orphan_rows() {
+        let path =
+            std::env::temp_dir().join(format!("memory_practice_orphans_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut conn = Connection::open(&path).unwrap();
+            migrations::runner()
+                .set_target(Target::Version(7))
+                .run(&mut conn)
+                .unwrap();
+            // The answer and review refer to a deck that no longer exists
+            conn.pragma_update(None, "foreign_keys", false).unwrap();
+            conn.execute_batch(
+                "INSERT INTO decks (id, status) VALUES (1, 'completed');
+                 INSERT INTO operations (id, operation_type, operand1, operand2, result, deck_id)
+                     VALUES (1, 'MULTIPLY', 7, 8, 56, 1);
+                 INSERT INTO answers (operation_id, user_answer, is_correct, time_spent_seconds,
+                                      deck_id)
+                     VALUES (1, 56, 1, 2.0, 1), (1, 48, 0, 2.0, 99);
+                 INSERT INTO review_log (operation_id, deck_id, scheduler, quality,
+                                         prev_repetitions, prev_interval, prev_ease_factor,
+                                         new_repetitions, new_interval, new_ease_factor,
+                                         next_review_date, reviewed_at)
+                     VALUES (1, 99, 'sm2', 5, 0, 0, 2.5, 1, 1, 2.6,
+                             '2025-01-02T00:00:00+00:00', '2025-01-01T00:00:00+00:00');",
+            )
+            .unwrap();
+        }
+
+        let conn = init_connection(&path.to_string_lossy()).unwrap();
+
+        assert_eq!(count(&conn, "PRAGMA foreign_keys"), 1);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM answers WHERE user_id = 1"),
+            2
+        );
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM review_log WHERE deck_id = 99"),
+            1
+        );
+        for table in ["decks", "answers", "review_log"] {
+            assert_eq!(
+                count(
+                    &conn,
+                    &format!(
+                        "SELECT COUNT(*) FROM pragma_foreign_key_list('{table}')
+                         WHERE \"table\" = 'users' AND \"from\" = 'user_id'"
+                    )
+                ),
+                1,
+                "{table}"
+            );
+        }
+        // New rows are checked against users(id)
+        assert!(
+            conn.execute("INSERT INTO decks (status) VALUES ('in_progress')", [])
+                .is_ok()
+        );
+        assert!(
+            conn.execute(
+                "INSERT INTO decks (status, user_id) VALUES ('in_progress', 7)",
+                []
+            )
+            .is_err()
+        );
+
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
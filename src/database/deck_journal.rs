@@ -0,0 +1,253 @@
+use crate::deck::DeckStatus;
+use crate::operations::{Operation, OperationType};
+use crate::row_factories::parse_datetime_with_timezone;
+use chrono::{DateTime, Utc};
+use log::debug;
+use rusqlite::{Connection, Result, params};
+
+/// A question of an unfinished deck, with its answer once given
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub operation: Operation,
+    /// Given answer and seconds taken
+    pub answer: Option<(i32, f64)>,
+    /// Whether the question was paused or left idle while it was answered
+    pub timing_interrupted: bool,
+    /// When the answer was given
+    pub answered_at: Option<DateTime<Utc>>,
+}
+
+/// An in-progress deck whose questions were journaled, so it can be continued after a crash
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumableDeck {
+    pub deck_id: i64,
+    pub created_at: DateTime<Utc>,
+    /// Questions in their original order
+    pub entries: Vec<JournalEntry>,
+}
+
+impl ResumableDeck {
+    pub fn answered(&self) -> usize {
+        self.entries.iter().filter(|e| e.answer.is_some()).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Journal of the questions and answers of decks still in progress
+pub struct DeckJournalRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> DeckJournalRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        DeckJournalRepository { conn, user_id }
+    }
+
+    /// Record the questions of a new deck in order
+    pub fn record_questions(&self, deck_id: i64, questions: &[Operation]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO deck_questions (deck_id, position, operation_id, operation_type,
+                                             operand1, operand2)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (position, question) in questions.iter().enumerate() {
+                stmt.execute(params![
+                    deck_id,
+                    position as i64,
+                    question.id,
+                    question.operation_type.as_str(),
+                    question.operand1,
+                    question.operand2,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        debug!(
+            "Journaled {} questions for deck {}",
+            questions.len(),
+            deck_id
+        );
+        Ok(())
+    }
+
+    /// Record the answer to the question at `position`
+    pub fn record_answer(
+        &self,
+        deck_id: i64,
+        position: usize,
+        user_answer: i32,
+        time_spent: f64,
+        timing_interrupted: bool,
+        answered_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE deck_questions
+             SET user_answer = ?1, time_spent_seconds = ?2, timing_interrupted = ?3,
+                 answered_at = ?4
+             WHERE deck_id = ?5 AND position = ?6",
+            params![
+                user_answer,
+                time_spent,
+                timing_interrupted as i32,
+                answered_at.to_rfc3339(),
+                deck_id,
+                position as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Journaled questions of a deck, in order
+    pub fn load(&self, deck_id: i64) -> Result<Vec<JournalEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT operation_id, operation_type, operand1, operand2, user_answer,
+                    time_spent_seconds, timing_interrupted, answered_at
+             FROM deck_questions
+             WHERE deck_id = ?1
+             ORDER BY position",
+        )?;
+        let rows = stmt.query_map([deck_id], |row| {
+            let operation_type: String = row.get(1)?;
+            let operation_type = OperationType::from_str(&operation_type).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    format!("unknown operation type '{}'", operation_type).into(),
+                )
+            })?;
+            let mut operation = Operation::new(operation_type, row.get(2)?, row.get(3)?);
+            operation.id = row.get(0)?;
+
+            let user_answer: Option<i32> = row.get(4)?;
+            let time_spent: Option<f64> = row.get(5)?;
+            Ok(JournalEntry {
+                operation,
+                answer: user_answer.map(|answer| (answer, time_spent.unwrap_or(0.0))),
+                timing_interrupted: row.get::<_, i32>(6)? != 0,
+                answered_at: row
+                    .get::<_, Option<String>>(7)?
+                    .map(|at| parse_datetime_with_timezone(&at)),
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// The most recent in-progress deck with journaled questions
+    pub fn find_resumable(&self) -> Result<Option<ResumableDeck>> {
+        let deck = self
+            .conn
+            .query_row(
+                "SELECT d.id, d.created_at
+                 FROM decks d
+                 WHERE d.status = ?1
+                   AND d.user_id = ?2
+                   AND EXISTS (SELECT 1 FROM deck_questions q WHERE q.deck_id = d.id)
+                 ORDER BY d.created_at DESC, d.id DESC
+                 LIMIT 1",
+                params![DeckStatus::InProgress.as_str(), self.user_id],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+
+        match deck {
+            Some((deck_id, created_at)) => Ok(Some(ResumableDeck {
+                deck_id,
+                created_at: parse_datetime_with_timezone(&created_at),
+                entries: self.load(deck_id)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the journal of a deck once its results are saved
+    pub fn clear(&self, deck_id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM deck_questions WHERE deck_id = ?1", [deck_id])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    fn questions() -> Vec<Operation> {
+        vec![
+            Operation::new(OperationType::Multiplication, 7, 8),
+            Operation::new(OperationType::Subtraction, 9, 4),
+            Operation::new(OperationType::Addition, 3, 5),
+        ]
+    }
+
+    #[test]
+    fn test_journal_round_trip_keeps_order_and_answers() {
+        let conn = create_test_db();
+        let deck_id = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now))
+            .create()
+            .unwrap();
+        let repo = DeckJournalRepository::new(&conn, DEFAULT_USER_ID);
+        let answered_at = Utc::now() - chrono::Duration::days(1);
+        repo.record_questions(deck_id, &questions()).unwrap();
+        repo.record_answer(deck_id, 0, 56, 2.5, false, answered_at)
+            .unwrap();
+        repo.record_answer(deck_id, 2, 8, 4.0, true, Utc::now())
+            .unwrap();
+
+        let entries = repo.load(deck_id).unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| e.operation.clone())
+                .collect::<Vec<_>>(),
+            questions()
+        );
+        assert_eq!(entries[0].answer, Some((56, 2.5)));
+        assert_eq!(entries[1].answer, None);
+        assert_eq!(entries[0].answered_at, Some(answered_at));
+        assert_eq!(entries[1].answered_at, None);
+        assert!(!entries[0].timing_interrupted);
+        assert!(entries[2].timing_interrupted);
+
+        let deck = repo.find_resumable().unwrap().expect("Deck is resumable");
+        assert_eq!(deck.deck_id, deck_id);
+        assert_eq!((deck.answered(), deck.total()), (2, 3));
+    }
+
+    #[test]
+    fn test_only_in_progress_journaled_decks_are_resumable() {
+        let conn = create_test_db();
+        let decks = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let repo = DeckJournalRepository::new(&conn, DEFAULT_USER_ID);
+
+        // In progress but never journaled, e.g. created before journaling existed
+        decks.create().unwrap();
+        assert_eq!(repo.find_resumable().unwrap(), None);
+
+        let abandoned = decks.create().unwrap();
+        repo.record_questions(abandoned, &questions()).unwrap();
+        decks.abandon(abandoned).unwrap();
+        assert_eq!(repo.find_resumable().unwrap(), None);
+
+        let cleared = decks.create().unwrap();
+        repo.record_questions(cleared, &questions()).unwrap();
+        repo.clear(cleared).unwrap();
+        assert_eq!(repo.find_resumable().unwrap(), None);
+    }
+}
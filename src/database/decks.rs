@@ -6,24 +6,27 @@ use rusqlite::{Connection, Result, params};
 pub struct DecksRepository<'a> {
     conn: &'a Connection,
     get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    user_id: i64,
 }
 
 impl<'a> DecksRepository<'a> {
     pub fn new(
         conn: &'a Connection,
+        user_id: i64,
         get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
     ) -> Self {
         DecksRepository {
             conn,
             get_current_time,
+            user_id,
         }
     }
 
     pub fn create(&self) -> Result<i64> {
         let now_utc = (self.get_current_time)().to_rfc3339();
         self.conn.execute(
-            "INSERT INTO decks (created_at, status) VALUES (?1, ?2)",
-            params![now_utc, DeckStatus::InProgress.as_str()],
+            "INSERT INTO decks (created_at, status, user_id) VALUES (?1, ?2, ?3)",
+            params![now_utc, DeckStatus::InProgress.as_str(), self.user_id],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
@@ -33,10 +36,10 @@ impl<'a> DecksRepository<'a> {
             "SELECT id, created_at, completed_at, status, total_questions,
                     correct_answers, incorrect_answers, total_time_seconds,
                     average_time_seconds, accuracy_percentage
-             FROM decks WHERE id = ?1",
+             FROM decks WHERE id = ?1 AND user_id = ?2",
         )?;
 
-        let mut rows = stmt.query([deck_id])?;
+        let mut rows = stmt.query([deck_id, self.user_id])?;
 
         if let Some(row) = rows.next()? {
             Ok(Some(DeckRowFactory::from_row(row)?))
@@ -54,7 +57,7 @@ impl<'a> DecksRepository<'a> {
                 total_time_seconds = ?4,
                 average_time_seconds = ?5,
                 accuracy_percentage = ?6
-             WHERE id = ?7",
+             WHERE id = ?7 AND user_id = ?8",
             params![
                 summary.total_questions,
                 summary.correct_answers,
@@ -62,7 +65,8 @@ impl<'a> DecksRepository<'a> {
                 summary.total_time_seconds,
                 summary.average_time_seconds,
                 summary.accuracy_percentage,
-                deck_id
+                deck_id,
+                self.user_id
             ],
         )?;
         Ok(())
@@ -71,16 +75,21 @@ impl<'a> DecksRepository<'a> {
     pub fn complete(&self, deck_id: i64) -> Result<()> {
         let now_utc = (self.get_current_time)().to_rfc3339();
         self.conn.execute(
-            "UPDATE decks SET status = ?1, completed_at = ?3 WHERE id = ?2",
-            params![DeckStatus::Completed.as_str(), deck_id, now_utc],
+            "UPDATE decks SET status = ?1, completed_at = ?3 WHERE id = ?2 AND user_id = ?4",
+            params![
+                DeckStatus::Completed.as_str(),
+                deck_id,
+                now_utc,
+                self.user_id
+            ],
         )?;
         Ok(())
     }
 
     pub fn abandon(&self, deck_id: i64) -> Result<()> {
         self.conn.execute(
-            "UPDATE decks SET status = ?1 WHERE id = ?2",
-            params![DeckStatus::Abandoned.as_str(), deck_id],
+            "UPDATE decks SET status = ?1 WHERE id = ?2 AND user_id = ?3",
+            params![DeckStatus::Abandoned.as_str(), deck_id, self.user_id],
         )?;
         Ok(())
     }
@@ -91,11 +100,12 @@ impl<'a> DecksRepository<'a> {
                     correct_answers, incorrect_answers, total_time_seconds,
                     average_time_seconds, accuracy_percentage
              FROM decks
+             WHERE user_id = ?2
              ORDER BY created_at DESC
              LIMIT ?1",
         )?;
 
-        let rows = stmt.query_map([limit], DeckRowFactory::from_row)?;
+        let rows = stmt.query_map(params![limit, self.user_id], DeckRowFactory::from_row)?;
 
         let mut decks = Vec::new();
         for deck_result in rows {
@@ -104,10 +114,39 @@ impl<'a> DecksRepository<'a> {
         Ok(decks)
     }
 
+    /// Decks completed at or after `start` and before `end`, oldest first
+    pub fn get_completed_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Deck>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, created_at, completed_at, status, total_questions,
+                    correct_answers, incorrect_answers, total_time_seconds,
+                    average_time_seconds, accuracy_percentage
+             FROM decks
+             WHERE status = ?1 AND completed_at >= ?2 AND completed_at < ?3 AND user_id = ?4
+             ORDER BY completed_at ASC",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                DeckStatus::Completed.as_str(),
+                start.to_rfc3339(),
+                end.to_rfc3339(),
+                self.user_id
+            ],
+            DeckRowFactory::from_row,
+        )?;
+        rows.collect()
+    }
+
     pub fn count(&self) -> Result<i64> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM decks", [], |row| row.get(0))?;
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM decks WHERE user_id = ?1",
+            [self.user_id],
+            |row| row.get(0),
+        )?;
         Ok(count)
     }
 }
@@ -116,6 +155,7 @@ impl<'a> DecksRepository<'a> {
 mod tests {
     use super::*;
     use crate::database::connection::init_connection;
+    use crate::database::users::DEFAULT_USER_ID;
 
     fn create_test_db() -> Connection {
         init_connection(":memory:").expect("Failed to create test database")
@@ -124,7 +164,7 @@ mod tests {
     #[test]
     fn test_create_deck() {
         let conn = create_test_db();
-        let repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let deck_id = repo.create().unwrap();
         assert_eq!(deck_id, 1);
         assert_eq!(repo.count().unwrap(), 1);
@@ -133,7 +173,7 @@ mod tests {
     #[test]
     fn test_get_deck() {
         let conn = create_test_db();
-        let repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let deck_id = repo.create().unwrap();
 
         let deck = repo.get(deck_id).unwrap().unwrap();
@@ -145,7 +185,7 @@ mod tests {
     #[test]
     fn test_complete_deck() {
         let conn = create_test_db();
-        let repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let deck_id = repo.create().unwrap();
 
         repo.complete(deck_id).unwrap();
@@ -158,7 +198,7 @@ mod tests {
     #[test]
     fn test_abandon_deck() {
         let conn = create_test_db();
-        let repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let deck_id = repo.create().unwrap();
 
         repo.abandon(deck_id).unwrap();
@@ -170,7 +210,7 @@ mod tests {
     #[test]
     fn test_update_deck_summary() {
         let conn = create_test_db();
-        let repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let deck_id = repo.create().unwrap();
 
         let summary = crate::deck::DeckSummary {
@@ -196,7 +236,7 @@ mod tests {
     #[test]
     fn test_get_recent_decks() {
         let conn = create_test_db();
-        let repo = DecksRepository::new(&conn, Box::new(|| Utc::now()));
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let _deck1 = repo.create().unwrap();
         let _deck2 = repo.create().unwrap();
         let _deck3 = repo.create().unwrap();
@@ -211,4 +251,26 @@ mod tests {
         // we should at least get 2 different decks
         assert_ne!(recent[0].id, recent[1].id);
     }
+
+    #[test]
+    fn test_get_completed_between() {
+        let conn = create_test_db();
+        let now = Utc::now();
+        for days_ago in [10, 3, 1] {
+            let when = now - chrono::Duration::days(days_ago);
+            let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(move || when));
+            let deck_id = repo.create().unwrap();
+            repo.complete(deck_id).unwrap();
+        }
+        let repo = DecksRepository::new(&conn, DEFAULT_USER_ID, Box::new(move || now));
+        repo.create().unwrap();
+
+        let decks = repo
+            .get_completed_between(now - chrono::Duration::days(7), now)
+            .unwrap();
+        assert_eq!(
+            decks.iter().map(|deck| deck.id).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
 }
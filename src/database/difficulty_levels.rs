@@ -0,0 +1,112 @@
+use crate::row_factories::parse_datetime_with_timezone;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Result, params};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifficultyLevelRecord {
+    pub operation_type: String,
+    pub level: i32,
+    /// When the level last changed; answers before this belong to the previous level
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct DifficultyLevelsRepository<'a> {
+    conn: &'a Connection,
+    get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    user_id: i64,
+}
+
+impl<'a> DifficultyLevelsRepository<'a> {
+    pub fn new(
+        conn: &'a Connection,
+        user_id: i64,
+        get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    ) -> Self {
+        DifficultyLevelsRepository {
+            conn,
+            get_current_time,
+            user_id,
+        }
+    }
+
+    pub fn get(&self, operation_type: &str) -> Result<Option<DifficultyLevelRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT operation_type, level, updated_at FROM difficulty_levels
+             WHERE operation_type = ?1 AND user_id = ?2",
+        )?;
+
+        let mut rows = stmt.query(params![operation_type, self.user_id])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_all(&self) -> Result<Vec<DifficultyLevelRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT operation_type, level, updated_at FROM difficulty_levels
+             WHERE user_id = ?1
+             ORDER BY operation_type",
+        )?;
+
+        let rows = stmt.query_map([self.user_id], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// Store the level for an operation type, stamping it with the current time
+    pub fn set(&self, operation_type: &str, level: i32) -> Result<()> {
+        let now_utc = (self.get_current_time)().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO difficulty_levels (user_id, operation_type, level, updated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(user_id, operation_type) DO UPDATE SET level = excluded.level,
+                                                                updated_at = excluded.updated_at",
+            params![self.user_id, operation_type, level, now_utc],
+        )?;
+        Ok(())
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<DifficultyLevelRecord> {
+        Ok(DifficultyLevelRecord {
+            operation_type: row.get(0)?,
+            level: row.get(1)?,
+            updated_at: parse_datetime_with_timezone(&row.get::<_, String>(2)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::connection::init_connection;
+    use crate::database::users::DEFAULT_USER_ID;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    #[test]
+    fn test_get_missing_level() {
+        let conn = create_test_db();
+        let repo = DifficultyLevelsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        assert_eq!(repo.get("ADD").unwrap(), None);
+        assert!(repo.get_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_and_update_level() {
+        let conn = create_test_db();
+        let repo = DifficultyLevelsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+
+        repo.set("ADD", 2).unwrap();
+        repo.set("MULTIPLY", 1).unwrap();
+        repo.set("ADD", 3).unwrap();
+
+        assert_eq!(repo.get("ADD").unwrap().unwrap().level, 3);
+        let all = repo.get_all().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].operation_type, "MULTIPLY");
+    }
+}
@@ -1,25 +1,38 @@
 pub mod analytics;
 pub mod answers;
 pub mod connection;
+pub mod deck_journal;
 pub mod decks;
+pub mod difficulty_levels;
 pub mod operations;
 pub mod review_items;
+pub mod review_log;
+pub mod settings;
+pub mod users;
 
 use crate::date_provider::{DateProvider, SystemDateProvider};
 use chrono::{DateTime, Utc};
 use rusqlite::{Connection, Result};
+use std::cell::Cell;
 use std::sync::Arc;
 
 pub use analytics::Analytics;
-pub use answers::{AnswerRecord, AnswersRepository};
+pub use answers::{AnswerRecord, AnswersRepository, NewAnswer};
+pub use deck_journal::{DeckJournalRepository, JournalEntry, ResumableDeck};
 pub use decks::DecksRepository;
+pub use difficulty_levels::DifficultyLevelsRepository;
 pub use operations::{OperationRecord, OperationsRepository};
 pub use review_items::ReviewItemsRepository;
+pub use review_log::{ReviewLogRecord, ReviewLogRepository};
+pub use settings::SettingsRepository;
+pub use users::{DEFAULT_USER_ID, User, UsersRepository};
 
 /// Main Database struct providing access to all repositories
 pub struct Database {
     pub conn: Connection,
     date_provider: Arc<dyn DateProvider>,
+    /// Profile whose history is read and written
+    user_id: Cell<i64>,
 }
 
 impl Database {
@@ -36,6 +49,7 @@ impl Database {
         Ok(Database {
             conn,
             date_provider,
+            user_id: Cell::new(DEFAULT_USER_ID),
         })
     }
 
@@ -43,6 +57,16 @@ impl Database {
     pub fn get_current_time(&self) -> DateTime<Utc> {
         self.date_provider.get_current_time()
     }
+
+    /// Id of the active profile
+    pub fn user_id(&self) -> i64 {
+        self.user_id.get()
+    }
+
+    /// Switch the active profile; repositories created afterwards are scoped to it
+    pub fn set_user(&self, user_id: i64) {
+        self.user_id.set(user_id);
+    }
 }
 
 #[cfg(test)]
@@ -62,7 +86,7 @@ mod tests {
         // Verify tables were created by checking counts
         let repo = OperationsRepository::new(&db.conn);
         assert_eq!(repo.count().unwrap(), 0);
-        let repo1 = AnswersRepository::new(&db.conn);
+        let repo1 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         assert_eq!(repo1.count().unwrap(), 0);
     }
 
@@ -101,12 +125,12 @@ mod tests {
         let repo = OperationsRepository::new(&db.conn);
         let op_id = repo.insert("MULTIPLY", 7, 8, 56, None).unwrap();
 
-        let repo1 = AnswersRepository::new(&db.conn);
+        let repo1 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         repo1.insert(op_id, 56, true, 2.5, None).unwrap();
-        let repo2 = AnswersRepository::new(&db.conn);
+        let repo2 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         assert_eq!(repo2.count().unwrap(), 1);
 
-        let repo2 = AnswersRepository::new(&db.conn);
+        let repo2 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         let answer = repo2.get(1).unwrap().unwrap();
         assert_eq!(answer.operation_id, op_id);
         assert_eq!(answer.user_answer, 56);
@@ -118,10 +142,14 @@ mod tests {
     fn test_create_deck() {
         let db = create_test_db();
         let current_time = db.get_current_time();
-        let repo = DecksRepository::new(&db.conn, Box::new(move || current_time));
+        let repo = DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(move || current_time));
         let deck_id = repo.create().unwrap();
         assert_eq!(deck_id, 1);
-        let repo1 = DecksRepository::new(&db.conn, Box::new(|| db.get_current_time()));
+        let repo1 = DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        );
         assert_eq!(repo1.count().unwrap(), 1);
     }
 
@@ -129,19 +157,19 @@ mod tests {
     fn test_operations_with_deck_id() {
         let db = create_test_db();
         let current_time = db.get_current_time();
-        let repo = DecksRepository::new(&db.conn, Box::new(move || current_time));
+        let repo = DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(move || current_time));
         let deck_id = repo.create().unwrap();
 
         let deck_id1 = Some(deck_id);
         let repo = OperationsRepository::new(&db.conn);
         let op_id = repo.insert("ADD", 5, 3, 8, deck_id1).unwrap();
         let deck_id2 = Some(deck_id);
-        let repo1 = AnswersRepository::new(&db.conn);
+        let repo1 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         repo1.insert(op_id, 8, true, 2.0, deck_id2).unwrap();
 
         let repo1 = OperationsRepository::new(&db.conn);
         assert_eq!(repo1.count().unwrap(), 1);
-        let repo2 = AnswersRepository::new(&db.conn);
+        let repo2 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         assert_eq!(repo2.count().unwrap(), 1);
     }
 
@@ -154,9 +182,14 @@ mod tests {
             .build();
         let db = DatabaseFactory::create(db_config).unwrap();
         let current_time = db.get_current_time();
-        let decks_repo = DecksRepository::new(&db.conn, Box::new(move || current_time));
+        let decks_repo =
+            DecksRepository::new(&db.conn, DEFAULT_USER_ID, Box::new(move || current_time));
         let date_provider_fn = Box::new(move || current_time);
-        let answers_repo = AnswersRepository::new_with_date_provider(&db.conn, &*date_provider_fn);
+        let answers_repo = AnswersRepository::new_with_date_provider(
+            &db.conn,
+            DEFAULT_USER_ID,
+            &*date_provider_fn,
+        );
         let operations_repo = OperationsRepository::new(&db.conn);
 
         let deck_id1 = Some(decks_repo.create().unwrap());
@@ -164,9 +197,9 @@ mod tests {
         let deck_id2 = Some(decks_repo.create().unwrap());
         answers_repo.insert(op_id, 5, true, 1.0, deck_id2).unwrap();
 
-        let now = Utc::now();
-        let analytics = Analytics::new(&db.conn);
-        let days_with_answers = StreakRepository::new(analytics.conn)
+        let now = db.get_current_time();
+        let analytics = Analytics::new(&db.conn, DEFAULT_USER_ID);
+        let days_with_answers = StreakRepository::new(analytics.conn, DEFAULT_USER_ID)
             .get_days_with_answers(now)
             .unwrap();
         assert_eq!(days_with_answers, vec!["2025-11-12"]);
@@ -54,6 +54,39 @@ impl<'a> OperationsRepository<'a> {
         }
     }
 
+    /// Find the operation stored for a fact (type and operands)
+    ///
+    /// With `either_order`, a row with the operands swapped also matches; the oldest match wins.
+    pub fn find(
+        &self,
+        operation_type: &str,
+        operand1: i32,
+        operand2: i32,
+        either_order: bool,
+    ) -> Result<Option<OperationRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operation_type, operand1, operand2, result FROM operations
+             WHERE operation_type = ?1
+               AND ((operand1 = ?2 AND operand2 = ?3) OR (?4 AND operand1 = ?3 AND operand2 = ?2))
+             ORDER BY id ASC
+             LIMIT 1",
+        )?;
+
+        let mut rows = stmt.query(params![operation_type, operand1, operand2, either_order])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(OperationRecord {
+                id: row.get(0)?,
+                operation_type: row.get(1)?,
+                operand1: row.get(2)?,
+                operand2: row.get(3)?,
+                result: row.get(4)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn count(&self) -> Result<i64> {
         let count: i64 = self
             .conn
@@ -104,4 +137,25 @@ mod tests {
         let result = repo.get(999).unwrap();
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_insert_duplicate_fact_is_rejected() {
+        let conn = create_test_db();
+        let repo = OperationsRepository::new(&conn);
+        repo.insert("MULTIPLY", 7, 8, 56, None).unwrap();
+        assert!(repo.insert("MULTIPLY", 7, 8, 56, None).is_err());
+        assert!(repo.insert("MULTIPLY", 8, 7, 56, None).is_ok());
+    }
+
+    #[test]
+    fn test_find_fact() {
+        let conn = create_test_db();
+        let repo = OperationsRepository::new(&conn);
+        let id = repo.insert("MULTIPLY", 7, 8, 56, None).unwrap();
+
+        assert_eq!(repo.find("MULTIPLY", 7, 8, false).unwrap().unwrap().id, id);
+        assert!(repo.find("MULTIPLY", 8, 7, false).unwrap().is_none());
+        assert_eq!(repo.find("MULTIPLY", 8, 7, true).unwrap().unwrap().id, id);
+        assert!(repo.find("ADD", 7, 8, true).unwrap().is_none());
+    }
 }
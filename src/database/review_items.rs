@@ -7,11 +7,21 @@ use rusqlite::{Connection, Result, params};
 
 pub struct ReviewItemsRepository<'a> {
     conn: &'a Connection,
+    get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    user_id: i64,
 }
 
 impl<'a> ReviewItemsRepository<'a> {
-    pub fn new(conn: &'a Connection) -> Self {
-        ReviewItemsRepository { conn }
+    pub fn new(
+        conn: &'a Connection,
+        user_id: i64,
+        get_current_time: Box<dyn Fn() -> DateTime<Utc> + 'a>,
+    ) -> Self {
+        ReviewItemsRepository {
+            conn,
+            get_current_time,
+            user_id,
+        }
     }
 
     pub fn insert(&self, operation_id: i64, next_review_date: DateTime<Utc>) -> Result<i64> {
@@ -19,12 +29,41 @@ impl<'a> ReviewItemsRepository<'a> {
         debug!(
             "Creating new review item for operation_id={}, next review: {}",
             operation_id,
-            format_time_difference(Utc::now(), next_review_date)
+            format_time_difference((self.get_current_time)(), next_review_date)
         );
         self.conn.execute(
-            "INSERT INTO review_items (operation_id, next_review_date)
-             VALUES (?1, ?2)",
-            params![operation_id, next_review_str],
+            "INSERT INTO review_items (operation_id, next_review_date, user_id)
+             VALUES (?1, ?2, ?3)",
+            params![operation_id, next_review_str, self.user_id],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Insert a review item with its full scheduling state in a single statement
+    pub fn insert_item(&self, item: &ReviewItem) -> Result<i64> {
+        debug!(
+            "Creating review item for operation_id={}: reps={}, interval={} days, next review: {}",
+            item.operation_id,
+            item.repetitions,
+            item.interval,
+            format_time_difference((self.get_current_time)(), item.next_review_date)
+        );
+        self.conn.execute(
+            "INSERT INTO review_items (operation_id, repetitions, interval, ease_factor,
+                                       next_review_date, last_reviewed_date,
+                                       fsrs_stability, fsrs_difficulty, user_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                item.operation_id,
+                item.repetitions,
+                item.interval,
+                item.ease_factor,
+                item.next_review_date.to_rfc3339(),
+                item.last_reviewed_date.map(|d| d.to_rfc3339()),
+                item.fsrs_stability,
+                item.fsrs_difficulty,
+                self.user_id,
+            ],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
@@ -39,21 +78,25 @@ impl<'a> ReviewItemsRepository<'a> {
             item.repetitions,
             item.interval,
             item.ease_factor,
-            format_time_difference(Utc::now(), item.next_review_date)
+            format_time_difference((self.get_current_time)(), item.next_review_date)
         );
 
         self.conn.execute(
             "UPDATE review_items
              SET repetitions = ?1, interval = ?2, ease_factor = ?3,
-                 next_review_date = ?4, last_reviewed_date = ?5
-             WHERE id = ?6",
+                 next_review_date = ?4, last_reviewed_date = ?5,
+                 fsrs_stability = ?6, fsrs_difficulty = ?7
+             WHERE id = ?8 AND user_id = ?9",
             params![
                 item.repetitions,
                 item.interval,
                 item.ease_factor,
                 next_review_str,
                 last_reviewed_str,
-                item.id
+                item.fsrs_stability,
+                item.fsrs_difficulty,
+                item.id,
+                self.user_id
             ],
         )?;
         Ok(())
@@ -62,11 +105,11 @@ impl<'a> ReviewItemsRepository<'a> {
     pub fn get(&self, operation_id: i64) -> Result<Option<ReviewItem>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, operation_id, repetitions, interval, ease_factor,
-                    next_review_date, last_reviewed_date
-             FROM review_items WHERE operation_id = ?1",
+                    next_review_date, last_reviewed_date, fsrs_stability, fsrs_difficulty
+             FROM review_items WHERE operation_id = ?1 AND user_id = ?2",
         )?;
 
-        let mut rows = stmt.query([operation_id])?;
+        let mut rows = stmt.query([operation_id, self.user_id])?;
 
         if let Some(row) = rows.next()? {
             Ok(Some(ReviewItemRowFactory::from_row(row)?))
@@ -79,13 +122,16 @@ impl<'a> ReviewItemsRepository<'a> {
         let before_str = before_date.to_rfc3339();
         let mut stmt = self.conn.prepare(
             "SELECT id, operation_id, repetitions, interval, ease_factor,
-                    next_review_date, last_reviewed_date
+                    next_review_date, last_reviewed_date, fsrs_stability, fsrs_difficulty
              FROM review_items
-             WHERE next_review_date <= ?1
+             WHERE next_review_date <= ?1 AND user_id = ?2
              ORDER BY next_review_date ASC",
         )?;
 
-        let items = stmt.query_map([&before_str], ReviewItemRowFactory::from_row)?;
+        let items = stmt.query_map(
+            params![before_str, self.user_id],
+            ReviewItemRowFactory::from_row,
+        )?;
 
         let mut result = Vec::new();
         for item in items {
@@ -96,11 +142,24 @@ impl<'a> ReviewItemsRepository<'a> {
         Ok(result)
     }
 
+    pub fn get_all(&self) -> Result<Vec<ReviewItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operation_id, repetitions, interval, ease_factor,
+                    next_review_date, last_reviewed_date, fsrs_stability, fsrs_difficulty
+             FROM review_items
+             WHERE user_id = ?1
+             ORDER BY id ASC",
+        )?;
+
+        let items = stmt.query_map([self.user_id], ReviewItemRowFactory::from_row)?;
+        items.collect()
+    }
+
     pub fn count_due(&self, before_date: DateTime<Utc>) -> Result<i64> {
         let before_str = before_date.to_rfc3339();
         let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM review_items WHERE next_review_date <= ?1",
-            [&before_str],
+            "SELECT COUNT(*) FROM review_items WHERE next_review_date <= ?1 AND user_id = ?2",
+            params![before_str, self.user_id],
             |row| row.get(0),
         )?;
         Ok(count)
@@ -112,6 +171,7 @@ mod tests {
     use super::*;
     use crate::database::connection::init_connection;
     use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
 
     fn create_test_db() -> Connection {
         init_connection(":memory:").expect("Failed to create test database")
@@ -121,7 +181,7 @@ mod tests {
     fn test_insert_review_item() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
 
         let op_id = ops_repo.insert("ADD", 2, 3, 5, None).unwrap();
         let now = chrono::Utc::now();
@@ -134,7 +194,7 @@ mod tests {
     fn test_get_review_item() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
 
         let op_id = ops_repo.insert("ADD", 2, 3, 5, None).unwrap();
         let now = chrono::Utc::now();
@@ -153,7 +213,7 @@ mod tests {
     fn test_update_review_item() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
 
         let op_id = ops_repo.insert("ADD", 2, 3, 5, None).unwrap();
         let now = chrono::Utc::now();
@@ -171,13 +231,62 @@ mod tests {
         assert_eq!(updated.repetitions, 1);
         assert_eq!(updated.interval, 3);
         assert_eq!(updated.ease_factor, 2.7);
+        assert_eq!(updated.fsrs_stability, None);
+    }
+
+    #[test]
+    fn test_insert_item_stores_full_state() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+
+        let op_id = ops_repo.insert("MULTIPLY", 6, 7, 42, None).unwrap();
+        let now = chrono::Utc::now();
+        let item = ReviewItem {
+            id: None,
+            operation_id: op_id,
+            repetitions: 1,
+            interval: 1,
+            ease_factor: 2.6,
+            next_review_date: now + chrono::Duration::days(1),
+            last_reviewed_date: Some(now),
+            fsrs_stability: Some(3.2),
+            fsrs_difficulty: Some(5.1),
+        };
+        let id = review_repo.insert_item(&item).unwrap();
+
+        let stored = review_repo.get(op_id).unwrap().unwrap();
+        assert_eq!(stored.id, Some(id));
+        assert_eq!(stored.repetitions, 1);
+        assert_eq!(stored.ease_factor, 2.6);
+        assert_eq!(stored.fsrs_stability, Some(3.2));
+        assert!(stored.last_reviewed_date.is_some());
+    }
+
+    #[test]
+    fn test_update_review_item_fsrs_state() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+
+        let op_id = ops_repo.insert("ADD", 2, 3, 5, None).unwrap();
+        review_repo.insert(op_id, chrono::Utc::now()).unwrap();
+
+        let mut item = review_repo.get(op_id).unwrap().unwrap();
+        item.fsrs_stability = Some(3.7);
+        item.fsrs_difficulty = Some(5.2);
+        review_repo.update(&item).unwrap();
+
+        let updated = review_repo.get(op_id).unwrap().unwrap();
+        assert_eq!(updated.fsrs_stability, Some(3.7));
+        assert_eq!(updated.fsrs_difficulty, Some(5.2));
     }
 
     #[test]
     fn test_get_due_reviews() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
 
         let now = chrono::Utc::now();
         let past = now - chrono::Duration::days(1);
@@ -208,7 +317,7 @@ mod tests {
     fn test_count_due_reviews() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
 
         let now = chrono::Utc::now();
         let past = now - chrono::Duration::days(1);
@@ -226,10 +335,59 @@ mod tests {
         assert_eq!(count, 2);
     }
 
+    #[test]
+    fn test_profiles_have_separate_schedules() {
+        let conn = create_test_db();
+        let op_id = OperationsRepository::new(&conn)
+            .insert("ADD", 2, 3, 5, None)
+            .unwrap();
+        let alice = crate::database::UsersRepository::new(&conn)
+            .create("Alice")
+            .unwrap();
+        let default_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+        let alice_repo = ReviewItemsRepository::new(&conn, alice, Box::new(Utc::now));
+
+        let now = chrono::Utc::now();
+        default_repo.insert(op_id, now).unwrap();
+        assert_eq!(alice_repo.get(op_id).unwrap(), None);
+        assert_eq!(alice_repo.count_due(now).unwrap(), 0);
+
+        // The same fact can be scheduled independently for another profile
+        alice_repo
+            .insert(op_id, now + chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(default_repo.count_due(now).unwrap(), 1);
+        assert_eq!(alice_repo.count_due(now).unwrap(), 0);
+        assert_eq!(alice_repo.get_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_all_review_items() {
+        let conn = create_test_db();
+        let ops_repo = OperationsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
+
+        let now = chrono::Utc::now();
+        let op_id1 = ops_repo.insert("ADD", 2, 3, 5, None).unwrap();
+        let op_id2 = ops_repo.insert("ADD", 4, 5, 9, None).unwrap();
+        review_repo.insert(op_id1, now).unwrap();
+        review_repo
+            .insert(op_id2, now + chrono::Duration::days(5))
+            .unwrap();
+
+        let all: Vec<i64> = review_repo
+            .get_all()
+            .unwrap()
+            .iter()
+            .map(|item| item.operation_id)
+            .collect();
+        assert_eq!(all, vec![op_id1, op_id2]);
+    }
+
     #[test]
     fn test_get_nonexistent_review_item() {
         let conn = create_test_db();
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
         let result = review_repo.get(999).unwrap();
         assert!(result.is_none());
     }
@@ -238,7 +396,7 @@ mod tests {
     fn test_review_item_with_multiple_operations() {
         let conn = create_test_db();
         let ops_repo = OperationsRepository::new(&conn);
-        let review_repo = ReviewItemsRepository::new(&conn);
+        let review_repo = ReviewItemsRepository::new(&conn, DEFAULT_USER_ID, Box::new(Utc::now));
 
         let now = chrono::Utc::now();
 
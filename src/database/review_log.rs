@@ -0,0 +1,245 @@
+use crate::row_factories::parse_datetime_with_timezone;
+use crate::spaced_repetition::ReviewItem;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Result, params};
+
+/// A single scheduling decision: the review item state before and after a graded answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewLogRecord {
+    pub id: Option<i64>,
+    pub operation_id: i64,
+    pub deck_id: Option<i64>,
+    /// Scheduler that made the decision (e.g. "sm2", "fsrs")
+    pub scheduler: String,
+    /// Quality grade (0-5) the answer was given
+    pub quality: i32,
+    pub prev_repetitions: i32,
+    pub prev_interval: i32,
+    pub prev_ease_factor: f32,
+    pub prev_stability: Option<f64>,
+    pub prev_difficulty: Option<f64>,
+    pub new_repetitions: i32,
+    pub new_interval: i32,
+    pub new_ease_factor: f32,
+    pub new_stability: Option<f64>,
+    pub new_difficulty: Option<f64>,
+    pub next_review_date: DateTime<Utc>,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+impl ReviewLogRecord {
+    /// Builds a log entry from the review item before and after scheduling
+    pub fn from_items(
+        scheduler: &str,
+        quality: i32,
+        before: &ReviewItem,
+        after: &ReviewItem,
+        deck_id: Option<i64>,
+        reviewed_at: DateTime<Utc>,
+    ) -> Self {
+        ReviewLogRecord {
+            id: None,
+            operation_id: after.operation_id,
+            deck_id,
+            scheduler: scheduler.to_string(),
+            quality,
+            prev_repetitions: before.repetitions,
+            prev_interval: before.interval,
+            prev_ease_factor: before.ease_factor,
+            prev_stability: before.fsrs_stability,
+            prev_difficulty: before.fsrs_difficulty,
+            new_repetitions: after.repetitions,
+            new_interval: after.interval,
+            new_ease_factor: after.ease_factor,
+            new_stability: after.fsrs_stability,
+            new_difficulty: after.fsrs_difficulty,
+            next_review_date: after.next_review_date,
+            reviewed_at,
+        }
+    }
+}
+
+const SELECT_COLUMNS: &str = "SELECT id, operation_id, deck_id, scheduler, quality,
+        prev_repetitions, prev_interval, prev_ease_factor, prev_stability, prev_difficulty,
+        new_repetitions, new_interval, new_ease_factor, new_stability, new_difficulty,
+        next_review_date, reviewed_at
+ FROM review_log";
+
+pub struct ReviewLogRepository<'a> {
+    conn: &'a Connection,
+    user_id: i64,
+}
+
+impl<'a> ReviewLogRepository<'a> {
+    pub fn new(conn: &'a Connection, user_id: i64) -> Self {
+        ReviewLogRepository { conn, user_id }
+    }
+
+    pub fn insert(&self, record: &ReviewLogRecord) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO review_log (operation_id, deck_id, scheduler, quality,
+                 prev_repetitions, prev_interval, prev_ease_factor, prev_stability, prev_difficulty,
+                 new_repetitions, new_interval, new_ease_factor, new_stability, new_difficulty,
+                 next_review_date, reviewed_at, user_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                record.operation_id,
+                record.deck_id,
+                record.scheduler,
+                record.quality,
+                record.prev_repetitions,
+                record.prev_interval,
+                record.prev_ease_factor,
+                record.prev_stability,
+                record.prev_difficulty,
+                record.new_repetitions,
+                record.new_interval,
+                record.new_ease_factor,
+                record.new_stability,
+                record.new_difficulty,
+                record.next_review_date.to_rfc3339(),
+                record.reviewed_at.to_rfc3339(),
+                self.user_id,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// All scheduling decisions for an operation, oldest first
+    pub fn get_for_operation(&self, operation_id: i64) -> Result<Vec<ReviewLogRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE operation_id = ?1 AND user_id = ?2 ORDER BY reviewed_at ASC, id ASC",
+            SELECT_COLUMNS
+        ))?;
+        let rows = stmt.query_map([operation_id, self.user_id], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// The complete review history, oldest first
+    pub fn get_all(&self) -> Result<Vec<ReviewLogRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE user_id = ?1 ORDER BY reviewed_at ASC, id ASC",
+            SELECT_COLUMNS
+        ))?;
+        let rows = stmt.query_map([self.user_id], Self::from_row)?;
+        rows.collect()
+    }
+
+    pub fn count(&self) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM review_log WHERE user_id = ?1",
+            [self.user_id],
+            |row| row.get(0),
+        )
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<ReviewLogRecord> {
+        Ok(ReviewLogRecord {
+            id: Some(row.get(0)?),
+            operation_id: row.get(1)?,
+            deck_id: row.get(2)?,
+            scheduler: row.get(3)?,
+            quality: row.get(4)?,
+            prev_repetitions: row.get(5)?,
+            prev_interval: row.get(6)?,
+            prev_ease_factor: row.get(7)?,
+            prev_stability: row.get(8)?,
+            prev_difficulty: row.get(9)?,
+            new_repetitions: row.get(10)?,
+            new_interval: row.get(11)?,
+            new_ease_factor: row.get(12)?,
+            new_stability: row.get(13)?,
+            new_difficulty: row.get(14)?,
+            next_review_date: parse_datetime_with_timezone(&row.get::<_, String>(15)?),
+            reviewed_at: parse_datetime_with_timezone(&row.get::<_, String>(16)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::connection::init_connection;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use chrono::{Duration, TimeZone};
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    fn review_item(operation_id: i64, repetitions: i32, interval: i32) -> ReviewItem {
+        ReviewItem {
+            id: Some(1),
+            operation_id,
+            repetitions,
+            interval,
+            ease_factor: 2.5,
+            next_review_date: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+                + Duration::days(interval as i64),
+            last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get_for_operation() {
+        let conn = create_test_db();
+        let op_id = OperationsRepository::new(&conn)
+            .insert("ADD", 2, 3, 5, None)
+            .unwrap();
+        let repo = ReviewLogRepository::new(&conn, DEFAULT_USER_ID);
+        let reviewed_at = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+
+        let before = review_item(op_id, 0, 0);
+        let mut after = review_item(op_id, 1, 1);
+        after.fsrs_stability = Some(3.7);
+        let record = ReviewLogRecord::from_items("fsrs", 5, &before, &after, None, reviewed_at);
+        let id = repo.insert(&record).unwrap();
+
+        let history = repo.get_for_operation(op_id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(
+            history[0],
+            ReviewLogRecord {
+                id: Some(id),
+                ..record
+            }
+        );
+        assert_eq!(history[0].prev_repetitions, 0);
+        assert_eq!(history[0].new_interval, 1);
+        assert_eq!(history[0].prev_stability, None);
+        assert_eq!(history[0].new_stability, Some(3.7));
+    }
+
+    #[test]
+    fn test_history_is_kept_in_order() {
+        let conn = create_test_db();
+        let op_id = OperationsRepository::new(&conn)
+            .insert("ADD", 2, 3, 5, None)
+            .unwrap();
+        let repo = ReviewLogRepository::new(&conn, DEFAULT_USER_ID);
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+
+        for day in 0..3 {
+            let before = review_item(op_id, day, day);
+            let after = review_item(op_id, day + 1, day + 1);
+            let record = ReviewLogRecord::from_items(
+                "sm2",
+                4,
+                &before,
+                &after,
+                None,
+                start + Duration::days(day as i64),
+            );
+            repo.insert(&record).unwrap();
+        }
+
+        let history = repo.get_for_operation(op_id).unwrap();
+        let repetitions: Vec<i32> = history.iter().map(|r| r.new_repetitions).collect();
+        assert_eq!(repetitions, vec![1, 2, 3]);
+        assert_eq!(repo.count().unwrap(), 3);
+        assert!(repo.get_for_operation(op_id + 1).unwrap().is_empty());
+    }
+}
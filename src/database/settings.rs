@@ -0,0 +1,80 @@
+use rusqlite::{Connection, OptionalExtension, Result, params};
+
+/// Settings key holding the name of the selected difficulty profile
+pub const DIFFICULTY_PROFILE_KEY: &str = "difficulty_profile";
+
+/// Settings key holding the scheduling algorithm (see `SchedulerKind`)
+pub const SCHEDULER_KEY: &str = "scheduler";
+
+/// Settings key holding whether swapped operands (7 × 8, 8 × 7) count as the same fact
+pub const COMMUTATIVE_FACTS_KEY: &str = "commutative_facts";
+
+/// Settings key holding how wrong answers are graded (see `PartialCreditPolicy`)
+pub const PARTIAL_CREDIT_KEY: &str = "partial_credit";
+
+/// Settings key holding how answer-time baselines are estimated (see `TimingEstimator`)
+pub const TIMING_ESTIMATOR_KEY: &str = "timing_estimator";
+
+/// Settings shared by every profile of the database; unlike history they are not scoped per user
+pub struct SettingsRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> SettingsRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        SettingsRepository { conn }
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()
+    }
+
+    /// Insert or replace the value stored under `key`
+    pub fn set(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::connection::init_connection;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    #[test]
+    fn test_get_missing_setting() {
+        let conn = create_test_db();
+        let repo = SettingsRepository::new(&conn);
+        assert_eq!(repo.get(DIFFICULTY_PROFILE_KEY).unwrap(), None);
+    }
+
+    #[test]
+    fn test_set_and_overwrite_setting() {
+        let conn = create_test_db();
+        let repo = SettingsRepository::new(&conn);
+
+        repo.set(DIFFICULTY_PROFILE_KEY, "single-digit").unwrap();
+        assert_eq!(
+            repo.get(DIFFICULTY_PROFILE_KEY).unwrap(),
+            Some("single-digit".to_string())
+        );
+
+        repo.set(DIFFICULTY_PROFILE_KEY, "two-by-one").unwrap();
+        assert_eq!(
+            repo.get(DIFFICULTY_PROFILE_KEY).unwrap(),
+            Some("two-by-one".to_string())
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use rusqlite::{Connection, OptionalExtension, Result};
+
+/// Profile that owns all history recorded before profiles existed
+pub const DEFAULT_USER_ID: i64 = 1;
+
+/// A practice profile; decks, answers, review items and statistics are kept per profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub id: i64,
+    pub name: String,
+}
+
+pub struct UsersRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> UsersRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        UsersRepository { conn }
+    }
+
+    /// Create a profile; names are unique
+    pub fn create(&self, name: &str) -> Result<i64> {
+        self.conn
+            .execute("INSERT INTO users (name) VALUES (?1)", [name])?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get(&self, user_id: i64) -> Result<Option<User>> {
+        self.conn
+            .query_row(
+                "SELECT id, name FROM users WHERE id = ?1",
+                [user_id],
+                |row| {
+                    Ok(User {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Result<Option<User>> {
+        self.conn
+            .query_row(
+                "SELECT id, name FROM users WHERE name = ?1",
+                [name],
+                |row| {
+                    Ok(User {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    /// Id of the profile called `name`, creating it on first use
+    pub fn get_or_create(&self, name: &str) -> Result<i64> {
+        match self.find_by_name(name)? {
+            Some(user) => Ok(user.id),
+            None => self.create(name),
+        }
+    }
+
+    /// All profiles in creation order
+    pub fn get_all(&self) -> Result<Vec<User>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name FROM users ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::connection::init_connection;
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    #[test]
+    fn test_default_profile_exists() {
+        let conn = create_test_db();
+        let repo = UsersRepository::new(&conn);
+        let users = repo.get_all().unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].id, DEFAULT_USER_ID);
+    }
+
+    #[test]
+    fn test_get_or_create_reuses_existing_profile() {
+        let conn = create_test_db();
+        let repo = UsersRepository::new(&conn);
+
+        let alice = repo.get_or_create("Alice").unwrap();
+        assert_ne!(alice, DEFAULT_USER_ID);
+        assert_eq!(repo.get_or_create("Alice").unwrap(), alice);
+        assert_eq!(repo.get(alice).unwrap().unwrap().name, "Alice");
+        assert!(repo.create("Alice").is_err());
+        assert_eq!(repo.get_all().unwrap().len(), 2);
+    }
+}
@@ -1,5 +1,11 @@
-use crate::database::Database;
+use crate::database::settings::{
+    COMMUTATIVE_FACTS_KEY, DIFFICULTY_PROFILE_KEY, PARTIAL_CREDIT_KEY, SCHEDULER_KEY,
+    TIMING_ESTIMATOR_KEY,
+};
+use crate::database::{Database, SettingsRepository, UsersRepository};
 use crate::date_provider::{DateProvider, OverrideDateProvider};
+use crate::spaced_repetition::{PartialCreditPolicy, SchedulerKind};
+use crate::timing_estimator::TimingEstimator;
 use chrono::{NaiveDate, Utc};
 use rusqlite::Result;
 use std::sync::Arc;
@@ -13,6 +19,18 @@ pub struct DatabaseConfig {
     pub db_path: Option<String>,
     /// Current date for the database (always injected, from CLI or today's date)
     pub current_date: NaiveDate,
+    /// Difficulty profile to persist in the database settings (None keeps the stored one)
+    pub difficulty_profile: Option<String>,
+    /// Scheduling algorithm to persist in the database settings (None keeps the stored one)
+    pub scheduler: Option<SchedulerKind>,
+    /// Whether swapped operands share a review item (None keeps the stored setting)
+    pub commutative_facts: Option<bool>,
+    /// How wrong answers are graded (None keeps the stored policy)
+    pub partial_credit: Option<PartialCreditPolicy>,
+    /// How answer-time baselines treat outliers (None keeps the stored estimator)
+    pub timing_estimator: Option<TimingEstimator>,
+    /// Name of the practice profile to activate, created on first use (None keeps the default)
+    pub user: Option<String>,
 }
 
 impl Default for DatabaseConfig {
@@ -26,6 +44,12 @@ pub struct DatabaseConfigBuilder {
     is_test_mode: bool,
     db_path: Option<String>,
     current_date: NaiveDate,
+    difficulty_profile: Option<String>,
+    scheduler: Option<SchedulerKind>,
+    commutative_facts: Option<bool>,
+    partial_credit: Option<PartialCreditPolicy>,
+    timing_estimator: Option<TimingEstimator>,
+    user: Option<String>,
 }
 
 impl DatabaseConfigBuilder {
@@ -34,6 +58,12 @@ impl DatabaseConfigBuilder {
             is_test_mode: false,
             db_path: None,
             current_date: Utc::now().naive_local().date(),
+            difficulty_profile: None,
+            scheduler: None,
+            commutative_facts: None,
+            partial_credit: None,
+            timing_estimator: None,
+            user: None,
         }
     }
 }
@@ -61,12 +91,54 @@ impl DatabaseConfigBuilder {
         self.date(NaiveDate::from_ymd_opt(year, month, date).unwrap())
     }
 
+    /// Set the difficulty profile to persist
+    pub fn difficulty(mut self, profile_name: &str) -> Self {
+        self.difficulty_profile = Some(profile_name.to_string());
+        self
+    }
+
+    /// Set the scheduling algorithm to persist
+    pub fn scheduler(mut self, scheduler: SchedulerKind) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Set whether swapped operands count as the same fact
+    pub fn commutative_facts(mut self, enabled: bool) -> Self {
+        self.commutative_facts = Some(enabled);
+        self
+    }
+
+    /// Set how wrong answers are graded
+    pub fn partial_credit(mut self, policy: PartialCreditPolicy) -> Self {
+        self.partial_credit = Some(policy);
+        self
+    }
+
+    /// Set how answer-time baselines treat outliers
+    pub fn timing_estimator(mut self, estimator: TimingEstimator) -> Self {
+        self.timing_estimator = Some(estimator);
+        self
+    }
+
+    /// Set the practice profile to activate
+    pub fn user(mut self, name: &str) -> Self {
+        self.user = Some(name.to_string());
+        self
+    }
+
     /// Build the DatabaseConfig
     pub fn build(self) -> DatabaseConfig {
         DatabaseConfig {
             is_test_mode: self.is_test_mode,
             db_path: self.db_path,
             current_date: self.current_date,
+            difficulty_profile: self.difficulty_profile,
+            scheduler: self.scheduler,
+            commutative_facts: self.commutative_facts,
+            partial_credit: self.partial_credit,
+            timing_estimator: self.timing_estimator,
+            user: self.user,
         }
     }
 }
@@ -113,7 +185,29 @@ impl DatabaseFactory {
         let path = config.get_path();
         let date_provider: Arc<dyn DateProvider> =
             Arc::new(OverrideDateProvider::new(config.current_date));
-        Database::with_date_provider(&path, date_provider)
+        let db = Database::with_date_provider(&path, date_provider)?;
+
+        let settings = SettingsRepository::new(&db.conn);
+        if let Some(ref profile_name) = config.difficulty_profile {
+            settings.set(DIFFICULTY_PROFILE_KEY, profile_name)?;
+        }
+        if let Some(scheduler) = config.scheduler {
+            settings.set(SCHEDULER_KEY, scheduler.as_str())?;
+        }
+        if let Some(enabled) = config.commutative_facts {
+            settings.set(COMMUTATIVE_FACTS_KEY, &enabled.to_string())?;
+        }
+        if let Some(policy) = config.partial_credit {
+            settings.set(PARTIAL_CREDIT_KEY, policy.as_str())?;
+        }
+        if let Some(estimator) = config.timing_estimator {
+            settings.set(TIMING_ESTIMATOR_KEY, &estimator.to_string())?;
+        }
+        if let Some(ref name) = config.user {
+            db.set_user(UsersRepository::new(&db.conn).get_or_create(name)?);
+        }
+
+        Ok(db)
     }
 
     /// Detects the database configuration from command line arguments using clap
@@ -122,6 +216,12 @@ impl DatabaseFactory {
     /// - `--test`: Use in-memory database
     /// - `--db-path <path>`: Use custom database file path
     /// - `--override-date <YYYY-MM-DD>`: Override the current date for the database (format: YYYY-MM-DD)
+    /// - `--difficulty <PROFILE>`: Select and persist the difficulty profile for new questions
+    /// - `--scheduler <ALGORITHM>`: Select and persist the spaced repetition scheduler
+    /// - `--commutative-facts <BOOL>`: Persist whether swapped operands share a review item
+    /// - `--partial-credit <POLICY>`: Select and persist how wrong answers are graded
+    /// - `--timing-estimator <ESTIMATOR>`: Select and persist how answer-time baselines treat outliers
+    /// - `--user <NAME>`: Practice as the named profile, creating it on first use
     ///
     /// If `--override-date` is not provided, uses today's date.
     /// Date validation errors will cause the program to exit with an error message.
@@ -136,6 +236,29 @@ impl DatabaseFactory {
                 std::process::exit(1);
             })
             .unwrap_or_else(|| chrono::Local::now().naive_local().date());
+        let difficulty_profile = args
+            .validate_difficulty()
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            })
+            .map(|profile| profile.name.to_string());
+        let scheduler = args.validate_scheduler().unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
+        let partial_credit = args.validate_partial_credit().unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
+        let timing_estimator = args.validate_timing_estimator().unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
+        let user = args.validate_user().unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
 
         DatabaseConfig {
             is_test_mode: args.test,
@@ -144,6 +267,12 @@ impl DatabaseFactory {
                 .as_ref()
                 .map(|p| p.to_string_lossy().to_string()),
             current_date,
+            difficulty_profile,
+            scheduler,
+            commutative_facts: args.commutative_facts,
+            partial_credit,
+            timing_estimator,
+            user,
         }
     }
 }
@@ -243,14 +372,14 @@ mod tests {
 
     #[test]
     fn test_create_with_builder() {
-        let config = DatabaseConfig::builder().build();
+        let config = DatabaseConfig::builder().test_mode().build();
         let db = DatabaseFactory::create(config);
         assert!(db.is_ok());
     }
 
     #[test]
     fn test_create_with_memory_database() {
-        let config = DatabaseConfig::builder().build();
+        let config = DatabaseConfig::builder().test_mode().build();
         let db = DatabaseFactory::create(config).expect("Failed to create in-memory database");
         // Verify the database works by executing a simple query
         let repo = OperationsRepository::new(&db.conn);
@@ -348,6 +477,82 @@ mod tests {
         assert_eq!(current_date, today);
     }
 
+    #[test]
+    fn test_create_persists_difficulty_profile() {
+        let config = DatabaseConfig::builder()
+            .test_mode()
+            .difficulty("single-digit")
+            .build();
+        let db = DatabaseFactory::create(config).unwrap();
+        let stored = SettingsRepository::new(&db.conn)
+            .get(DIFFICULTY_PROFILE_KEY)
+            .unwrap();
+        assert_eq!(stored, Some("single-digit".to_string()));
+    }
+
+    #[test]
+    fn test_create_persists_scheduler() {
+        let config = DatabaseConfig::builder()
+            .test_mode()
+            .scheduler(SchedulerKind::Fsrs)
+            .build();
+        let db = DatabaseFactory::create(config).unwrap();
+        let stored = SettingsRepository::new(&db.conn)
+            .get(SCHEDULER_KEY)
+            .unwrap();
+        assert_eq!(stored, Some("fsrs".to_string()));
+    }
+
+    #[test]
+    fn test_create_persists_commutative_facts() {
+        let config = DatabaseConfig::builder()
+            .test_mode()
+            .commutative_facts(true)
+            .build();
+        let db = DatabaseFactory::create(config).unwrap();
+        let stored = SettingsRepository::new(&db.conn)
+            .get(COMMUTATIVE_FACTS_KEY)
+            .unwrap();
+        assert_eq!(stored, Some("true".to_string()));
+    }
+
+    #[test]
+    fn test_create_persists_partial_credit() {
+        let config = DatabaseConfig::builder()
+            .test_mode()
+            .partial_credit(PartialCreditPolicy::Lenient)
+            .build();
+        let db = DatabaseFactory::create(config).unwrap();
+        let stored = SettingsRepository::new(&db.conn)
+            .get(PARTIAL_CREDIT_KEY)
+            .unwrap();
+        assert_eq!(stored, Some("lenient".to_string()));
+    }
+
+    #[test]
+    fn test_create_persists_timing_estimator() {
+        let config = DatabaseConfig::builder()
+            .test_mode()
+            .timing_estimator(TimingEstimator::Trimmed { fraction: 0.05 })
+            .build();
+        let db = DatabaseFactory::create(config).unwrap();
+        let stored = SettingsRepository::new(&db.conn)
+            .get(TIMING_ESTIMATOR_KEY)
+            .unwrap();
+        assert_eq!(stored, Some("trimmed:0.05".to_string()));
+    }
+
+    #[test]
+    fn test_create_activates_user() {
+        let config = DatabaseConfig::builder().test_mode().user("Alice").build();
+        let db = DatabaseFactory::create(config).unwrap();
+        let user = UsersRepository::new(&db.conn)
+            .get(db.user_id())
+            .unwrap()
+            .expect("Profile should have been created");
+        assert_eq!(user.name, "Alice");
+    }
+
     #[test]
     fn test_database_config_with_current_date() {
         use chrono::NaiveDate;
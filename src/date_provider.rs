@@ -1,4 +1,5 @@
-use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use std::sync::Mutex;
 
 /// Trait for providing the current date/time to the database
 /// This allows for flexible date handling (system time, overrides, etc.)
@@ -40,6 +41,32 @@ impl DateProvider for OverrideDateProvider {
     }
 }
 
+/// Date provider driven by a manually advanced clock, for replaying or simulating sessions
+pub struct SimulatedDateProvider {
+    current_time: Mutex<DateTime<Utc>>,
+}
+
+impl SimulatedDateProvider {
+    /// Create a simulated clock starting at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            current_time: Mutex::new(start),
+        }
+    }
+
+    /// Move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        let mut current_time = self.current_time.lock().unwrap();
+        *current_time += duration;
+    }
+}
+
+impl DateProvider for SimulatedDateProvider {
+    fn get_current_time(&self) -> DateTime<Utc> {
+        *self.current_time.lock().unwrap()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +108,20 @@ mod tests {
         };
         assert!(second_diff <= 1);
     }
+
+    #[test]
+    fn test_simulated_date_provider_only_moves_when_advanced() {
+        let start = DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let provider = SimulatedDateProvider::new(start);
+        assert_eq!(provider.get_current_time(), start);
+        assert_eq!(provider.get_current_time(), start);
+
+        provider.advance(Duration::days(3));
+        assert_eq!(
+            provider.get_current_time().format("%Y-%m-%d").to_string(),
+            "2025-01-04"
+        );
+    }
 }
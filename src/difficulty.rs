@@ -0,0 +1,491 @@
+use crate::adaptive_difficulty;
+use crate::operations::{Operation, OperationType};
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Maximum number of samples drawn when looking for an operation that satisfies a carry rule
+const MAX_SAMPLE_ATTEMPTS: usize = 200;
+
+/// Name of the profile used when none has been selected
+pub const DEFAULT_PROFILE_NAME: &str = "mixed";
+
+/// Name of the profile whose ranges follow the user's adaptive level per operation type
+pub const ADAPTIVE_PROFILE_NAME: &str = "adaptive";
+
+/// Constraint on carries (addition) or borrows (subtraction) when generating operands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CarryRule {
+    /// No constraint
+    Any,
+    /// At least one column must carry/borrow
+    Require,
+    /// No column may carry/borrow
+    Avoid,
+}
+
+/// Generation settings for a single operation type within a profile
+///
+/// For division, `operand1` is the range of the quotient and `operand2` the range of the
+/// divisor; the dividend is built from both so the answer is always a whole number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationProfile {
+    pub operation_type: OperationType,
+    /// Relative weight of this operation type in the mix
+    pub weight: u32,
+    pub operand1: RangeInclusive<i32>,
+    pub operand2: RangeInclusive<i32>,
+    /// Carry rule for addition, borrow rule for subtraction; ignored otherwise
+    pub carry: CarryRule,
+    /// Allow subtraction results below zero
+    pub allow_negative: bool,
+}
+
+impl OperationProfile {
+    pub fn new(
+        operation_type: OperationType,
+        weight: u32,
+        operand1: RangeInclusive<i32>,
+        operand2: RangeInclusive<i32>,
+    ) -> Self {
+        Self {
+            operation_type,
+            weight,
+            operand1,
+            operand2,
+            carry: CarryRule::Any,
+            allow_negative: false,
+        }
+    }
+
+    pub fn with_carry(mut self, carry: CarryRule) -> Self {
+        self.carry = carry;
+        self
+    }
+
+    /// Generate an operation, retrying until the carry rule is satisfied
+    ///
+    /// Falls back to an unconstrained sample if the rule cannot be met within the ranges.
+    pub fn generate(&self, rng: &mut impl Rng) -> Operation {
+        for _ in 0..MAX_SAMPLE_ATTEMPTS {
+            let candidate = self.sample(rng);
+            if self.satisfies_carry_rule(&candidate) {
+                return candidate;
+            }
+        }
+        self.sample(rng)
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> Operation {
+        let a = rng.gen_range(self.operand1.clone());
+        let b = rng.gen_range(self.operand2.clone());
+
+        match self.operation_type {
+            OperationType::Subtraction if !self.allow_negative && a < b => {
+                Operation::new(OperationType::Subtraction, b, a)
+            }
+            OperationType::Division => Operation::new(OperationType::Division, a * b, b),
+            _ => Operation::new(self.operation_type.clone(), a, b),
+        }
+    }
+
+    fn satisfies_carry_rule(&self, operation: &Operation) -> bool {
+        let regrouped = match self.operation_type {
+            OperationType::Addition => has_carry(operation.operand1, operation.operand2),
+            OperationType::Subtraction => has_borrow(operation.operand1, operation.operand2),
+            _ => return true,
+        };
+
+        match self.carry {
+            CarryRule::Any => true,
+            CarryRule::Require => regrouped,
+            CarryRule::Avoid => !regrouped,
+        }
+    }
+}
+
+/// A named set of operand ranges, carry constraints and operation mix
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifficultyProfile {
+    /// Identifier used on the command line and in the settings table
+    pub name: &'static str,
+    pub description: &'static str,
+    pub operations: Vec<OperationProfile>,
+}
+
+impl DifficultyProfile {
+    /// All built-in profiles
+    pub fn all() -> Vec<DifficultyProfile> {
+        vec![
+            Self::mixed(),
+            Self::single_digit(),
+            Self::two_digit_by_one_digit(),
+            Self::three_digit_addition_carries(),
+            adaptive_difficulty::profile_for_levels(&HashMap::new()),
+        ]
+    }
+
+    /// Look up a built-in profile by name
+    pub fn by_name(name: &str) -> Option<DifficultyProfile> {
+        Self::all().into_iter().find(|profile| profile.name == name)
+    }
+
+    /// Names of all built-in profiles
+    pub fn names() -> Vec<&'static str> {
+        Self::all().iter().map(|profile| profile.name).collect()
+    }
+
+    /// Two-digit practice across all four operations
+    ///
+    /// Reproduces [`Operation::generate_random`]: each operation type a quarter of the time,
+    /// a multiplicand up to 10 may be multiplied by up to 99, and each divisor from 2 to 9 is
+    /// equally likely with a dividend below 100. The weights split those cases over 792 draws
+    /// per operation type.
+    pub fn mixed() -> Self {
+        let mut operations = vec![
+            OperationProfile::new(OperationType::Addition, 792, 1..=99, 1..=99),
+            OperationProfile::new(OperationType::Subtraction, 792, 1..=99, 1..=99),
+            OperationProfile::new(OperationType::Multiplication, 80, 1..=10, 1..=99),
+            OperationProfile::new(OperationType::Multiplication, 712, 11..=99, 1..=9),
+        ];
+        operations.extend((2..=9).map(|divisor| {
+            OperationProfile::new(
+                OperationType::Division,
+                99,
+                1..=99 / divisor,
+                divisor..=divisor,
+            )
+        }));
+
+        Self {
+            name: DEFAULT_PROFILE_NAME,
+            description: "Two-digit addition and subtraction, times tables up to 99 × 9",
+            operations,
+        }
+    }
+
+    /// Single-digit operands only
+    pub fn single_digit() -> Self {
+        Self {
+            name: "single-digit",
+            description: "Single-digit operands for all four operations",
+            operations: vec![
+                OperationProfile::new(OperationType::Addition, 1, 1..=9, 1..=9),
+                OperationProfile::new(OperationType::Subtraction, 1, 1..=9, 1..=9),
+                OperationProfile::new(OperationType::Multiplication, 1, 1..=9, 1..=9),
+                OperationProfile::new(OperationType::Division, 1, 1..=9, 1..=9),
+            ],
+        }
+    }
+
+    /// Two-digit numbers multiplied or divided by a single digit
+    pub fn two_digit_by_one_digit() -> Self {
+        Self {
+            name: "two-by-one",
+            description: "Two-digit by one-digit multiplication and division",
+            operations: vec![
+                OperationProfile::new(OperationType::Multiplication, 2, 10..=99, 2..=9),
+                OperationProfile::new(OperationType::Division, 1, 10..=99, 2..=9),
+            ],
+        }
+    }
+
+    /// Three-digit additions that always involve at least one carry
+    pub fn three_digit_addition_carries() -> Self {
+        Self {
+            name: "three-digit-carry",
+            description: "Three-digit addition with carries",
+            operations: vec![
+                OperationProfile::new(OperationType::Addition, 1, 100..=999, 100..=999)
+                    .with_carry(CarryRule::Require),
+            ],
+        }
+    }
+
+    /// Whether this profile adapts to the user's stored difficulty levels
+    pub fn is_adaptive(&self) -> bool {
+        self.name == ADAPTIVE_PROFILE_NAME
+    }
+
+    /// Generate a random operation according to this profile's mix
+    pub fn generate(&self) -> Operation {
+        let mut rng = rand::thread_rng();
+        let weights = self.operations.iter().map(|op| op.weight);
+        let profile = match WeightedIndex::new(weights) {
+            Ok(index) => &self.operations[index.sample(&mut rng)],
+            Err(_) => return Operation::generate_random(),
+        };
+        profile.generate(&mut rng)
+    }
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        Self::mixed()
+    }
+}
+
+/// Whether adding the two (non-negative) numbers carries in any column
+pub fn has_carry(a: i32, b: i32) -> bool {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while a > 0 || b > 0 {
+        if a % 10 + b % 10 >= 10 {
+            return true;
+        }
+        a /= 10;
+        b /= 10;
+    }
+    false
+}
+
+/// Whether subtracting `b` from `a` (with `a >= b`) borrows in any column
+pub fn has_borrow(a: i32, b: i32) -> bool {
+    let (mut a, mut b) = (a.max(b).abs(), a.min(b).abs());
+    while b > 0 {
+        if a % 10 < b % 10 {
+            return true;
+        }
+        a /= 10;
+        b /= 10;
+    }
+    false
+}
+
+/// Whether multiplying the two numbers carries: some pair of their digits multiplies to ten or more
+pub fn has_product_carry(a: i32, b: i32) -> bool {
+    let digits = |n: i32| {
+        n.abs()
+            .to_string()
+            .bytes()
+            .map(|d| i32::from(d - b'0'))
+            .collect::<Vec<_>>()
+    };
+    let b_digits = digits(b);
+    digits(a)
+        .iter()
+        .any(|x| b_digits.iter().any(|y| x * y >= 10))
+}
+
+/// Coarse difficulty class of a question, so answer times are only compared between similar ones
+///
+/// "2 + 3" and "87 + 96" are both additions but take very different times to answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DifficultyBucket {
+    /// Digits across both operands: two for 2 + 3, four for 87 + 96
+    pub digits: u32,
+    /// Whether the question needs a carry or borrow; for division, whether the multiplication it
+    /// inverts does
+    pub regrouping: bool,
+    /// Whether the larger operand starts with a digit of 5 or more
+    pub large: bool,
+}
+
+impl DifficultyBucket {
+    pub fn of(operation: &Operation) -> Self {
+        let (a, b) = (operation.operand1, operation.operand2);
+        let regrouping = match operation.operation_type {
+            OperationType::Addition => has_carry(a, b),
+            OperationType::Subtraction => has_borrow(a, b),
+            OperationType::Multiplication => has_product_carry(a, b),
+            OperationType::Division => has_product_carry(operation.result, b),
+        };
+        let larger = a.abs().max(b.abs()).to_string();
+
+        Self {
+            digits: (a.abs().to_string().len() + b.abs().to_string().len()) as u32,
+            regrouping,
+            large: larger.as_bytes()[0] >= b'5',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_carry() {
+        assert!(!has_carry(12, 34));
+        assert!(has_carry(15, 7));
+        assert!(has_carry(150, 950));
+        assert!(!has_carry(100, 899));
+    }
+
+    #[test]
+    fn test_has_product_carry() {
+        assert!(!has_product_carry(3, 2));
+        assert!(has_product_carry(7, 8));
+        assert!(!has_product_carry(21, 4));
+        assert!(has_product_carry(23, 4));
+    }
+
+    #[test]
+    fn test_difficulty_bucket_separates_easy_and_hard_questions() {
+        let bucket = |t, a, b| DifficultyBucket::of(&Operation::new(t, a, b));
+        let easy = bucket(OperationType::Addition, 2, 3);
+        let hard = bucket(OperationType::Addition, 87, 96);
+        assert_eq!(
+            easy,
+            DifficultyBucket {
+                digits: 2,
+                regrouping: false,
+                large: false
+            }
+        );
+        assert_eq!(
+            hard,
+            DifficultyBucket {
+                digits: 4,
+                regrouping: true,
+                large: true
+            }
+        );
+        assert_eq!(easy, bucket(OperationType::Addition, 3, 4));
+        assert_ne!(
+            bucket(OperationType::Multiplication, 2, 3),
+            bucket(OperationType::Multiplication, 7, 8)
+        );
+        // 56 ÷ 8 inverts 7 × 8
+        assert!(bucket(OperationType::Division, 56, 8).regrouping);
+        assert!(!bucket(OperationType::Division, 6, 2).regrouping);
+    }
+
+    #[test]
+    fn test_has_borrow() {
+        assert!(!has_borrow(57, 23));
+        assert!(has_borrow(52, 27));
+        assert!(has_borrow(300, 1));
+        assert!(!has_borrow(9, 9));
+    }
+
+    #[test]
+    fn test_by_name_finds_all_profiles() {
+        for name in DifficultyProfile::names() {
+            assert_eq!(DifficultyProfile::by_name(name).unwrap().name, name);
+        }
+        assert!(DifficultyProfile::by_name("impossible").is_none());
+    }
+
+    #[test]
+    fn test_default_profile_is_mixed() {
+        assert_eq!(DifficultyProfile::default().name, DEFAULT_PROFILE_NAME);
+    }
+
+    #[test]
+    fn test_default_profile_matches_generate_random() {
+        let profile = DifficultyProfile::default();
+        let weight_of = |operation_type: OperationType| -> u32 {
+            profile
+                .operations
+                .iter()
+                .filter(|op| op.operation_type == operation_type)
+                .map(|op| op.weight)
+                .sum()
+        };
+        for operation_type in OperationType::all() {
+            assert_eq!(weight_of(operation_type), 792);
+        }
+
+        let ranges = |operation_type: OperationType| {
+            profile
+                .operations
+                .iter()
+                .filter(|op| op.operation_type == operation_type)
+                .map(|op| (op.weight, op.operand1.clone(), op.operand2.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranges(OperationType::Addition), vec![(792, 1..=99, 1..=99)]);
+        assert_eq!(
+            ranges(OperationType::Subtraction),
+            vec![(792, 1..=99, 1..=99)]
+        );
+        // Operand1 is uniform over 1-99, and only 1-10 may be multiplied by more than 9
+        assert_eq!(
+            ranges(OperationType::Multiplication),
+            vec![(80, 1..=10, 1..=99), (712, 11..=99, 1..=9)]
+        );
+        assert_eq!(
+            ranges(OperationType::Division),
+            (2..=9)
+                .map(|divisor| (99, 1..=99 / divisor, divisor..=divisor))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_single_digit_operands() {
+        let profile = DifficultyProfile::single_digit();
+        for _ in 0..200 {
+            let op = profile.generate();
+            match op.operation_type {
+                OperationType::Division => {
+                    assert!((1..=9).contains(&op.operand2));
+                    assert!((1..=9).contains(&op.result));
+                }
+                _ => {
+                    assert!((1..=9).contains(&op.operand1), "{}", op);
+                    assert!((1..=9).contains(&op.operand2), "{}", op);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_two_by_one_operation_mix() {
+        let profile = DifficultyProfile::two_digit_by_one_digit();
+        for _ in 0..200 {
+            let op = profile.generate();
+            assert!(matches!(
+                op.operation_type,
+                OperationType::Multiplication | OperationType::Division
+            ));
+            assert!((2..=9).contains(&op.operand2));
+            if op.operation_type == OperationType::Division {
+                assert_eq!(op.operand1 % op.operand2, 0);
+            } else {
+                assert!((10..=99).contains(&op.operand1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_three_digit_addition_always_carries() {
+        let profile = DifficultyProfile::three_digit_addition_carries();
+        for _ in 0..200 {
+            let op = profile.generate();
+            assert_eq!(op.operation_type, OperationType::Addition);
+            assert!((100..=999).contains(&op.operand1));
+            assert!((100..=999).contains(&op.operand2));
+            assert!(has_carry(op.operand1, op.operand2), "{} should carry", op);
+        }
+    }
+
+    #[test]
+    fn test_avoid_borrow_rule() {
+        let profile = OperationProfile::new(OperationType::Subtraction, 1, 10..=99, 10..=99)
+            .with_carry(CarryRule::Avoid);
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let op = profile.generate(&mut rng);
+            assert!(op.result >= 0);
+            assert!(
+                !has_borrow(op.operand1, op.operand2),
+                "{} should not borrow",
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn test_division_always_exact() {
+        let profile = DifficultyProfile::mixed();
+        for _ in 0..200 {
+            let op = profile.generate();
+            if op.operation_type == OperationType::Division {
+                assert_eq!(op.operand1 % op.operand2, 0);
+                assert!(op.operand1 < 100);
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use crate::difficulty::{has_borrow, has_carry};
+use crate::operations::{Operation, OperationType};
+
+/// Likely cause of a wrong answer, guessed from how it differs from the correct result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorKind {
+    /// One more or one less than the result
+    OffByOne,
+    /// The result of a neighbouring multiplication fact, e.g. 7 × 7 for 7 × 8
+    TableNeighbour,
+    /// The result of another operation on the same operands, e.g. 7 + 8 for 7 × 8
+    WrongOperation,
+    /// The right digits in the wrong order
+    TransposedDigits,
+    /// A carry (addition) or borrow (subtraction) was left out
+    DroppedCarry,
+    /// The result multiplied or divided by a power of ten
+    PlaceValueShift,
+    /// None of the above
+    Other,
+}
+
+impl ErrorKind {
+    pub fn all() -> [ErrorKind; 7] {
+        [
+            ErrorKind::OffByOne,
+            ErrorKind::TableNeighbour,
+            ErrorKind::WrongOperation,
+            ErrorKind::TransposedDigits,
+            ErrorKind::DroppedCarry,
+            ErrorKind::PlaceValueShift,
+            ErrorKind::Other,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::OffByOne => "OFF_BY_ONE",
+            ErrorKind::TableNeighbour => "TABLE_NEIGHBOUR",
+            ErrorKind::WrongOperation => "WRONG_OPERATION",
+            ErrorKind::TransposedDigits => "TRANSPOSED_DIGITS",
+            ErrorKind::DroppedCarry => "DROPPED_CARRY",
+            ErrorKind::PlaceValueShift => "PLACE_VALUE_SHIFT",
+            ErrorKind::Other => "OTHER",
+        }
+    }
+
+    pub fn from(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|kind| kind.as_str() == s)
+    }
+
+    /// Short description shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorKind::OffByOne => "Off by one",
+            ErrorKind::TableNeighbour => "Neighbouring table fact",
+            ErrorKind::WrongOperation => "Wrong operation",
+            ErrorKind::TransposedDigits => "Transposed digits",
+            ErrorKind::DroppedCarry => "Dropped carry/borrow",
+            ErrorKind::PlaceValueShift => "Place-value shift",
+            ErrorKind::Other => "Other",
+        }
+    }
+}
+
+/// One-line summary of mistake counts, e.g. "Off by one: 3, Transposed digits: 1"
+pub fn describe_counts(counts: &[(ErrorKind, i64)]) -> String {
+    counts
+        .iter()
+        .map(|(kind, count)| format!("{}: {}", kind.label(), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Classify a wrong answer; None when the answer is correct
+///
+/// Checks run from the most to the least specific explanation, so an answer that fits several
+/// (3 × 2 answered 5 is both off by one and 3 + 2) gets the first.
+pub fn classify(operation: &Operation, user_answer: i32) -> Option<ErrorKind> {
+    let result = operation.result;
+    if user_answer == result {
+        return None;
+    }
+    let (a, b) = (operation.operand1, operation.operand2);
+
+    let kind = if (i64::from(user_answer) - i64::from(result)).abs() == 1 {
+        ErrorKind::OffByOne
+    } else if operation.operation_type == OperationType::Multiplication
+        && is_table_neighbour(a, b, user_answer)
+    {
+        ErrorKind::TableNeighbour
+    } else if other_operation_results(operation).contains(&user_answer) {
+        ErrorKind::WrongOperation
+    } else if is_transposed(result, user_answer) {
+        ErrorKind::TransposedDigits
+    } else if is_dropped_carry(operation, user_answer) {
+        ErrorKind::DroppedCarry
+    } else if is_place_value_shift(result, user_answer) {
+        ErrorKind::PlaceValueShift
+    } else {
+        ErrorKind::Other
+    };
+    Some(kind)
+}
+
+fn is_table_neighbour(a: i32, b: i32, answer: i32) -> bool {
+    [(a, b - 1), (a, b + 1), (a - 1, b), (a + 1, b)]
+        .iter()
+        .any(|&(x, y)| x.checked_mul(y) == Some(answer))
+}
+
+fn other_operation_results(operation: &Operation) -> Vec<i32> {
+    OperationType::all()
+        .into_iter()
+        .filter(|t| *t != operation.operation_type)
+        .filter(|t| {
+            // Only whole-number quotients are plausible mistakes
+            *t != OperationType::Division
+                || (operation.operand2 != 0 && operation.operand1 % operation.operand2 == 0)
+        })
+        .map(|t| Operation::new(t, operation.operand1, operation.operand2).result)
+        .collect()
+}
+
+fn is_transposed(result: i32, answer: i32) -> bool {
+    if result.signum() != answer.signum() {
+        return false;
+    }
+    let mut result_digits: Vec<char> = result.abs().to_string().chars().collect();
+    let mut answer_digits: Vec<char> = answer.abs().to_string().chars().collect();
+    if result_digits.len() < 2 || result_digits.len() != answer_digits.len() {
+        return false;
+    }
+    result_digits.sort_unstable();
+    answer_digits.sort_unstable();
+    result_digits == answer_digits
+}
+
+fn is_dropped_carry(operation: &Operation, answer: i32) -> bool {
+    let (a, b, result) = (operation.operand1, operation.operand2, operation.result);
+    match operation.operation_type {
+        OperationType::Addition if a >= 0 && b >= 0 && has_carry(a, b) => {
+            // Every carry dropped, or a single one
+            answer == digitwise(a, b, |x, y| (x + y) % 10)
+                || powers_of_ten(result).any(|p| answer == result - p)
+        }
+        OperationType::Subtraction if a >= 0 && b >= 0 && has_borrow(a, b) => {
+            // Smaller digit taken from the larger in every column, or a single borrow not repaid
+            let (high, low) = (a.max(b), a.min(b));
+            let magnitude = digitwise(high, low, |x, y| (x - y).abs());
+            let signed = if a >= b { magnitude } else { -magnitude };
+            answer == signed
+                || powers_of_ten(result).any(|p| answer == result + p * result.signum())
+        }
+        _ => false,
+    }
+}
+
+/// Combine the digits of two non-negative numbers column by column
+fn digitwise(a: i32, b: i32, column: impl Fn(i32, i32) -> i32) -> i32 {
+    let (mut a, mut b) = (a, b);
+    let mut combined = 0;
+    let mut place = 1;
+    while a > 0 || b > 0 {
+        combined += column(a % 10, b % 10) * place;
+        a /= 10;
+        b /= 10;
+        place *= 10;
+    }
+    combined
+}
+
+/// 10, 100, ... up to the magnitude of `n`
+fn powers_of_ten(n: i32) -> impl Iterator<Item = i32> {
+    std::iter::successors(Some(10i32), |p| p.checked_mul(10)).take_while(move |p| *p <= n.abs())
+}
+
+fn is_place_value_shift(result: i32, answer: i32) -> bool {
+    if result == 0 || answer == 0 {
+        return false;
+    }
+    powers_of_ten(i32::MAX)
+        .take(3)
+        .any(|p| result.checked_mul(p) == Some(answer) || answer.checked_mul(p) == Some(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OperationType::*;
+
+    fn kind(operation_type: OperationType, a: i32, b: i32, answer: i32) -> Option<ErrorKind> {
+        classify(&Operation::new(operation_type, a, b), answer)
+    }
+
+    #[test]
+    fn test_correct_answer_has_no_error() {
+        assert_eq!(kind(Multiplication, 7, 8, 56), None);
+    }
+
+    #[test]
+    fn test_classify_wrong_answers() {
+        assert_eq!(kind(Addition, 27, 15, 43), Some(ErrorKind::OffByOne));
+        assert_eq!(
+            kind(Multiplication, 7, 8, 49),
+            Some(ErrorKind::TableNeighbour)
+        );
+        assert_eq!(
+            kind(Multiplication, 7, 8, 64),
+            Some(ErrorKind::TableNeighbour)
+        );
+        assert_eq!(
+            kind(Multiplication, 7, 8, 15),
+            Some(ErrorKind::WrongOperation)
+        );
+        assert_eq!(kind(Addition, 9, 3, 27), Some(ErrorKind::WrongOperation));
+        assert_eq!(
+            kind(Multiplication, 7, 9, 36),
+            Some(ErrorKind::TransposedDigits)
+        );
+        assert_eq!(kind(Addition, 27, 15, 32), Some(ErrorKind::DroppedCarry));
+        assert_eq!(kind(Addition, 7, 8, 5), Some(ErrorKind::DroppedCarry));
+        assert_eq!(kind(Subtraction, 52, 17, 45), Some(ErrorKind::DroppedCarry));
+        assert_eq!(
+            kind(Multiplication, 20, 30, 60),
+            Some(ErrorKind::PlaceValueShift)
+        );
+        assert_eq!(kind(Division, 80, 4, 2), Some(ErrorKind::PlaceValueShift));
+        assert_eq!(kind(Multiplication, 7, 8, 71), Some(ErrorKind::Other));
+    }
+
+    #[test]
+    fn test_first_matching_explanation_wins() {
+        // Both off by one and 3 + 2
+        assert_eq!(kind(Multiplication, 3, 2, 5), Some(ErrorKind::OffByOne));
+    }
+
+    #[test]
+    fn test_negative_results() {
+        assert_eq!(kind(Subtraction, 7, 8, 1), Some(ErrorKind::Other));
+        assert_eq!(kind(Subtraction, 7, 8, -2), Some(ErrorKind::OffByOne));
+        assert_eq!(
+            kind(Subtraction, 17, 71, -45),
+            Some(ErrorKind::TransposedDigits)
+        );
+    }
+
+    #[test]
+    fn test_describe_counts() {
+        assert_eq!(
+            describe_counts(&[(ErrorKind::OffByOne, 3), (ErrorKind::TransposedDigits, 1)]),
+            "Off by one: 3, Transposed digits: 1"
+        );
+        assert_eq!(describe_counts(&[]), "");
+    }
+
+    #[test]
+    fn test_error_kind_round_trip() {
+        for kind in ErrorKind::all() {
+            assert_eq!(ErrorKind::from(kind.as_str()), Some(kind));
+        }
+        assert_eq!(ErrorKind::from("UNKNOWN"), None);
+    }
+}
@@ -0,0 +1,468 @@
+use crate::database::DEFAULT_USER_ID;
+use crate::row_factories::parse_datetime_with_timezone;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::Path;
+
+/// Bumped whenever the exported fields change incompatibly
+///
+/// Fields added later without a bump are optional when read, e.g. `timing_interrupted`.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// File names used for each table when exporting to CSV
+pub const OPERATIONS_CSV: &str = "operations.csv";
+pub const DECKS_CSV: &str = "decks.csv";
+pub const ANSWERS_CSV: &str = "answers.csv";
+pub const REVIEW_ITEMS_CSV: &str = "review_items.csv";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Restricts which rows are exported
+///
+/// Only the history of one profile is exported. The date range applies to answers and decks by
+/// creation time and to review items by their last review; operations are exported when an
+/// exported answer or review item uses them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportFilter {
+    /// Profile whose history is exported
+    pub user_id: i64,
+    /// Inclusive lower bound
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive upper bound
+    pub until: Option<DateTime<Utc>>,
+    /// Operation types to keep (all when empty), e.g. "MULTIPLY"
+    pub operation_types: Vec<String>,
+}
+
+impl Default for ExportFilter {
+    fn default() -> Self {
+        Self {
+            user_id: DEFAULT_USER_ID,
+            since: None,
+            until: None,
+            operation_types: Vec::new(),
+        }
+    }
+}
+
+impl ExportFilter {
+    fn has_date_range(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
+    }
+
+    fn includes_time(&self, at: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| at >= since) && self.until.is_none_or(|until| at < until)
+    }
+
+    fn includes_type(&self, operation_type: &str) -> bool {
+        self.operation_types.is_empty()
+            || self
+                .operation_types
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(operation_type))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationExport {
+    pub id: i64,
+    pub operation_type: String,
+    pub operand1: i32,
+    pub operand2: i32,
+    pub result: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeckExport {
+    pub id: i64,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub status: String,
+    pub total_questions: i32,
+    pub correct_answers: i32,
+    pub incorrect_answers: i32,
+    pub total_time_seconds: f64,
+    pub average_time_seconds: Option<f64>,
+    pub accuracy_percentage: Option<f64>,
+}
+
+/// One answer, with its operation repeated so the file is usable on its own in a spreadsheet
+///
+/// The likely cause of a wrong answer is deliberately not exported: it follows from the fact and
+/// the given answer, so import derives it again with the importing version's classifier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnswerExport {
+    pub id: i64,
+    pub deck_id: Option<i64>,
+    pub operation_id: i64,
+    pub operation_type: String,
+    pub operand1: i32,
+    pub operand2: i32,
+    pub result: i32,
+    pub user_answer: i32,
+    pub is_correct: bool,
+    pub time_spent_seconds: f64,
+    pub created_at: String,
+    /// Whether the question was paused while shown; false in files written before it existed
+    #[serde(default)]
+    pub timing_interrupted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewItemExport {
+    pub id: i64,
+    pub operation_id: i64,
+    pub repetitions: i32,
+    pub interval: i32,
+    pub ease_factor: f64,
+    pub next_review_date: String,
+    pub last_reviewed_date: Option<String>,
+    pub fsrs_stability: Option<f64>,
+    pub fsrs_difficulty: Option<f64>,
+}
+
+/// Practice history in the exported layout, ordered by id within each table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryExport {
+    pub format_version: u32,
+    pub exported_at: String,
+    pub operations: Vec<OperationExport>,
+    pub decks: Vec<DeckExport>,
+    pub answers: Vec<AnswerExport>,
+    pub review_items: Vec<ReviewItemExport>,
+}
+
+/// Normalizes stored timestamps (RFC 3339 or SQLite CURRENT_TIMESTAMP) to RFC 3339
+fn normalize_time(stored: &str) -> String {
+    parse_datetime_with_timezone(stored).to_rfc3339()
+}
+
+impl HistoryExport {
+    /// Read the rows selected by `filter` from the database
+    pub fn collect(
+        conn: &Connection,
+        filter: &ExportFilter,
+        now: DateTime<Utc>,
+    ) -> rusqlite::Result<Self> {
+        let mut stmt = conn.prepare(
+            "SELECT a.id, a.deck_id, a.operation_id, o.operation_type, o.operand1, o.operand2,
+                    o.result, a.user_answer, a.is_correct, a.time_spent_seconds, a.created_at,
+                    a.timing_interrupted
+             FROM answers a
+             INNER JOIN operations o ON a.operation_id = o.id
+             WHERE a.user_id = ?1
+             ORDER BY a.id",
+        )?;
+        let answers: Vec<AnswerExport> = stmt
+            .query_map([filter.user_id], |row| {
+                Ok(AnswerExport {
+                    id: row.get(0)?,
+                    deck_id: row.get(1)?,
+                    operation_id: row.get(2)?,
+                    operation_type: row.get(3)?,
+                    operand1: row.get(4)?,
+                    operand2: row.get(5)?,
+                    result: row.get(6)?,
+                    user_answer: row.get(7)?,
+                    is_correct: row.get::<_, i32>(8)? != 0,
+                    time_spent_seconds: row.get(9)?,
+                    created_at: normalize_time(&row.get::<_, String>(10)?),
+                    timing_interrupted: row.get::<_, i32>(11)? != 0,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|answer| {
+                filter.includes_type(&answer.operation_type)
+                    && filter.includes_time(parse_datetime_with_timezone(&answer.created_at))
+            })
+            .collect();
+
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.operation_id, r.repetitions, r.interval, r.ease_factor,
+                    r.next_review_date, r.last_reviewed_date, r.fsrs_stability,
+                    r.fsrs_difficulty, o.operation_type
+             FROM review_items r
+             INNER JOIN operations o ON r.operation_id = o.id
+             WHERE r.user_id = ?1
+             ORDER BY r.id",
+        )?;
+        let review_items: Vec<ReviewItemExport> = stmt
+            .query_map([filter.user_id], |row| {
+                let item = ReviewItemExport {
+                    id: row.get(0)?,
+                    operation_id: row.get(1)?,
+                    repetitions: row.get(2)?,
+                    interval: row.get(3)?,
+                    ease_factor: row.get(4)?,
+                    next_review_date: normalize_time(&row.get::<_, String>(5)?),
+                    last_reviewed_date: row
+                        .get::<_, Option<String>>(6)?
+                        .map(|s| normalize_time(&s)),
+                    fsrs_stability: row.get(7)?,
+                    fsrs_difficulty: row.get(8)?,
+                };
+                Ok((item, row.get::<_, String>(9)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|(item, operation_type)| {
+                let reviewed_in_range = !filter.has_date_range()
+                    || item
+                        .last_reviewed_date
+                        .as_deref()
+                        .is_some_and(|at| filter.includes_time(parse_datetime_with_timezone(at)));
+                filter.includes_type(operation_type) && reviewed_in_range
+            })
+            .map(|(item, _)| item)
+            .collect();
+
+        let used_operations: BTreeSet<i64> = answers
+            .iter()
+            .map(|answer| answer.operation_id)
+            .chain(review_items.iter().map(|item| item.operation_id))
+            .collect();
+        let mut stmt = conn.prepare(
+            "SELECT id, operation_type, operand1, operand2, result FROM operations ORDER BY id",
+        )?;
+        let operations: Vec<OperationExport> = stmt
+            .query_map([], |row| {
+                Ok(OperationExport {
+                    id: row.get(0)?,
+                    operation_type: row.get(1)?,
+                    operand1: row.get(2)?,
+                    operand2: row.get(3)?,
+                    result: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|operation| used_operations.contains(&operation.id))
+            .collect();
+
+        // With an operation type filter, only decks that contain exported answers are relevant
+        let answered_decks: BTreeSet<i64> =
+            answers.iter().filter_map(|answer| answer.deck_id).collect();
+        let mut stmt = conn.prepare(
+            "SELECT id, created_at, completed_at, status, total_questions, correct_answers,
+                    incorrect_answers, total_time_seconds, average_time_seconds,
+                    accuracy_percentage
+             FROM decks
+             WHERE user_id = ?1
+             ORDER BY id",
+        )?;
+        let decks: Vec<DeckExport> = stmt
+            .query_map([filter.user_id], |row| {
+                Ok(DeckExport {
+                    id: row.get(0)?,
+                    created_at: normalize_time(&row.get::<_, String>(1)?),
+                    completed_at: row.get::<_, Option<String>>(2)?.map(|s| normalize_time(&s)),
+                    status: row.get(3)?,
+                    total_questions: row.get(4)?,
+                    correct_answers: row.get(5)?,
+                    incorrect_answers: row.get(6)?,
+                    total_time_seconds: row.get(7)?,
+                    average_time_seconds: row.get(8)?,
+                    accuracy_percentage: row.get(9)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|deck| {
+                filter.includes_time(parse_datetime_with_timezone(&deck.created_at))
+                    && (filter.operation_types.is_empty() || answered_decks.contains(&deck.id))
+            })
+            .collect();
+
+        Ok(HistoryExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: now.to_rfc3339(),
+            operations,
+            decks,
+            answers,
+            review_items,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Write one CSV file per table into `dir`, creating it if needed
+    pub fn write_csv_dir(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(dir)?;
+        write_csv(&dir.join(OPERATIONS_CSV), &self.operations)?;
+        write_csv(&dir.join(DECKS_CSV), &self.decks)?;
+        write_csv(&dir.join(ANSWERS_CSV), &self.answers)?;
+        write_csv(&dir.join(REVIEW_ITEMS_CSV), &self.review_items)?;
+        Ok(())
+    }
+}
+
+fn write_csv<T: Serialize>(path: &Path, rows: &[T]) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::AnswersRepository;
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::operations::OperationsRepository;
+    use crate::database::review_items::ReviewItemsRepository;
+    use chrono::{Duration, TimeZone};
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    /// Two completed decks a week apart: ADD 2+3 in the first, MULTIPLY 3×4 in the second
+    fn populate(conn: &Connection) -> DateTime<Utc> {
+        let first = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+        let ops_repo = OperationsRepository::new(conn);
+        for (when, op_type, a, b, result, answer) in [
+            (first, "ADD", 2, 3, 5, 5),
+            (first + Duration::days(7), "MULTIPLY", 3, 4, 12, 11),
+        ] {
+            let clock = move || when;
+            let decks_repo = DecksRepository::new(conn, DEFAULT_USER_ID, Box::new(clock));
+            let answers_repo =
+                AnswersRepository::new_with_date_provider(conn, DEFAULT_USER_ID, &clock);
+            let review_repo = ReviewItemsRepository::new(conn, DEFAULT_USER_ID, Box::new(clock));
+            let deck_id = decks_repo.create().unwrap();
+            let op_id = ops_repo
+                .insert(op_type, a, b, result, Some(deck_id))
+                .unwrap();
+            answers_repo
+                .insert(op_id, answer, answer == result, 2.5, Some(deck_id))
+                .unwrap();
+            review_repo.insert(op_id, when + Duration::days(1)).unwrap();
+            let mut item = review_repo.get(op_id).unwrap().unwrap();
+            item.last_reviewed_date = Some(when);
+            review_repo.update(&item).unwrap();
+            decks_repo.complete(deck_id).unwrap();
+        }
+        first
+    }
+
+    #[test]
+    fn test_export_everything() {
+        let conn = create_test_db();
+        populate(&conn);
+
+        let export = HistoryExport::collect(&conn, &ExportFilter::default(), Utc::now()).unwrap();
+        assert_eq!(export.format_version, EXPORT_FORMAT_VERSION);
+        assert_eq!(export.operations.len(), 2);
+        assert_eq!(export.decks.len(), 2);
+        assert_eq!(export.review_items.len(), 2);
+        assert_eq!(export.answers.len(), 2);
+
+        let answer = &export.answers[1];
+        assert_eq!(answer.operation_type, "MULTIPLY");
+        assert_eq!(answer.user_answer, 11);
+        assert!(!answer.is_correct);
+        assert_eq!(answer.deck_id, Some(export.decks[1].id));
+        assert_eq!(answer.created_at, "2025-03-08T10:00:00+00:00");
+        assert_eq!(export.decks[1].status, "completed");
+    }
+
+    #[test]
+    fn test_export_filters_by_operation_type() {
+        let conn = create_test_db();
+        populate(&conn);
+
+        let filter = ExportFilter {
+            operation_types: vec!["multiply".to_string()],
+            ..Default::default()
+        };
+        let export = HistoryExport::collect(&conn, &filter, Utc::now()).unwrap();
+        assert_eq!(export.answers.len(), 1);
+        assert_eq!(export.operations.len(), 1);
+        assert_eq!(export.operations[0].operation_type, "MULTIPLY");
+        assert_eq!(export.review_items.len(), 1);
+        assert_eq!(export.decks.len(), 1);
+        assert_eq!(export.decks[0].id, export.answers[0].deck_id.unwrap());
+    }
+
+    #[test]
+    fn test_export_filters_by_date_range() {
+        let conn = create_test_db();
+        let first = populate(&conn);
+
+        let filter = ExportFilter {
+            since: Some(first),
+            until: Some(first + Duration::days(7)),
+            ..Default::default()
+        };
+        let export = HistoryExport::collect(&conn, &filter, Utc::now()).unwrap();
+        assert_eq!(export.answers.len(), 1);
+        assert_eq!(export.answers[0].operation_type, "ADD");
+        assert_eq!(export.decks.len(), 1);
+        assert_eq!(export.review_items.len(), 1);
+        assert_eq!(export.operations.len(), 1);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let conn = create_test_db();
+        populate(&conn);
+
+        let export = HistoryExport::collect(&conn, &ExportFilter::default(), Utc::now()).unwrap();
+        let json = export.to_json().unwrap();
+        let parsed: HistoryExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, export);
+    }
+
+    #[test]
+    fn test_csv_export_writes_one_file_per_table() {
+        let conn = create_test_db();
+        populate(&conn);
+        let dir =
+            std::env::temp_dir().join(format!("memory_practice_export_{}", std::process::id()));
+
+        let export = HistoryExport::collect(&conn, &ExportFilter::default(), Utc::now()).unwrap();
+        export.write_csv_dir(&dir).unwrap();
+
+        let answers = std::fs::read_to_string(dir.join(ANSWERS_CSV)).unwrap();
+        let mut lines = answers.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "id,deck_id,operation_id,operation_type,operand1,operand2,result,user_answer,is_correct,time_spent_seconds,created_at,timing_interrupted"
+        );
+        assert_eq!(lines.count(), 2);
+        for name in [OPERATIONS_CSV, DECKS_CSV, REVIEW_ITEMS_CSV] {
+            assert!(dir.join(name).exists());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_format_from() {
+        assert_eq!(ExportFormat::from("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from("json"), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from("xml"), None);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::spaced_repetition::{ReviewItem, Scheduler, SchedulerKind, retained_progress};
+use chrono::{DateTime, Duration, Utc};
+use sra::sm_2::Quality;
+
+/// Default FSRS-4.5 model weights
+pub const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+const DECAY: f64 = -0.5;
+/// Chosen so that retrievability is 90% when elapsed time equals stability
+const FACTOR: f64 = 19.0 / 81.0;
+const MIN_DIFFICULTY: f64 = 1.0;
+const MAX_DIFFICULTY: f64 = 10.0;
+
+/// FSRS answer rating (1 = Again, 2 = Hard, 3 = Good, 4 = Easy)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rating {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+impl Rating {
+    /// Map an SM-2 quality grade onto the four FSRS ratings
+    pub fn from_quality(quality: Quality) -> Self {
+        match quality {
+            Quality::Grade0 | Quality::Grade1 | Quality::Grade2 => Rating::Again,
+            Quality::Grade3 => Rating::Hard,
+            Quality::Grade4 => Rating::Good,
+            Quality::Grade5 => Rating::Easy,
+        }
+    }
+
+    fn value(self) -> f64 {
+        self as i32 as f64
+    }
+}
+
+/// Free Spaced Repetition Scheduler, modelling memory stability and item difficulty
+pub struct FsrsScheduler {
+    weights: [f64; 17],
+    /// Probability of recall the next review is scheduled for
+    desired_retention: f64,
+}
+
+impl FsrsScheduler {
+    /// Creates a scheduler with the default FSRS-4.5 weights and 90% desired retention
+    pub fn new() -> Self {
+        Self {
+            weights: DEFAULT_WEIGHTS,
+            desired_retention: 0.9,
+        }
+    }
+
+    /// Set the probability of recall targeted when scheduling the next review
+    pub fn with_desired_retention(mut self, desired_retention: f64) -> Self {
+        self.desired_retention = desired_retention.clamp(0.7, 0.99);
+        self
+    }
+
+    /// Probability of recalling an item `elapsed_days` after its last review
+    pub fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+    }
+
+    /// Interval in days until retrievability falls to the desired retention
+    pub fn next_interval(&self, stability: f64) -> i32 {
+        let interval = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        (interval.round() as i32).max(1)
+    }
+
+    fn initial_stability(&self, rating: Rating) -> f64 {
+        self.weights[rating as usize - 1].max(0.1)
+    }
+
+    fn initial_difficulty(&self, rating: Rating) -> f64 {
+        let w = &self.weights;
+        (w[4] - (rating.value() - 3.0) * w[5]).clamp(MIN_DIFFICULTY, MAX_DIFFICULTY)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, rating: Rating) -> f64 {
+        let w = &self.weights;
+        let next = difficulty - w[6] * (rating.value() - 3.0);
+        // Mean reversion towards the initial difficulty of a "Good" answer
+        let reverted = w[7] * self.initial_difficulty(Rating::Good) + (1.0 - w[7]) * next;
+        reverted.clamp(MIN_DIFFICULTY, MAX_DIFFICULTY)
+    }
+
+    fn recall_stability(&self, difficulty: f64, stability: f64, r: f64, rating: Rating) -> f64 {
+        let w = &self.weights;
+        let hard_penalty = if rating == Rating::Hard { w[15] } else { 1.0 };
+        let easy_bonus = if rating == Rating::Easy { w[16] } else { 1.0 };
+        stability
+            * (w[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-w[9])
+                * ((w[10] * (1.0 - r)).exp() - 1.0)
+                * hard_penalty
+                * easy_bonus
+                + 1.0)
+    }
+
+    fn forget_stability(&self, difficulty: f64, stability: f64, r: f64) -> f64 {
+        let w = &self.weights;
+        (w[11]
+            * difficulty.powf(-w[12])
+            * ((stability + 1.0).powf(w[13]) - 1.0)
+            * (w[14] * (1.0 - r)).exp())
+        .min(stability)
+    }
+
+    /// Computes the next (stability, difficulty) for a review at `now`
+    pub fn next_state(&self, item: &ReviewItem, rating: Rating, now: DateTime<Utc>) -> (f64, f64) {
+        match (item.fsrs_stability, item.fsrs_difficulty) {
+            (Some(stability), Some(difficulty)) => {
+                let elapsed_days = item
+                    .last_reviewed_date
+                    .map(|last| (now - last).num_seconds().max(0) as f64 / 86_400.0)
+                    .unwrap_or(0.0);
+                let r = Self::retrievability(elapsed_days, stability);
+
+                let next_stability = if rating == Rating::Again {
+                    self.forget_stability(difficulty, stability, r)
+                } else {
+                    self.recall_stability(difficulty, stability, r, rating)
+                };
+                (next_stability, self.next_difficulty(difficulty, rating))
+            }
+            // First FSRS review of this item (new, or previously scheduled by SM-2)
+            _ => (
+                self.initial_stability(rating),
+                self.initial_difficulty(rating),
+            ),
+        }
+    }
+}
+
+impl Default for FsrsScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for FsrsScheduler {
+    fn kind(&self) -> SchedulerKind {
+        SchedulerKind::Fsrs
+    }
+
+    fn schedule(&self, item: &ReviewItem, quality: Quality, now: DateTime<Utc>) -> ReviewItem {
+        let rating = Rating::from_quality(quality);
+        let (mut stability, difficulty) = self.next_state(item, rating, now);
+        // FSRS has a single failing rating; partial credit keeps part of the lost stability
+        if let Some(previous) = item.fsrs_stability {
+            stability += (previous - stability).max(0.0) * retained_progress(quality);
+        }
+        let interval = self.next_interval(stability);
+
+        ReviewItem {
+            repetitions: if rating == Rating::Again {
+                0
+            } else {
+                item.repetitions + 1
+            },
+            interval,
+            next_review_date: now + Duration::days(interval as i64),
+            last_reviewed_date: Some(now),
+            fsrs_stability: Some(stability),
+            fsrs_difficulty: Some(difficulty),
+            ..item.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_item() -> ReviewItem {
+        ReviewItem {
+            id: Some(1),
+            operation_id: 1,
+            repetitions: 0,
+            interval: 0,
+            ease_factor: 2.5,
+            next_review_date: Utc::now(),
+            last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
+        }
+    }
+
+    #[test]
+    fn test_rating_from_quality() {
+        assert_eq!(Rating::from_quality(Quality::Grade0), Rating::Again);
+        assert_eq!(Rating::from_quality(Quality::Grade2), Rating::Again);
+        assert_eq!(Rating::from_quality(Quality::Grade3), Rating::Hard);
+        assert_eq!(Rating::from_quality(Quality::Grade4), Rating::Good);
+        assert_eq!(Rating::from_quality(Quality::Grade5), Rating::Easy);
+    }
+
+    #[test]
+    fn test_retrievability_is_90_percent_at_stability() {
+        let r = FsrsScheduler::retrievability(10.0, 10.0);
+        assert!((r - 0.9).abs() < 0.001);
+        assert_eq!(FsrsScheduler::retrievability(0.0, 5.0), 1.0);
+    }
+
+    #[test]
+    fn test_next_interval_matches_stability_at_default_retention() {
+        let scheduler = FsrsScheduler::new();
+        assert_eq!(scheduler.next_interval(10.0), 10);
+        assert_eq!(scheduler.next_interval(0.2), 1);
+    }
+
+    #[test]
+    fn test_first_review_initialises_state() {
+        let scheduler = FsrsScheduler::new();
+        let updated = scheduler.schedule(&new_item(), Quality::Grade5, Utc::now());
+
+        assert_eq!(updated.repetitions, 1);
+        assert_eq!(updated.fsrs_stability, Some(DEFAULT_WEIGHTS[3]));
+        assert!(updated.fsrs_difficulty.unwrap() < DEFAULT_WEIGHTS[4]);
+        assert_eq!(updated.interval, 14);
+        assert!(updated.last_reviewed_date.is_some());
+    }
+
+    #[test]
+    fn test_easier_ratings_schedule_longer_intervals() {
+        let scheduler = FsrsScheduler::new();
+        let again = scheduler.schedule(&new_item(), Quality::Grade0, Utc::now());
+        let hard = scheduler.schedule(&new_item(), Quality::Grade3, Utc::now());
+        let good = scheduler.schedule(&new_item(), Quality::Grade4, Utc::now());
+        let easy = scheduler.schedule(&new_item(), Quality::Grade5, Utc::now());
+
+        assert!(again.interval <= hard.interval);
+        assert!(hard.interval <= good.interval);
+        assert!(good.interval <= easy.interval);
+        assert_eq!(again.repetitions, 0);
+    }
+
+    #[test]
+    fn test_successful_review_grows_stability() {
+        let scheduler = FsrsScheduler::new();
+        let mut item = scheduler.schedule(&new_item(), Quality::Grade4, Utc::now());
+        let first_stability = item.fsrs_stability.unwrap();
+
+        // Review when the item is due
+        item.last_reviewed_date = Some(Utc::now() - Duration::days(item.interval as i64));
+        let second = scheduler.schedule(&item, Quality::Grade4, Utc::now());
+
+        assert!(second.fsrs_stability.unwrap() > first_stability);
+        assert!(second.interval > item.interval);
+        assert_eq!(second.repetitions, 2);
+    }
+
+    #[test]
+    fn test_lapse_reduces_stability_and_raises_difficulty() {
+        let scheduler = FsrsScheduler::new();
+        let mut item = scheduler.schedule(&new_item(), Quality::Grade4, Utc::now());
+        item.last_reviewed_date = Some(Utc::now() - Duration::days(item.interval as i64));
+
+        let lapsed = scheduler.schedule(&item, Quality::Grade0, Utc::now());
+        assert!(lapsed.fsrs_stability.unwrap() < item.fsrs_stability.unwrap());
+        assert!(lapsed.fsrs_difficulty.unwrap() > item.fsrs_difficulty.unwrap());
+        assert_eq!(lapsed.repetitions, 0);
+    }
+
+    #[test]
+    fn test_near_miss_keeps_part_of_stability() {
+        let scheduler = FsrsScheduler::new();
+        let mut item = scheduler.schedule(&new_item(), Quality::Grade5, Utc::now());
+        item.last_reviewed_date = Some(Utc::now() - Duration::days(item.interval as i64));
+
+        let blackout = scheduler.schedule(&item, Quality::Grade0, Utc::now());
+        let near_miss = scheduler.schedule(&item, Quality::Grade2, Utc::now());
+        assert!(near_miss.fsrs_stability.unwrap() > blackout.fsrs_stability.unwrap());
+        assert!(near_miss.fsrs_stability.unwrap() < item.fsrs_stability.unwrap());
+        assert!(near_miss.interval > blackout.interval);
+        assert_eq!(near_miss.fsrs_difficulty, blackout.fsrs_difficulty);
+        assert_eq!(near_miss.repetitions, 0);
+    }
+
+    #[test]
+    fn test_sm2_state_is_left_untouched() {
+        let scheduler = FsrsScheduler::new();
+        let updated = scheduler.schedule(&new_item(), Quality::Grade4, Utc::now());
+        assert_eq!(updated.ease_factor, 2.5);
+        assert_eq!(updated.operation_id, 1);
+    }
+}
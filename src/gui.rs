@@ -1,146 +1,259 @@
-use crate::database::{Database, DecksRepository};
-use crate::operations::generate_question_block;
+use crate::charts;
+use crate::database::analytics::{
+    ChartRange, MasteryGrid, MasteryLevel, MasteryRepository, PeriodStatistics, StatisticsSummary,
+    Trends, TrendsRepository,
+};
+use crate::database::{Analytics, Database, ResumableDeck, User, UsersRepository};
+use crate::error_analysis::describe_counts;
+use crate::operations::Operation;
 use crate::quiz_service::{QuestionResult, QuizService};
+use crate::session::{Session, SessionState};
 use crate::time_format::format_time_difference;
-use chrono::Utc;
 use eframe::egui;
-use log::debug;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::Duration;
 
 pub struct MemoryPracticeApp {
     db: Arc<Database>,
-    questions: Vec<crate::operations::Operation>,
-    current_question_index: usize,
+    session: Session,
     user_answers: Vec<String>,
-    question_start_time: Option<Instant>,
-    results: Vec<QuestionResult>,
     state: AppState,
-    current_deck_id: Option<i64>,
-    questions_per_block: usize,
+    mastery_grid: Option<MasteryGrid>,
+    statistics: Option<StatisticsSummary>,
+    chart_range: ChartRange,
+    trends: Option<Trends>,
+    /// Deck left in progress by a crash, offered for resuming on the results screen
+    resumable: Option<ResumableDeck>,
+    /// Name of the active profile
+    profile_name: Option<String>,
+    /// Profiles offered by the profile picker
+    profiles: Vec<User>,
+    /// Name typed into the profile picker for a new profile
+    new_profile_name: String,
+    profile_error: Option<String>,
 }
 
+/// Largest operand shown in the multiplication mastery panel
+const MASTERY_GRID_SIZE: i32 = 12;
+
 #[derive(Debug, PartialEq)]
 pub enum AppState {
+    ChoosingProfile,
     ShowingQuestions,
     ShowingResults,
+    ShowingStatistics,
 }
 
 impl MemoryPracticeApp {
     pub fn new(db: Arc<Database>, questions_per_block: usize) -> Self {
-        Self {
+        let mut app = Self {
+            session: Session::new(db.clone(), questions_per_block),
             db,
-            questions: Vec::new(),
-            current_question_index: 0,
             user_answers: Vec::new(),
-            question_start_time: None,
-            results: Vec::new(),
             state: AppState::ShowingResults,
-            current_deck_id: None,
-            questions_per_block,
-        }
+            mastery_grid: None,
+            statistics: None,
+            chart_range: ChartRange::default(),
+            trends: None,
+            resumable: None,
+            profile_name: None,
+            profiles: Vec::new(),
+            new_profile_name: String::new(),
+            profile_error: None,
+        };
+        app.load_profile();
+        app
     }
 
-    /// Create a QuizService with a reference to the database connection
-    fn create_service(&self) -> QuizService<'_> {
-        QuizService::new(&self.db.conn, self.db.clone())
+    /// Create the app showing the profile picker first
+    pub fn with_profile_picker(db: Arc<Database>, questions_per_block: usize) -> Self {
+        let mut app = Self::new(db, questions_per_block);
+        app.show_profile_picker();
+        app
     }
 
-    fn submit_current_answer(&mut self) {
-        if self.current_question_index >= self.questions.len() {
+    /// Reload the profiles and switch to the profile picker
+    pub fn show_profile_picker(&mut self) {
+        self.profiles = match UsersRepository::new(&self.db.conn).get_all() {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                log::error!("Failed to load profiles: {}", e);
+                Vec::new()
+            }
+        };
+        self.profile_error = None;
+        self.state = AppState::ChoosingProfile;
+    }
+
+    /// Practise as the given profile, leaving the previous profile's deck behind
+    pub fn select_profile(&mut self, user_id: i64) {
+        // Replacing the session first abandons a deck in progress for the previous profile
+        self.session = Session::new(self.db.clone(), self.session.questions_per_block());
+        self.db.set_user(user_id);
+        self.load_profile();
+        self.state = AppState::ShowingResults;
+    }
+
+    /// Load the active profile's name, unfinished deck and mastery grid
+    fn load_profile(&mut self) {
+        self.profile_name = UsersRepository::new(&self.db.conn)
+            .get(self.db.user_id())
+            .ok()
+            .flatten()
+            .map(|user| user.name);
+        self.resumable = self.session.find_resumable();
+        self.refresh_mastery_grid();
+    }
+
+    /// Create a profile named after the text typed into the picker and select it
+    pub fn add_profile(&mut self) {
+        let name = self.new_profile_name.trim().to_string();
+        if name.is_empty() {
             return;
         }
+        match UsersRepository::new(&self.db.conn).get_or_create(&name) {
+            Ok(user_id) => {
+                self.new_profile_name.clear();
+                self.select_profile(user_id);
+            }
+            Err(e) => {
+                log::error!("Failed to create profile '{}': {}", name, e);
+                self.profile_error = Some(format!("Could not create profile: {}", e));
+            }
+        }
+    }
+
+    /// Reload the multiplication mastery heatmap from the database
+    fn refresh_mastery_grid(&mut self) {
+        self.mastery_grid = MasteryRepository::new(&self.db.conn, self.db.user_id())
+            .grid("MULTIPLY", 1..=MASTERY_GRID_SIZE, 1..=MASTERY_GRID_SIZE)
+            .ok();
+    }
 
-        let answer_str = &self.user_answers[self.current_question_index];
+    fn submit_current_answer(&mut self) {
+        let index = self.session.current_index();
+        let Some(answer_str) = self.user_answers.get(index) else {
+            return;
+        };
         if answer_str.is_empty() {
             return;
         }
 
-        if let Ok(user_answer) = answer_str.parse::<i32>() {
-            let question = &self.questions[self.current_question_index];
-            let time_spent = self
-                .question_start_time
-                .map(|start| start.elapsed().as_secs_f64())
-                .unwrap_or(0.0);
-
-            // Use service to process the answer
-            let service = self.create_service();
-            let result = service.process_answer(question, user_answer, time_spent);
-            self.results.push(result);
-
-            // Move to next question
-            self.current_question_index += 1;
-
-            if self.current_question_index >= self.questions.len() {
-                // All questions answered - write results to database and complete deck
-                self.complete_current_deck();
-                self.state = AppState::ShowingResults;
-            } else {
-                self.question_start_time = Some(Instant::now());
-            }
+        if let Ok(user_answer) = answer_str.parse::<i32>()
+            && self.session.submit_answer(user_answer).is_some()
+            && self.session.next_question() == SessionState::Completed
+        {
+            // All questions answered - results were written and the deck completed
+            self.refresh_mastery_grid();
+            self.state = AppState::ShowingResults;
         }
     }
 
-    fn write_results_to_database(&mut self) {
-        if let Some(deck_id) = self.current_deck_id {
-            let service = self.create_service();
-            self.results = service.persist_results(&self.results, deck_id);
-        }
+    /// Hide the current question and stop its answer time
+    pub fn pause_question(&mut self) {
+        self.session.pause();
+    }
+
+    /// Show the paused question again and continue its answer time
+    pub fn resume_question(&mut self) {
+        self.session.unpause();
     }
 
-    fn complete_current_deck(&mut self) {
-        if let Some(deck_id) = self.current_deck_id {
-            // Write all results to database first
-            self.write_results_to_database();
+    pub fn is_question_paused(&self) -> bool {
+        self.session.state() == SessionState::Paused
+    }
 
-            // Use service to complete the deck
-            let service = self.create_service();
-            service.complete_deck(deck_id, &self.results);
+    /// Pause the question when the window loses focus or input stops for the idle timeout
+    fn track_activity(&mut self, ctx: &egui::Context) {
+        let (focused, active) = ctx.input(|i| {
+            (
+                i.viewport().focused,
+                !i.events.is_empty() || i.pointer.is_moving(),
+            )
+        });
+        if focused == Some(false) {
+            self.session.pause();
+        } else if active {
+            self.session.record_activity();
         }
+        self.session.check_idle();
+        // Keep checking while nothing else repaints the window
+        ctx.request_repaint_after(Duration::from_secs(1));
     }
 
-    pub fn start_new_block(&mut self) {
-        // Mark previous deck as abandoned if not completed
-        if let Some(deck_id) = self.current_deck_id
-            && self.state != AppState::ShowingResults
+    /// Load the latest statistics and switch to the statistics screen
+    pub fn show_statistics(&mut self) {
+        let now = self.db.get_current_time();
+        self.statistics = match Analytics::new(&self.db.conn, self.db.user_id()).summary(now) {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                log::error!("Failed to load statistics: {}", e);
+                None
+            }
+        };
+        self.refresh_trends();
+        self.state = AppState::ShowingStatistics;
+    }
+
+    /// Change the range of the trend charts and reload them
+    pub fn set_chart_range(&mut self, range: ChartRange) {
+        self.chart_range = range;
+        self.refresh_trends();
+    }
+
+    fn refresh_trends(&mut self) {
+        let now = self.db.get_current_time();
+        self.trends = match TrendsRepository::new(&self.db.conn, self.db.user_id())
+            .for_range(self.chart_range, now)
         {
-            let self1 = &self.db;
-            let repo = DecksRepository::new(&self1.conn, Box::new(|| self1.get_current_time()));
-            let _ = repo.abandon(deck_id);
-        }
+            Ok(trends) => Some(trends),
+            Err(e) => {
+                log::error!("Failed to load trends: {}", e);
+                None
+            }
+        };
+    }
+
+    /// Leave the statistics screen and return to the last deck results
+    pub fn close_statistics(&mut self) {
+        self.state = AppState::ShowingResults;
+    }
 
-        // Create new deck
-        let self1 = &self.db;
-        let current_time = self1.get_current_time();
-        let repo = DecksRepository::new(&self1.conn, Box::new(move || current_time));
-        self.current_deck_id = repo.create().ok();
-
-        // Fetch due reviews using service
-        let service = self.create_service();
-        let mut questions = service.fetch_due_reviews();
-
-        // Generate new questions to fill the block
-        let mut new_questions =
-            generate_question_block(self.questions_per_block.saturating_sub(questions.len()));
-        questions.append(&mut new_questions);
-
-        // If we have fewer questions than requested, generate more
-        if questions.len() < self.questions_per_block {
-            let needed = self.questions_per_block - questions.len();
-            let mut more_questions = generate_question_block(needed);
-            questions.append(&mut more_questions);
+    /// Start a new deck; a deck still in progress, or left unfinished by a crash, is abandoned
+    pub fn start_new_block(&mut self) {
+        self.discard_unfinished_deck();
+        self.session.start();
+        self.show_questions();
+    }
+
+    /// Continue the deck left unfinished by a crash from its next unanswered question
+    pub fn resume_deck(&mut self) {
+        let Some(deck) = self.resumable.take() else {
+            return;
+        };
+        self.session.resume(deck);
+        if self.session.is_in_progress() {
+            self.show_questions();
+        } else {
+            self.refresh_mastery_grid();
+            self.state = AppState::ShowingResults;
         }
+    }
 
-        // Limit to exactly questions_per_block
-        questions.truncate(self.questions_per_block);
+    /// Save the answers of the deck left unfinished by a crash and mark it abandoned
+    pub fn discard_unfinished_deck(&mut self) {
+        if let Some(deck) = self.resumable.take() {
+            self.session.discard(deck);
+        }
+    }
 
-        debug!("Started new block with {} questions", questions.len());
+    /// Deck left in progress by a crash, if it has not been resumed or discarded
+    pub fn get_resumable_deck(&self) -> Option<&ResumableDeck> {
+        self.resumable.as_ref()
+    }
 
-        self.questions = questions;
-        self.user_answers = vec![String::new(); self.questions.len()];
-        self.current_question_index = 0;
-        self.question_start_time = Some(Instant::now());
-        self.results.clear();
+    fn show_questions(&mut self) {
+        self.user_answers = vec![String::new(); self.session.questions().len()];
         self.state = AppState::ShowingQuestions;
     }
 
@@ -150,11 +263,15 @@ impl MemoryPracticeApp {
     }
 
     pub fn get_current_question_index(&self) -> usize {
-        self.current_question_index
+        self.session.current_index()
+    }
+
+    pub fn get_questions(&self) -> &[Operation] {
+        self.session.questions()
     }
 
     pub fn get_results(&self) -> &[QuestionResult] {
-        &self.results
+        self.session.results()
     }
 
     pub fn set_answer(&mut self, index: usize, answer: String) {
@@ -168,40 +285,81 @@ impl MemoryPracticeApp {
     }
 
     pub fn get_current_deck_id(&self) -> Option<i64> {
-        self.current_deck_id
+        self.session.deck_id()
     }
-}
 
-impl Drop for MemoryPracticeApp {
-    fn drop(&mut self) {
-        // When app closes, if deck is in progress (not completed), write results and abandon
-        if let Some(_deck_id) = self.current_deck_id
-            && self.state != AppState::ShowingResults
-        {
-            // Write any answers that were collected to database before abandoning
-            self.write_results_to_database();
-            let self1 = &self.db;
-            let repo = DecksRepository::new(&self1.conn, Box::new(|| self1.get_current_time()));
-            let _ = repo.abandon(_deck_id);
-        }
+    pub fn get_profiles(&self) -> &[User] {
+        &self.profiles
+    }
+
+    pub fn set_new_profile_name(&mut self, name: String) {
+        self.new_profile_name = name;
     }
 }
 
 impl eframe::App for MemoryPracticeApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.state == AppState::ShowingQuestions {
+            self.track_activity(ctx);
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.state {
+                AppState::ChoosingProfile => {
+                    ui.heading("Who is practising?");
+                    ui.add_space(20.0);
+
+                    let mut selected = None;
+                    for profile in &self.profiles {
+                        if ui.button(&profile.name).clicked() {
+                            selected = Some(profile.id);
+                        }
+                    }
+
+                    ui.add_space(20.0);
+                    let add_clicked = ui
+                        .horizontal(|ui| {
+                            ui.label("New profile:");
+                            let response = ui.text_edit_singleline(&mut self.new_profile_name);
+                            let enter_pressed = response.lost_focus()
+                                && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            ui.button("Add Profile").clicked() || enter_pressed
+                        })
+                        .inner;
+                    if let Some(e) = &self.profile_error {
+                        ui.label(egui::RichText::new(e).color(egui::Color32::RED));
+                    }
+
+                    if let Some(user_id) = selected {
+                        self.select_profile(user_id);
+                    } else if add_clicked {
+                        self.add_profile();
+                    }
+                }
                 AppState::ShowingQuestions => {
                     ui.heading("Memory Practice - Math Operations");
+                    show_save_error(ui, &self.session);
                     ui.add_space(20.0);
 
-                    if self.current_question_index < self.questions.len() {
-                        let question = &self.questions[self.current_question_index];
+                    if self.is_question_paused() {
+                        ui.label(format!(
+                            "Question {} of {}",
+                            self.session.current_index() + 1,
+                            self.session.questions().len()
+                        ));
+                        ui.add_space(20.0);
+                        ui.heading(egui::RichText::new("Paused").size(32.0).strong());
+                        ui.label("The clock is stopped. The question is hidden until you resume.");
+                        ui.add_space(10.0);
+                        if ui.button("Resume").clicked() {
+                            self.resume_question();
+                        }
+                    } else if let Some(question) = self.session.current_question() {
+                        let index = self.session.current_index();
 
                         ui.label(format!(
                             "Question {} of {}",
-                            self.current_question_index + 1,
-                            self.questions.len()
+                            index + 1,
+                            self.session.questions().len()
                         ));
                         ui.add_space(20.0);
 
@@ -216,9 +374,7 @@ impl eframe::App for MemoryPracticeApp {
                         // Answer input
                         ui.horizontal(|ui| {
                             ui.label("Your answer:");
-                            let response = ui.text_edit_singleline(
-                                &mut self.user_answers[self.current_question_index],
-                            );
+                            let response = ui.text_edit_singleline(&mut self.user_answers[index]);
 
                             // Auto-focus the text input
                             if !response.lost_focus() {
@@ -235,8 +391,18 @@ impl eframe::App for MemoryPracticeApp {
 
                         ui.add_space(10.0);
 
-                        if ui.button("Submit Answer").clicked() {
+                        let (submit_clicked, pause_clicked) = ui
+                            .horizontal(|ui| {
+                                (
+                                    ui.button("Submit Answer").clicked(),
+                                    ui.button("Pause").clicked(),
+                                )
+                            })
+                            .inner;
+                        if submit_clicked {
                             self.submit_current_answer();
+                        } else if pause_clicked {
+                            self.pause_question();
                         }
                     }
                 }
@@ -244,18 +410,25 @@ impl eframe::App for MemoryPracticeApp {
                     ui.heading("Deck Results");
                     ui.add_space(10.0);
 
-                    if let Some(deck_id) = self.current_deck_id {
+                    if let Some(name) = &self.profile_name {
+                        ui.label(format!("Profile: {}", name));
+                    }
+
+                    if let Some(deck_id) = self.session.deck_id() {
                         ui.label(format!("Deck ID: {}", deck_id));
                     }
+                    show_save_error(ui, &self.session);
                     ui.add_space(10.0);
 
-                    let total = self.results.len();
+                    let results = self.session.results();
+                    let total = results.len();
+                    let now = self.db.get_current_time();
                     if total == 0 {
                         ui.label("No results yet. Click 'Start new Deck' to begin.");
                     } else {
-                        let correct_count = self.results.iter().filter(|r| r.is_correct).count();
+                        let correct_count = results.iter().filter(|r| r.is_correct).count();
                         let average_time = if total > 0 {
-                            self.results.iter().map(|r| r.time_spent).sum::<f64>() / total as f64
+                            results.iter().map(|r| r.time_spent).sum::<f64>() / total as f64
                         } else {
                             0.0
                         };
@@ -274,7 +447,7 @@ impl eframe::App for MemoryPracticeApp {
 
                         // Show detailed results
                         egui::ScrollArea::vertical().show(ui, |ui| {
-                            for (i, result) in self.results.iter().enumerate() {
+                            for (i, result) in results.iter().enumerate() {
                                 ui.horizontal(|ui| {
                                     let status = if result.is_correct { "✓" } else { "✗" };
                                     let color = if result.is_correct {
@@ -289,6 +462,13 @@ impl eframe::App for MemoryPracticeApp {
                                     ui.label(format!("(Your answer: {})", result.user_answer));
                                     ui.label(egui::RichText::new(status).color(color).strong());
                                     ui.label(format!("{:.2}s", result.time_spent));
+                                    if result.timing_interrupted {
+                                        ui.label("(paused)");
+                                    }
+
+                                    if let Some(kind) = result.error_kind {
+                                        ui.label(egui::RichText::new(kind.label()).italics());
+                                    }
 
                                     // Display grade if available
                                     if let Some(grade) = result.grade {
@@ -297,8 +477,7 @@ impl eframe::App for MemoryPracticeApp {
 
                                     // Display next review date if available
                                     if let Some(next_date) = result.next_review_date {
-                                        let time_until =
-                                            format_time_difference(Utc::now(), next_date);
+                                        let time_until = format_time_difference(now, next_date);
                                         ui.label(format!("Next: {}", time_until));
                                     }
                                 });
@@ -308,11 +487,91 @@ impl eframe::App for MemoryPracticeApp {
 
                     ui.add_space(20.0);
 
-                    let button_clicked = ui.button("Start new Deck").clicked();
+                    if let Some(grid) = &self.mastery_grid {
+                        show_mastery_grid(ui, grid);
+                        ui.add_space(10.0);
+                    }
+
+                    let (mut continue_clicked, mut discard_clicked) = (false, false);
+                    if let Some(deck) = &self.resumable {
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "Unfinished deck from {} ({} of {} answered)",
+                                deck.created_at
+                                    .with_timezone(&chrono::Local)
+                                    .format("%Y-%m-%d %H:%M"),
+                                deck.answered(),
+                                deck.total()
+                            ))
+                            .strong(),
+                        );
+                        (continue_clicked, discard_clicked) = ui
+                            .horizontal(|ui| {
+                                (
+                                    ui.button("Continue Deck").clicked(),
+                                    ui.button("Discard").clicked(),
+                                )
+                            })
+                            .inner;
+                        ui.add_space(10.0);
+                    }
+
+                    let (button_clicked, statistics_clicked, switch_clicked) = ui
+                        .horizontal(|ui| {
+                            (
+                                ui.button("Start new Deck").clicked(),
+                                ui.button("Statistics").clicked(),
+                                ui.button("Switch Profile").clicked(),
+                            )
+                        })
+                        .inner;
                     let enter_pressed = ui.input(|i| i.key_pressed(egui::Key::Enter));
 
-                    if button_clicked || enter_pressed {
+                    // Enter continues the unfinished deck when one is offered
+                    if continue_clicked || (enter_pressed && self.resumable.is_some()) {
+                        self.resume_deck();
+                    } else if discard_clicked {
+                        self.discard_unfinished_deck();
+                    } else if button_clicked || enter_pressed {
                         self.start_new_block();
+                    } else if statistics_clicked {
+                        self.show_statistics();
+                    } else if switch_clicked {
+                        self.show_profile_picker();
+                    }
+                }
+                AppState::ShowingStatistics => {
+                    ui.heading("Statistics");
+                    ui.add_space(10.0);
+
+                    let mut chart_range = self.chart_range;
+                    let mut range_changed = false;
+                    egui::ScrollArea::vertical()
+                        .max_height(ui.available_height() - 40.0)
+                        .show(ui, |ui| {
+                            match &self.statistics {
+                                Some(summary) => show_statistics_summary(ui, summary),
+                                None => {
+                                    ui.label("Statistics are unavailable.");
+                                }
+                            }
+
+                            ui.add_space(10.0);
+                            ui.label(egui::RichText::new("Trends").strong());
+                            range_changed = charts::show_range_selector(ui, &mut chart_range);
+                            if let Some(trends) = &self.trends {
+                                charts::show_trends(ui, trends);
+                            }
+                        });
+                    if range_changed {
+                        self.set_chart_range(chart_range);
+                    }
+
+                    ui.add_space(10.0);
+                    let back_clicked = ui.button("Back").clicked();
+                    let escape_pressed = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                    if back_clicked || escape_pressed {
+                        self.close_statistics();
                     }
                 }
             }
@@ -320,7 +579,164 @@ impl eframe::App for MemoryPracticeApp {
     }
 }
 
-pub fn run_app(db: Arc<Database>, is_test_mode: bool) -> Result<(), eframe::Error> {
+/// Tell the user that the last deck's results were not saved
+fn show_save_error(ui: &mut egui::Ui, session: &Session) {
+    if let Some(e) = session.save_error() {
+        ui.label(
+            egui::RichText::new(format!("Your results could not be saved: {}", e))
+                .color(egui::Color32::RED)
+                .strong(),
+        );
+    }
+}
+
+/// Streak, due items and per-operation accuracy and timing for each period
+fn show_statistics_summary(ui: &mut egui::Ui, summary: &StatisticsSummary) {
+    ui.label(format!(
+        "Consecutive days streak: {} days",
+        summary.streak_days
+    ));
+    ui.label(format!("Reviews due today: {}", summary.due_today));
+    ui.add_space(10.0);
+
+    if summary.operations.is_empty() {
+        ui.label("No completed decks yet.");
+        return;
+    }
+
+    for operation in &summary.operations {
+        ui.label(egui::RichText::new(&operation.operation_type).strong());
+        egui::Grid::new(format!("statistics_{}", operation.operation_type))
+            .striped(true)
+            .spacing([16.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label("Accuracy");
+                ui.label("Average time");
+                ui.label("Std Dev");
+                ui.end_row();
+                for (label, period) in [
+                    ("Global (all time)", &operation.global),
+                    ("Last 30 days", &operation.last_30_days),
+                    ("Last 10 decks", &operation.last_10_decks),
+                ] {
+                    show_period_row(ui, label, period);
+                }
+            });
+        if !operation.mistakes_last_30_days.is_empty() {
+            ui.label(format!(
+                "Mistakes (last 30 days): {}",
+                describe_counts(&operation.mistakes_last_30_days)
+            ));
+        }
+        ui.add_space(10.0);
+    }
+
+    ui.label(egui::RichText::new("All operations").strong());
+    for (label, total) in [
+        ("Global (all time)", summary.total_global),
+        ("Last 30 days", summary.total_last_30_days),
+        ("Last 10 decks", summary.total_last_10_decks),
+    ] {
+        match total {
+            Some((correct, count, accuracy)) => ui.label(format!(
+                "{}: {}/{} correct ({:.1}%)",
+                label, correct, count, accuracy
+            )),
+            None => ui.label(format!("{}: No data available", label)),
+        };
+    }
+}
+
+fn show_period_row(ui: &mut egui::Ui, label: &str, period: &PeriodStatistics) {
+    ui.label(label);
+    match period.accuracy {
+        Some((correct, total, accuracy)) => {
+            let color = if accuracy >= 90.0 {
+                egui::Color32::GREEN
+            } else if accuracy >= 75.0 {
+                egui::Color32::YELLOW
+            } else {
+                egui::Color32::RED
+            };
+            ui.label(
+                egui::RichText::new(format!("{}/{} ({:.1}%)", correct, total, accuracy))
+                    .color(color),
+            );
+        }
+        None => {
+            ui.label("-");
+        }
+    }
+    match period.timing {
+        Some(timing) => {
+            ui.label(format!("{:.2}s", timing.average));
+            ui.label(format!("{:.2}s", timing.standard_deviation));
+        }
+        None => {
+            ui.label("-");
+            ui.label("-");
+        }
+    }
+    ui.end_row();
+}
+
+/// Multiplication table colored by mastery level, with details on hover
+fn show_mastery_grid(ui: &mut egui::Ui, grid: &MasteryGrid) {
+    egui::CollapsingHeader::new("Multiplication mastery")
+        .default_open(false)
+        .show(ui, |ui| {
+            egui::Grid::new("mastery_grid")
+                .spacing([4.0, 2.0])
+                .show(ui, |ui| {
+                    ui.label("×");
+                    for column in grid.columns.clone() {
+                        ui.label(egui::RichText::new(column.to_string()).strong());
+                    }
+                    ui.end_row();
+
+                    for row in grid.rows.clone() {
+                        ui.label(egui::RichText::new(row.to_string()).strong());
+                        for column in grid.columns.clone() {
+                            let Some(cell) = grid.get(row, column) else {
+                                continue;
+                            };
+                            let color = match cell.level() {
+                                MasteryLevel::Mastered => egui::Color32::GREEN,
+                                MasteryLevel::Learning => egui::Color32::YELLOW,
+                                MasteryLevel::Struggling => egui::Color32::RED,
+                                MasteryLevel::Unseen => egui::Color32::GRAY,
+                            };
+                            let text = cell
+                                .accuracy()
+                                .map(|accuracy| format!("{:.0}%", accuracy))
+                                .unwrap_or_else(|| "-".to_string());
+                            let median = cell
+                                .median_time
+                                .map(|time| format!("{:.1}s", time))
+                                .unwrap_or_else(|| "-".to_string());
+                            let interval = cell
+                                .interval
+                                .map(|days| format!("{} days", days))
+                                .unwrap_or_else(|| "-".to_string());
+                            ui.label(egui::RichText::new(text).color(color).monospace())
+                                .on_hover_text(format!(
+                                    "{} × {}: {}/{} correct, median {}, interval {}",
+                                    row, column, cell.correct, cell.total, median, interval
+                                ));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// Run the GUI; with `choose_profile` it asks who is practising before the first deck
+pub fn run_app(
+    db: Arc<Database>,
+    is_test_mode: bool,
+    choose_profile: bool,
+) -> Result<(), eframe::Error> {
     // In test mode, use 1 question per block; in production, use 10
     let questions_per_block = if is_test_mode { 1 } else { 10 };
 
@@ -333,20 +749,46 @@ pub fn run_app(db: Arc<Database>, is_test_mode: bool) -> Result<(), eframe::Erro
         "Memory Practice",
         options,
         Box::new(move |_cc| {
-            Ok(Box::new(MemoryPracticeApp::new(
-                db.clone(),
-                questions_per_block,
-            )))
+            let app = if choose_profile {
+                MemoryPracticeApp::with_profile_picker(db.clone(), questions_per_block)
+            } else {
+                MemoryPracticeApp::new(db.clone(), questions_per_block)
+            };
+            Ok(Box::new(app))
         }),
     )
 }
 
 #[cfg(test)]
+#[allow(clippy::arc_with_non_send_sync)]
 mod tests {
     use super::*;
-    use crate::database::{AnswersRepository, OperationsRepository};
+    use crate::database::{
+        AnswersRepository, DEFAULT_USER_ID, DecksRepository, OperationsRepository,
+    };
     use crate::deck::DeckStatus;
 
+    #[test]
+    fn test_adding_profile_switches_to_it() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut app = MemoryPracticeApp::with_profile_picker(db.clone(), 1);
+        assert_eq!(app.state, AppState::ChoosingProfile);
+        assert_eq!(app.get_profiles().len(), 1);
+
+        app.set_new_profile_name("  Alice ".to_string());
+        app.add_profile();
+        assert_eq!(app.state, AppState::ShowingResults);
+        assert_eq!(app.profile_name.as_deref(), Some("Alice"));
+        assert_ne!(db.user_id(), DEFAULT_USER_ID);
+
+        app.start_new_block();
+        let deck_id = app.get_current_deck_id().unwrap();
+        let decks =
+            |user_id| DecksRepository::new(&db.conn, user_id, Box::new(|| db.get_current_time()));
+        assert!(decks(DEFAULT_USER_ID).get(deck_id).unwrap().is_none());
+        assert!(decks(db.user_id()).get(deck_id).unwrap().is_some());
+    }
+
     #[test]
     fn test_deck_abandoned_on_drop_during_questions() {
         let db = Arc::new(Database::new(":memory:").unwrap());
@@ -355,7 +797,11 @@ mod tests {
             app.start_new_block();
             let deck_id = app.get_current_deck_id().expect("Deck should be created");
             // Verify deck was created as in_progress
-            let repo = DecksRepository::new(&db.conn, Box::new(|| db.get_current_time()));
+            let repo = DecksRepository::new(
+                &db.conn,
+                DEFAULT_USER_ID,
+                Box::new(|| db.get_current_time()),
+            );
             let deck = repo
                 .get(deck_id)
                 .expect("Database access should succeed")
@@ -365,7 +811,11 @@ mod tests {
         }
 
         // After drop, the deck should be marked as abandoned
-        let repo = DecksRepository::new(&db.conn, Box::new(|| db.get_current_time()));
+        let repo = DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        );
         let deck = repo
             .get(1)
             .expect("Database access should succeed")
@@ -386,14 +836,18 @@ mod tests {
             let deck_id = app.get_current_deck_id().expect("Deck should be created");
 
             // Simulate completing the deck
+            app.session.complete();
             app.state = AppState::ShowingResults;
-            app.complete_current_deck();
 
             deck_id
         };
 
         // After drop, the deck should still be completed (not abandoned)
-        let repo = DecksRepository::new(&db.conn, Box::new(|| db.get_current_time()));
+        let repo = DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        );
         let deck = repo
             .get(deck_id)
             .expect("Database access should succeed")
@@ -405,6 +859,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mastery_grid_refreshed_after_completion() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut app = MemoryPracticeApp::new(db.clone(), 1);
+        let grid = app.mastery_grid.as_ref().expect("Grid should be loaded");
+        assert_eq!(grid.cells.len(), 144);
+        assert!(grid.cells.iter().all(|cell| cell.total == 0));
+
+        app.session
+            .start_with_questions(vec![crate::operations::Operation::new(
+                crate::operations::OperationType::Multiplication,
+                7,
+                8,
+            )]);
+        app.show_questions();
+        app.set_answer(0, "56".to_string());
+        app.submit_answer();
+
+        let cell = app.mastery_grid.as_ref().unwrap().get(7, 8).unwrap();
+        assert_eq!((cell.correct, cell.total), (1, 1));
+        assert!(cell.interval.is_some());
+    }
+
+    #[test]
+    fn test_statistics_screen_keeps_completed_deck() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let deck_id = {
+            let mut app = MemoryPracticeApp::new(db.clone(), 1);
+            app.start_new_block();
+            let expected_answer = app.session.questions()[0].result;
+            app.set_answer(0, expected_answer.to_string());
+            app.submit_answer();
+
+            app.show_statistics();
+            assert_eq!(app.state, AppState::ShowingStatistics);
+            let summary = app.statistics.as_ref().expect("Statistics should load");
+            assert_eq!(summary.total_global, Some((1, 1, 100.0)));
+            assert_eq!(summary.streak_days, 1);
+
+            let trends = app.trends.as_ref().expect("Trends should load");
+            assert_eq!(trends.range, ChartRange::Last30Days);
+            assert_eq!(trends.decks.len(), 1);
+
+            app.set_chart_range(ChartRange::AllTime);
+            assert_eq!(app.trends.as_ref().unwrap().range, ChartRange::AllTime);
+
+            app.get_current_deck_id().unwrap()
+            // app drops while on the statistics screen
+        };
+
+        let repo = DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        );
+        let deck = repo.get(deck_id).unwrap().unwrap();
+        assert_eq!(deck.status, DeckStatus::Completed);
+    }
+
+    #[test]
+    fn test_failed_save_is_reported_and_rolled_back() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut app = MemoryPracticeApp::new(db.clone(), 1);
+        app.start_new_block();
+        let deck_id = app.get_current_deck_id().unwrap();
+
+        // The review log write happens last; its failure must undo the answer and review item
+        db.conn.execute("DROP TABLE review_log", []).unwrap();
+        let expected_answer = app.session.questions()[0].result;
+        app.set_answer(0, expected_answer.to_string());
+        app.submit_answer();
+
+        assert_eq!(app.state, AppState::ShowingResults);
+        assert!(app.session.save_error().is_some());
+        assert_eq!(app.get_results().len(), 1);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            0
+        );
+        assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 0);
+        let repo = DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        );
+        assert_eq!(
+            repo.get(deck_id).unwrap().unwrap().status,
+            DeckStatus::InProgress
+        );
+    }
+
     #[test]
     fn test_app_uses_correct_question_count() {
         let db = Arc::new(Database::new(":memory:").unwrap());
@@ -412,18 +959,37 @@ mod tests {
         // Test mode: 1 question per block
         let mut app_test = MemoryPracticeApp::new(db.clone(), 1);
         app_test.start_new_block();
-        assert_eq!(app_test.questions_per_block, 1);
-        assert_eq!(app_test.questions.len(), 1);
+        assert_eq!(app_test.session.questions_per_block(), 1);
+        assert_eq!(app_test.session.questions().len(), 1);
         assert_eq!(app_test.user_answers.len(), 1);
 
         // Production mode: 10 questions per block
         let mut app_prod = MemoryPracticeApp::new(db.clone(), 10);
         app_prod.start_new_block();
-        assert_eq!(app_prod.questions_per_block, 10);
-        assert_eq!(app_prod.questions.len(), 10);
+        assert_eq!(app_prod.session.questions_per_block(), 10);
+        assert_eq!(app_prod.session.questions().len(), 10);
         assert_eq!(app_prod.user_answers.len(), 10);
     }
 
+    #[test]
+    fn test_paused_question_takes_no_answer() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut app = MemoryPracticeApp::new(db, 2);
+        app.start_new_block();
+
+        app.pause_question();
+        assert!(app.is_question_paused());
+        app.set_answer(0, "42".to_string());
+        app.submit_answer();
+        assert!(app.session.results().is_empty());
+
+        app.resume_question();
+        assert!(!app.is_question_paused());
+        app.submit_answer();
+        assert_eq!(app.session.results().len(), 1);
+        assert!(app.session.results()[0].timing_interrupted);
+    }
+
     #[test]
     fn test_answers_not_written_immediately() {
         let db = Arc::new(Database::new(":memory:").unwrap());
@@ -436,8 +1002,8 @@ mod tests {
         app.submit_answer();
 
         // Answer should be in memory
-        assert_eq!(app.results.len(), 1);
-        assert_eq!(app.results[0].user_answer, 42);
+        assert_eq!(app.session.results().len(), 1);
+        assert_eq!(app.session.results()[0].user_answer, 42);
 
         // But NOT in the database yet (deck is incomplete)
         let repo = OperationsRepository::new(&db.conn);
@@ -456,7 +1022,7 @@ mod tests {
         let _deck_id = app.get_current_deck_id().expect("Deck should be created");
 
         // Get the expected answer from the question
-        let expected_answer = app.questions[0].result;
+        let expected_answer = app.session.questions()[0].result;
 
         // Submit the correct answer
         app.set_answer(0, expected_answer.to_string());
@@ -484,7 +1050,7 @@ mod tests {
             "Operation type should exist"
         );
 
-        let repo1 = AnswersRepository::new(&db.conn);
+        let repo1 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
         let answer = repo1
             .get(1)
             .expect("Database access should succeed")
@@ -521,7 +1087,11 @@ mod tests {
             "Answers should be written when app closes with incomplete deck"
         );
 
-        let repo1 = DecksRepository::new(&db.conn, Box::new(|| db.get_current_time()));
+        let repo1 = DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        );
         let deck = repo1
             .get(1)
             .expect("Database access should succeed")
@@ -556,11 +1126,11 @@ mod tests {
         // Verify all answers are in database
         for i in 1..=3 {
             let answer_id = i as i64;
-            let repo1 = AnswersRepository::new(&db.conn);
+            let repo1 = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
             let answer = repo1
                 .get(answer_id)
                 .expect("Database access should succeed")
-                .expect(&format!("Answer {} should exist", i));
+                .unwrap_or_else(|| panic!("Answer {} should exist", i));
             assert!(answer.user_answer >= 0);
         }
     }
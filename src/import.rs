@@ -0,0 +1,764 @@
+use crate::database::connection::{latest_schema_version, open_read_only, schema_version};
+use crate::database::{OperationsRepository, UsersRepository};
+use crate::error_analysis::classify;
+use crate::export::{
+    ANSWERS_CSV, DECKS_CSV, ExportFilter, HistoryExport, OPERATIONS_CSV, REVIEW_ITEMS_CSV,
+};
+use crate::operations::{Operation, OperationType};
+use crate::row_factories::try_parse_datetime_with_timezone;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, params};
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// What an import added to or changed in the target database
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub operations_added: usize,
+    pub decks_added: usize,
+    pub answers_added: usize,
+    /// Answers already present in the target (same fact, time, answer and duration)
+    pub answers_skipped: usize,
+    pub review_items_added: usize,
+    /// Review items replaced because the imported state was reviewed more recently
+    pub review_items_updated: usize,
+}
+
+/// Why an import file or merge source was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// A timestamp in none of the known formats
+    InvalidTimestamp {
+        table: &'static str,
+        /// 1-based position in the import, or the id of a row already in the target database
+        row: i64,
+        value: String,
+    },
+    /// The database to merge was last opened by an older version and is not fully migrated
+    OutdatedSchema { found: Option<u32>, expected: u32 },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::InvalidTimestamp { table, row, value } => {
+                write!(f, "Invalid timestamp '{}' in {} row {}", value, table, row)
+            }
+            ImportError::OutdatedSchema { found, expected } => write!(
+                f,
+                "The database to merge has schema version {} (expected {}); open it once with \
+                 this version of the application to upgrade it",
+                found.map_or("none".to_string(), |v| v.to_string()),
+                expected
+            ),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+fn parse_timestamp(
+    table: &'static str,
+    row: i64,
+    value: &str,
+) -> Result<DateTime<Utc>, ImportError> {
+    try_parse_datetime_with_timezone(value).ok_or_else(|| ImportError::InvalidTimestamp {
+        table,
+        row,
+        value: value.to_string(),
+    })
+}
+
+/// 1-based position of the `index`th row of an imported table
+fn position(index: usize) -> i64 {
+    index as i64 + 1
+}
+
+/// Open another practice database to merge from, read-only and without migrating it
+///
+/// The source is never written; one older than this build's schema is rejected.
+pub fn open_merge_source(path: &Path) -> Result<Connection, Box<dyn Error>> {
+    let conn = open_read_only(&path.to_string_lossy())?;
+    let found = schema_version(&conn)?;
+    let expected = latest_schema_version();
+    if found.is_none_or(|version| version < expected) {
+        return Err(ImportError::OutdatedSchema { found, expected }.into());
+    }
+    Ok(conn)
+}
+
+/// Read an export written with `--format json`
+pub fn read_json(path: &Path) -> Result<HistoryExport, Box<dyn Error>> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Read an export written with `--format csv` (one file per table)
+pub fn read_csv_dir(dir: &Path) -> Result<HistoryExport, Box<dyn Error>> {
+    Ok(HistoryExport {
+        format_version: crate::export::EXPORT_FORMAT_VERSION,
+        exported_at: String::new(),
+        operations: read_csv(&dir.join(OPERATIONS_CSV))?,
+        decks: read_csv(&dir.join(DECKS_CSV))?,
+        answers: read_csv(&dir.join(ANSWERS_CSV))?,
+        review_items: read_csv(&dir.join(REVIEW_ITEMS_CSV))?,
+    })
+}
+
+fn read_csv<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let rows = reader.deserialize().collect::<Result<Vec<T>, _>>()?;
+    Ok(rows)
+}
+
+/// Read the complete history of one profile of another database
+pub fn read_database(
+    conn: &Connection,
+    user_id: i64,
+    now: DateTime<Utc>,
+) -> rusqlite::Result<HistoryExport> {
+    let filter = ExportFilter {
+        user_id,
+        ..ExportFilter::default()
+    };
+    HistoryExport::collect(conn, &filter, now)
+}
+
+/// Key identifying a deck across databases: creation and completion time, size and status
+///
+/// Timestamps only have second resolution, so decks started in the same second are told
+/// apart by the rest.
+type DeckKey = (DateTime<Utc>, Option<DateTime<Utc>>, i32, String);
+
+/// Key identifying an answer across databases: fact, time, answer and duration
+type AnswerKey = (i64, DateTime<Utc>, i32, u64);
+
+/// Merge `history` into the profile `user_id` in a single transaction
+///
+/// Operations are matched by fact and re-keyed, decks by times, size and status, answers by
+/// fact, time, given answer and duration, so importing the same history twice adds nothing.
+/// For review items the most recently reviewed state wins.
+pub fn import_history(
+    conn: &Connection,
+    user_id: i64,
+    history: &HistoryExport,
+) -> Result<ImportSummary, Box<dyn Error>> {
+    let tx = conn.unchecked_transaction()?;
+    let mut summary = ImportSummary::default();
+    write_history(&tx, user_id, history, &mut summary)?;
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Merge every profile of the `source` database into the profile of the same name, in a
+/// single transaction; profiles missing from the target are created
+pub fn merge_database(
+    conn: &Connection,
+    source: &Connection,
+    now: DateTime<Utc>,
+) -> Result<ImportSummary, Box<dyn Error>> {
+    let tx = conn.unchecked_transaction()?;
+    let mut summary = ImportSummary::default();
+    for user in UsersRepository::new(source).get_all()? {
+        let history = read_database(source, user.id, now)?;
+        let user_id = UsersRepository::new(&tx).get_or_create(&user.name)?;
+        write_history(&tx, user_id, &history, &mut summary)?;
+    }
+    tx.commit()?;
+    Ok(summary)
+}
+
+fn write_history(
+    tx: &Connection,
+    user_id: i64,
+    history: &HistoryExport,
+    summary: &mut ImportSummary,
+) -> Result<(), Box<dyn Error>> {
+    if history.format_version > crate::export::EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported export format version {} (expected at most {})",
+            history.format_version,
+            crate::export::EXPORT_FORMAT_VERSION
+        )
+        .into());
+    }
+
+    // Operations: imported id -> local id
+    let mut operation_ids: HashMap<i64, i64> = HashMap::new();
+    let local_operation = |operation_type: &str,
+                           operand1: i32,
+                           operand2: i32,
+                           result: i32,
+                           summary: &mut ImportSummary|
+     -> rusqlite::Result<i64> {
+        let ops_repo = OperationsRepository::new(tx);
+        match ops_repo.find(operation_type, operand1, operand2, false)? {
+            Some(existing) => Ok(existing.id),
+            None => {
+                summary.operations_added += 1;
+                ops_repo.insert(operation_type, operand1, operand2, result, None)
+            }
+        }
+    };
+    for op in &history.operations {
+        let id = local_operation(
+            &op.operation_type,
+            op.operand1,
+            op.operand2,
+            op.result,
+            summary,
+        )?;
+        operation_ids.insert(op.id, id);
+    }
+
+    // Decks: imported id -> local id
+    let mut existing_decks: HashMap<DeckKey, i64> = HashMap::new();
+    {
+        let mut stmt = tx.prepare(
+            "SELECT id, created_at, completed_at, total_questions, status FROM decks
+             WHERE user_id = ?1",
+        )?;
+        let rows = stmt.query_map([user_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, i32>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, created_at, completed_at, total_questions, status) = row?;
+            let key = (
+                parse_timestamp("decks", id, &created_at)?,
+                completed_at
+                    .as_deref()
+                    .map(|at| parse_timestamp("decks", id, at))
+                    .transpose()?,
+                total_questions,
+                status,
+            );
+            existing_decks.insert(key, id);
+        }
+    }
+    let mut deck_ids: HashMap<i64, i64> = HashMap::new();
+    for (index, deck) in history.decks.iter().enumerate() {
+        let created_at = parse_timestamp("decks", position(index), &deck.created_at)?;
+        let completed_at = deck
+            .completed_at
+            .as_deref()
+            .map(|at| parse_timestamp("decks", position(index), at))
+            .transpose()?;
+        let key = (
+            created_at,
+            completed_at,
+            deck.total_questions,
+            deck.status.clone(),
+        );
+        let id = match existing_decks.get(&key) {
+            Some(id) => *id,
+            None => {
+                tx.execute(
+                    "INSERT INTO decks (created_at, completed_at, status, total_questions,
+                                        correct_answers, incorrect_answers, total_time_seconds,
+                                        average_time_seconds, accuracy_percentage, user_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        created_at.to_rfc3339(),
+                        completed_at.map(|at| at.to_rfc3339()),
+                        deck.status,
+                        deck.total_questions,
+                        deck.correct_answers,
+                        deck.incorrect_answers,
+                        deck.total_time_seconds,
+                        deck.average_time_seconds,
+                        deck.accuracy_percentage,
+                        user_id,
+                    ],
+                )?;
+                let id = tx.last_insert_rowid();
+                existing_decks.insert(key, id);
+                summary.decks_added += 1;
+                id
+            }
+        };
+        deck_ids.insert(deck.id, id);
+    }
+
+    // Answers, skipping those already present
+    let mut existing_answers: HashSet<AnswerKey> = HashSet::new();
+    {
+        let mut stmt = tx.prepare(
+            "SELECT id, operation_id, created_at, user_answer, time_spent_seconds FROM answers
+             WHERE user_id = ?1",
+        )?;
+        let rows = stmt.query_map([user_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i32>(3)?,
+                row.get::<_, f64>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, operation_id, created_at, user_answer, time_spent) = row?;
+            existing_answers.insert((
+                operation_id,
+                parse_timestamp("answers", id, &created_at)?,
+                user_answer,
+                time_spent.to_bits(),
+            ));
+        }
+    }
+    for (index, answer) in history.answers.iter().enumerate() {
+        // The answer carries its fact, so it can be imported even without its operation row
+        let operation_id = local_operation(
+            &answer.operation_type,
+            answer.operand1,
+            answer.operand2,
+            answer.result,
+            summary,
+        )?;
+        let created_at = parse_timestamp("answers", position(index), &answer.created_at)?;
+        let key = (
+            operation_id,
+            created_at,
+            answer.user_answer,
+            answer.time_spent_seconds.to_bits(),
+        );
+        if !existing_answers.insert(key) {
+            summary.answers_skipped += 1;
+            continue;
+        }
+        // Not part of the export; derived from the fact and the answer, as when it was given
+        let error_kind = OperationType::from_str(&answer.operation_type)
+            .filter(|_| !answer.is_correct)
+            .and_then(|operation_type| {
+                let operation = Operation::new(operation_type, answer.operand1, answer.operand2);
+                classify(&operation, answer.user_answer)
+            });
+        tx.execute(
+            "INSERT INTO answers (operation_id, user_answer, is_correct, time_spent_seconds,
+                                  deck_id, created_at, user_id, error_kind, timing_interrupted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                operation_id,
+                answer.user_answer,
+                answer.is_correct,
+                answer.time_spent_seconds,
+                answer.deck_id.and_then(|id| deck_ids.get(&id)),
+                created_at.to_rfc3339(),
+                user_id,
+                error_kind.map(|kind| kind.as_str()),
+                answer.timing_interrupted,
+            ],
+        )?;
+        summary.answers_added += 1;
+    }
+
+    // Review items: keep the most recently reviewed state per operation
+    for (index, item) in history.review_items.iter().enumerate() {
+        let Some(&operation_id) = operation_ids.get(&item.operation_id) else {
+            continue;
+        };
+        let incoming_reviewed = item
+            .last_reviewed_date
+            .as_deref()
+            .map(|at| parse_timestamp("review_items", position(index), at))
+            .transpose()?;
+        let next_review_date =
+            parse_timestamp("review_items", position(index), &item.next_review_date)?;
+        let existing: Option<(i64, Option<String>)> = tx
+            .query_row(
+                "SELECT id, last_reviewed_date FROM review_items
+                 WHERE operation_id = ?1 AND user_id = ?2",
+                [operation_id, user_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+
+        let state = params![
+            item.repetitions,
+            item.interval,
+            item.ease_factor,
+            next_review_date.to_rfc3339(),
+            incoming_reviewed.map(|at| at.to_rfc3339()),
+            item.fsrs_stability,
+            item.fsrs_difficulty,
+            operation_id,
+            user_id,
+        ];
+        match existing {
+            None => {
+                tx.execute(
+                    "INSERT INTO review_items (repetitions, interval, ease_factor,
+                                               next_review_date, last_reviewed_date,
+                                               fsrs_stability, fsrs_difficulty, operation_id,
+                                               user_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    state,
+                )?;
+                summary.review_items_added += 1;
+            }
+            Some((existing_id, existing_reviewed)) => {
+                let existing_reviewed = existing_reviewed
+                    .as_deref()
+                    .map(|at| parse_timestamp("review_items", existing_id, at))
+                    .transpose()?;
+                if incoming_reviewed > existing_reviewed {
+                    tx.execute(
+                        "UPDATE review_items
+                         SET repetitions = ?1, interval = ?2, ease_factor = ?3,
+                             next_review_date = ?4, last_reviewed_date = ?5,
+                             fsrs_stability = ?6, fsrs_difficulty = ?7
+                         WHERE operation_id = ?8 AND user_id = ?9",
+                        state,
+                    )?;
+                    summary.review_items_updated += 1;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::answers::AnswersRepository;
+    use crate::database::connection::init_connection;
+    use crate::database::decks::DecksRepository;
+    use crate::database::review_items::ReviewItemsRepository;
+    use crate::database::users::DEFAULT_USER_ID;
+    use chrono::{Duration, TimeZone};
+
+    fn create_test_db() -> Connection {
+        init_connection(":memory:").expect("Failed to create test database")
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    /// Complete a deck at `when` answering `a` × `b`, and record its review state
+    fn practice(conn: &Connection, when: DateTime<Utc>, a: i32, b: i32, interval: i32) {
+        practice_as(conn, DEFAULT_USER_ID, when, a, b, interval);
+    }
+
+    fn practice_as(
+        conn: &Connection,
+        user_id: i64,
+        when: DateTime<Utc>,
+        a: i32,
+        b: i32,
+        interval: i32,
+    ) {
+        let clock = move || when;
+        let decks_repo = DecksRepository::new(conn, user_id, Box::new(clock));
+        let answers_repo = AnswersRepository::new_with_date_provider(conn, user_id, &clock);
+        let review_repo = ReviewItemsRepository::new(conn, user_id, Box::new(clock));
+        let ops_repo = OperationsRepository::new(conn);
+
+        let deck_id = decks_repo.create().unwrap();
+        let op_id = match ops_repo.find("MULTIPLY", a, b, false).unwrap() {
+            Some(op) => op.id,
+            None => ops_repo
+                .insert("MULTIPLY", a, b, a * b, Some(deck_id))
+                .unwrap(),
+        };
+        answers_repo
+            .insert(op_id, a * b, true, 2.0, Some(deck_id))
+            .unwrap();
+        decks_repo.complete(deck_id).unwrap();
+
+        if review_repo.get(op_id).unwrap().is_none() {
+            review_repo.insert(op_id, when).unwrap();
+        }
+        let mut item = review_repo.get(op_id).unwrap().unwrap();
+        item.interval = interval;
+        item.last_reviewed_date = Some(when);
+        item.next_review_date = when + Duration::days(interval as i64);
+        review_repo.update(&item).unwrap();
+    }
+
+    #[test]
+    fn test_merge_rekeys_operations_and_is_idempotent() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let local = create_test_db();
+        practice(&local, day, 2, 2, 1);
+
+        let other = create_test_db();
+        // Same fact practised elsewhere, plus one the local database has never seen
+        practice(&other, day + Duration::hours(1), 3, 3, 1);
+        practice(&other, day + Duration::hours(2), 2, 2, 6);
+
+        let history = read_database(&other, DEFAULT_USER_ID, Utc::now()).unwrap();
+        let summary = import_history(&local, DEFAULT_USER_ID, &history).unwrap();
+        assert_eq!(summary.operations_added, 1);
+        assert_eq!(summary.decks_added, 2);
+        assert_eq!(summary.answers_added, 2);
+        assert_eq!(summary.review_items_added, 1);
+        assert_eq!(summary.review_items_updated, 1);
+
+        assert_eq!(count(&local, "operations"), 2);
+        assert_eq!(count(&local, "decks"), 3);
+        assert_eq!(count(&local, "answers"), 3);
+        let op = OperationsRepository::new(&local)
+            .find("MULTIPLY", 2, 2, false)
+            .unwrap()
+            .unwrap();
+        let item = ReviewItemsRepository::new(&local, DEFAULT_USER_ID, Box::new(Utc::now))
+            .get(op.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.interval, 6);
+
+        let again = import_history(&local, DEFAULT_USER_ID, &history).unwrap();
+        assert_eq!(
+            again,
+            ImportSummary {
+                answers_skipped: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(count(&local, "answers"), 3);
+        assert_eq!(count(&local, "decks"), 3);
+    }
+
+    #[test]
+    fn test_decks_started_in_the_same_second_are_kept_apart() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let source = create_test_db();
+        practice(&source, day, 2, 2, 1);
+        practice(&source, day + Duration::minutes(5), 3, 3, 1);
+        let mut history = read_database(&source, DEFAULT_USER_ID, Utc::now()).unwrap();
+        history.decks[1].created_at = history.decks[0].created_at.clone();
+
+        let target = create_test_db();
+        let summary = import_history(&target, DEFAULT_USER_ID, &history).unwrap();
+        assert_eq!(summary.decks_added, 2);
+        assert_eq!(count(&target, "decks"), 2);
+        let decks_with_answers: i64 = target
+            .query_row("SELECT COUNT(DISTINCT deck_id) FROM answers", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(decks_with_answers, 2);
+
+        let again = import_history(&target, DEFAULT_USER_ID, &history).unwrap();
+        assert_eq!(again.decks_added, 0);
+        assert_eq!(count(&target, "decks"), 2);
+    }
+
+    #[test]
+    fn test_merge_database_keeps_profiles_apart() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let local = create_test_db();
+        practice(&local, day, 2, 2, 1);
+
+        let other = create_test_db();
+        let sam = UsersRepository::new(&other).create("Sam").unwrap();
+        practice_as(&other, sam, day + Duration::hours(1), 2, 2, 6);
+
+        let summary = merge_database(&local, &other, Utc::now()).unwrap();
+        assert_eq!(summary.answers_added, 1);
+        assert_eq!(summary.review_items_added, 1);
+
+        let local_sam = UsersRepository::new(&local)
+            .find_by_name("Sam")
+            .unwrap()
+            .expect("Profile should be created by the merge");
+        assert_eq!(
+            AnswersRepository::new(&local, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            AnswersRepository::new(&local, local_sam.id)
+                .count()
+                .unwrap(),
+            1
+        );
+        // The default profile keeps its own schedule for the shared fact
+        let op = OperationsRepository::new(&local)
+            .find("MULTIPLY", 2, 2, false)
+            .unwrap()
+            .unwrap();
+        let item = ReviewItemsRepository::new(&local, DEFAULT_USER_ID, Box::new(Utc::now))
+            .get(op.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.interval, 1);
+
+        let again = merge_database(&local, &other, Utc::now()).unwrap();
+        assert_eq!(again.answers_added, 0);
+        assert_eq!(UsersRepository::new(&local).get_all().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_older_review_state_does_not_overwrite() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let local = create_test_db();
+        practice(&local, day + Duration::days(3), 2, 2, 9);
+        let other = create_test_db();
+        practice(&other, day, 2, 2, 1);
+
+        let history = read_database(&other, DEFAULT_USER_ID, Utc::now()).unwrap();
+        let summary = import_history(&local, DEFAULT_USER_ID, &history).unwrap();
+        assert_eq!(summary.review_items_updated, 0);
+
+        let op = OperationsRepository::new(&local)
+            .find("MULTIPLY", 2, 2, false)
+            .unwrap()
+            .unwrap();
+        let item = ReviewItemsRepository::new(&local, DEFAULT_USER_ID, Box::new(Utc::now))
+            .get(op.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.interval, 9);
+    }
+
+    #[test]
+    fn test_csv_and_json_round_trip() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let source = create_test_db();
+        practice(&source, day, 4, 5, 2);
+        practice(&source, day + Duration::days(1), 6, 7, 3);
+        let history = read_database(&source, DEFAULT_USER_ID, Utc::now()).unwrap();
+
+        let dir =
+            std::env::temp_dir().join(format!("memory_practice_import_{}", std::process::id()));
+        history.write_csv_dir(&dir).unwrap();
+        let json_path = dir.join("history.json");
+        std::fs::write(&json_path, history.to_json().unwrap()).unwrap();
+
+        for parsed in [read_csv_dir(&dir).unwrap(), read_json(&json_path).unwrap()] {
+            let target = create_test_db();
+            let summary = import_history(&target, DEFAULT_USER_ID, &parsed).unwrap();
+            assert_eq!(summary.answers_added, 2);
+            assert_eq!(summary.review_items_added, 2);
+
+            let round_trip = read_database(&target, DEFAULT_USER_ID, Utc::now()).unwrap();
+            assert_eq!(round_trip.answers, history.answers);
+            assert_eq!(round_trip.decks, history.decks);
+            assert_eq!(round_trip.review_items, history.review_items);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_malformed_timestamp_is_reported() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let source = create_test_db();
+        practice(&source, day, 4, 5, 2);
+        practice(&source, day + Duration::days(1), 6, 7, 3);
+        let mut history = read_database(&source, DEFAULT_USER_ID, Utc::now()).unwrap();
+        history.answers[1].created_at = "yesterday".to_string();
+
+        let target = create_test_db();
+        let error = import_history(&target, DEFAULT_USER_ID, &history).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ImportError>(),
+            Some(&ImportError::InvalidTimestamp {
+                table: "answers",
+                row: 2,
+                value: "yesterday".to_string(),
+            })
+        );
+        // Nothing from the failed import is kept
+        assert_eq!(count(&target, "answers"), 0);
+    }
+
+    #[test]
+    fn test_merge_source_is_opened_read_only() {
+        let dir = std::env::temp_dir().join(format!(
+            "memory_practice_merge_source_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("other.db");
+        {
+            let other = init_connection(&path.to_string_lossy()).unwrap();
+            practice(&other, Utc::now(), 3, 4, 1);
+        }
+        let before = std::fs::read(&path).unwrap();
+
+        let source = open_merge_source(&path).unwrap();
+        assert!(source.execute("DELETE FROM answers", []).is_err());
+        let local = create_test_db();
+        let summary = merge_database(&local, &source, Utc::now()).unwrap();
+        assert_eq!(summary.answers_added, 1);
+        drop(source);
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+
+        // A file never migrated by this application is refused
+        let unmigrated = dir.join("unmigrated.db");
+        Connection::open(&unmigrated)
+            .unwrap()
+            .execute_batch("CREATE TABLE answers (id INTEGER PRIMARY KEY)")
+            .unwrap();
+        let error = open_merge_source(&unmigrated).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ImportError>(),
+            Some(ImportError::OutdatedSchema { found: None, .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_timing_survives_round_trip() {
+        let day = Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap();
+        let source = create_test_db();
+        practice(&source, day, 4, 5, 2);
+        source
+            .execute("UPDATE answers SET timing_interrupted = 1", [])
+            .unwrap();
+        let history = read_database(&source, DEFAULT_USER_ID, Utc::now()).unwrap();
+        assert!(history.answers[0].timing_interrupted);
+
+        let dir = std::env::temp_dir().join(format!(
+            "memory_practice_import_interrupted_{}",
+            std::process::id()
+        ));
+        history.write_csv_dir(&dir).unwrap();
+        let json = history.to_json().unwrap();
+        let parsed_json: HistoryExport = serde_json::from_str(&json).unwrap();
+        for parsed in [read_csv_dir(&dir).unwrap(), parsed_json] {
+            let target = create_test_db();
+            import_history(&target, DEFAULT_USER_ID, &parsed).unwrap();
+            let answer = AnswersRepository::new(&target, DEFAULT_USER_ID)
+                .get(1)
+                .unwrap()
+                .unwrap();
+            assert!(answer.timing_interrupted);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Files written before the flag existed import as uninterrupted
+        let without_flag = json.replace("\"timing_interrupted\": true", "\"unused\": 0");
+        assert_ne!(without_flag, json);
+        let parsed: HistoryExport = serde_json::from_str(&without_flag).unwrap();
+        assert!(!parsed.answers[0].timing_interrupted);
+    }
+
+    #[test]
+    fn test_newer_format_version_is_rejected() {
+        let conn = create_test_db();
+        let mut history = read_database(&conn, DEFAULT_USER_ID, Utc::now()).unwrap();
+        history.format_version = crate::export::EXPORT_FORMAT_VERSION + 1;
+        assert!(import_history(&conn, DEFAULT_USER_ID, &history).is_err());
+    }
+}
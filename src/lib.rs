@@ -1,12 +1,22 @@
+pub mod adaptive_difficulty;
 pub mod answer_evaluator_service;
+pub mod charts;
 pub mod cli;
 pub mod database;
 pub mod database_factory;
 pub mod date_provider;
 pub mod deck;
+pub mod difficulty;
+pub mod error_analysis;
+pub mod export;
+pub mod fsrs;
 pub mod gui;
+pub mod import;
 pub mod operations;
 pub mod quiz_service;
 pub mod row_factories;
+pub mod session;
 pub mod spaced_repetition;
 pub mod time_format;
+pub mod timing_estimator;
+pub mod tui;
@@ -9,16 +9,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::builder().format_timestamp_millis().init();
 
     // Detect database configuration from command line arguments using clap
-    // Supported arguments: --test, --db-path <PATH>, --override-date <YYYY-MM-DD>
+    // Supported arguments: --test, --db-path <PATH>, --override-date <YYYY-MM-DD>, --user <NAME>
     // Use --help for more information
     let config = DatabaseFactory::detect_config();
     let is_test_mode = config.is_test_mode;
+    // Without --user, ask who is practising
+    let choose_profile = config.user.is_none();
 
     // Create database based on detected configuration
     let db = Arc::new(DatabaseFactory::create(config)?);
 
     // Run the GUI application
-    gui::run_app(db, is_test_mode)?;
+    gui::run_app(db, is_test_mode, choose_profile)?;
 
     Ok(())
 }
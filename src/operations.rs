@@ -1,38 +1,67 @@
+use crate::difficulty::DifficultyProfile;
 use rand::Rng;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum OperationType {
     Addition,
+    Subtraction,
     Multiplication,
+    Division,
 }
 
 impl OperationType {
+    /// All supported operation types
+    pub fn all() -> [OperationType; 4] {
+        [
+            OperationType::Addition,
+            OperationType::Subtraction,
+            OperationType::Multiplication,
+            OperationType::Division,
+        ]
+    }
+
     pub(crate) fn from_str(op_record: &String) -> Option<OperationType> {
         if op_record == "ADD" {
             Some(OperationType::Addition)
+        } else if op_record == "SUBTRACT" {
+            Some(OperationType::Subtraction)
         } else if op_record == "MULTIPLY" {
             Some(OperationType::Multiplication)
+        } else if op_record == "DIVIDE" {
+            Some(OperationType::Division)
         } else {
             None
         }
     }
+    /// Whether swapping the operands gives the same fact (7 × 8 and 8 × 7)
+    pub fn is_commutative(&self) -> bool {
+        matches!(
+            self,
+            OperationType::Addition | OperationType::Multiplication
+        )
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
             OperationType::Addition => "ADD",
+            OperationType::Subtraction => "SUBTRACT",
             OperationType::Multiplication => "MULTIPLY",
+            OperationType::Division => "DIVIDE",
         }
     }
 
     pub fn symbol(&self) -> &str {
         match self {
             OperationType::Addition => "+",
+            OperationType::Subtraction => "-",
             OperationType::Multiplication => "×",
+            OperationType::Division => "÷",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Operation {
     #[allow(dead_code)]
     pub id: Option<i64>,
@@ -46,7 +75,10 @@ impl Operation {
     pub fn new(operation_type: OperationType, operand1: i32, operand2: i32) -> Self {
         let result = match operation_type {
             OperationType::Addition => operand1 + operand2,
+            OperationType::Subtraction => operand1 - operand2,
             OperationType::Multiplication => operand1 * operand2,
+            // Integer division; a zero divisor is never generated, but guard stored data
+            OperationType::Division => operand1.checked_div(operand2).unwrap_or(0),
         };
 
         Operation {
@@ -61,24 +93,57 @@ impl Operation {
     pub fn generate_random() -> Self {
         let mut rng = rand::thread_rng();
 
-        // Randomly choose between addition and multiplication
-        let (operation_type, operand1, operand2) = if rng.gen_bool(0.5) {
-            (
+        // Randomly choose one of the four operation types
+        match rng.gen_range(0..4) {
+            0 => Operation::new(
                 OperationType::Addition,
                 rng.gen_range(1..100),
                 rng.gen_range(1..100),
-            )
+            ),
+            1 => Self::random_subtraction(&mut rng, false),
+            2 => {
+                let operand1 = rng.gen_range(1..100);
+                let operand2 = if operand1 > 10 {
+                    rng.gen_range(1..10)
+                } else {
+                    rng.gen_range(1..100)
+                };
+                Operation::new(OperationType::Multiplication, operand1, operand2)
+            }
+            _ => Self::random_division(&mut rng),
+        }
+    }
+
+    /// Generate a random subtraction with operands in 1-99
+    ///
+    /// Unless `allow_negative` is set, the operands are ordered so the result is never negative.
+    pub fn generate_subtraction(allow_negative: bool) -> Self {
+        Self::random_subtraction(&mut rand::thread_rng(), allow_negative)
+    }
+
+    /// Generate a random division whose result is always a whole number
+    ///
+    /// The divisor is in 2-9 and the dividend stays below 100.
+    pub fn generate_division() -> Self {
+        Self::random_division(&mut rand::thread_rng())
+    }
+
+    fn random_subtraction(rng: &mut impl Rng, allow_negative: bool) -> Self {
+        let a = rng.gen_range(1..100);
+        let b = rng.gen_range(1..100);
+        let (operand1, operand2) = if allow_negative || a >= b {
+            (a, b)
         } else {
-            let operand1 = rng.gen_range(1..100);
-            let operand2 = if operand1 > 10 {
-                rng.gen_range(1..10)
-            } else {
-                rng.gen_range(1..100)
-            };
-            (OperationType::Multiplication, operand1, operand2)
+            (b, a)
         };
+        Operation::new(OperationType::Subtraction, operand1, operand2)
+    }
 
-        Operation::new(operation_type, operand1, operand2)
+    fn random_division(rng: &mut impl Rng) -> Self {
+        // Build the dividend from divisor × quotient so the answer is exact
+        let divisor = rng.gen_range(2..10);
+        let quotient = rng.gen_range(1..=99 / divisor);
+        Operation::new(OperationType::Division, divisor * quotient, divisor)
     }
 
     pub fn check_answer(&self, answer: i32) -> bool {
@@ -98,8 +163,9 @@ impl fmt::Display for Operation {
     }
 }
 
-pub fn generate_question_block(count: usize) -> Vec<Operation> {
-    (0..count).map(|_| Operation::generate_random()).collect()
+/// Generate `count` random questions following the given difficulty profile
+pub fn generate_question_block(count: usize, profile: &DifficultyProfile) -> Vec<Operation> {
+    (0..count).map(|_| profile.generate()).collect()
 }
 
 #[cfg(test)]
@@ -124,6 +190,20 @@ mod tests {
         assert_eq!(op.operation_type, OperationType::Multiplication);
     }
 
+    #[test]
+    fn test_operation_new_subtraction() {
+        let op = Operation::new(OperationType::Subtraction, 9, 4);
+        assert_eq!(op.result, 5);
+        assert_eq!(op.operation_type, OperationType::Subtraction);
+    }
+
+    #[test]
+    fn test_operation_new_division() {
+        let op = Operation::new(OperationType::Division, 56, 8);
+        assert_eq!(op.result, 7);
+        assert_eq!(op.operation_type, OperationType::Division);
+    }
+
     #[test]
     fn test_check_answer_correct() {
         let op = Operation::new(OperationType::Addition, 10, 15);
@@ -148,16 +228,79 @@ mod tests {
         assert_eq!(op.to_string(), "5 × 7 = ?");
     }
 
+    #[test]
+    fn test_to_string_subtraction() {
+        let op = Operation::new(OperationType::Subtraction, 40, 15);
+        assert_eq!(op.to_string(), "40 - 15 = ?");
+    }
+
+    #[test]
+    fn test_to_string_division() {
+        let op = Operation::new(OperationType::Division, 42, 6);
+        assert_eq!(op.to_string(), "42 ÷ 6 = ?");
+    }
+
     #[test]
     fn test_operation_type_as_str() {
         assert_eq!(OperationType::Addition.as_str(), "ADD");
+        assert_eq!(OperationType::Subtraction.as_str(), "SUBTRACT");
         assert_eq!(OperationType::Multiplication.as_str(), "MULTIPLY");
+        assert_eq!(OperationType::Division.as_str(), "DIVIDE");
+    }
+
+    #[test]
+    fn test_operation_type_round_trip() {
+        for op_type in OperationType::all() {
+            let stored = op_type.as_str().to_string();
+            assert_eq!(OperationType::from_str(&stored), Some(op_type));
+        }
+        assert_eq!(OperationType::from_str(&"MODULO".to_string()), None);
+    }
+
+    #[test]
+    fn test_operation_type_is_commutative() {
+        assert!(OperationType::Addition.is_commutative());
+        assert!(OperationType::Multiplication.is_commutative());
+        assert!(!OperationType::Subtraction.is_commutative());
+        assert!(!OperationType::Division.is_commutative());
     }
 
     #[test]
     fn test_operation_type_symbol() {
         assert_eq!(OperationType::Addition.symbol(), "+");
+        assert_eq!(OperationType::Subtraction.symbol(), "-");
         assert_eq!(OperationType::Multiplication.symbol(), "×");
+        assert_eq!(OperationType::Division.symbol(), "÷");
+    }
+
+    #[test]
+    fn test_generate_subtraction_never_negative() {
+        for _ in 0..100 {
+            let op = Operation::generate_subtraction(false);
+            assert_eq!(op.operation_type, OperationType::Subtraction);
+            assert!(op.result >= 0, "{} should not be negative", op);
+        }
+    }
+
+    #[test]
+    fn test_generate_subtraction_allow_negative() {
+        let any_negative = (0..200).any(|_| Operation::generate_subtraction(true).result < 0);
+        assert!(
+            any_negative,
+            "Negative results should be possible when allowed"
+        );
+    }
+
+    #[test]
+    fn test_generate_division_exact() {
+        for _ in 0..100 {
+            let op = Operation::generate_division();
+            assert_eq!(op.operation_type, OperationType::Division);
+            assert!(op.operand2 >= 2 && op.operand2 < 10);
+            assert!(op.operand1 < 100);
+            assert_eq!(op.operand1 % op.operand2, 0, "{} should divide exactly", op);
+            assert_eq!(op.result * op.operand2, op.operand1);
+        }
     }
 
     #[test]
@@ -183,7 +326,9 @@ mod tests {
             let op = Operation::generate_random();
             let expected_result = match op.operation_type {
                 OperationType::Addition => op.operand1 + op.operand2,
+                OperationType::Subtraction => op.operand1 - op.operand2,
                 OperationType::Multiplication => op.operand1 * op.operand2,
+                OperationType::Division => op.operand1 / op.operand2,
             };
             assert_eq!(op.result, expected_result);
         }
@@ -191,23 +336,25 @@ mod tests {
 
     #[test]
     fn test_generate_question_block_count() {
-        let block = generate_question_block(10);
+        let block = generate_question_block(10, &DifficultyProfile::default());
         assert_eq!(block.len(), 10);
 
-        let block = generate_question_block(5);
+        let block = generate_question_block(5, &DifficultyProfile::default());
         assert_eq!(block.len(), 5);
     }
 
     #[test]
     fn test_generate_question_block_all_valid() {
-        let block = generate_question_block(20);
+        let block = generate_question_block(20, &DifficultyProfile::default());
         for op in block {
             assert!(op.operand1 >= 1 && op.operand1 < 100);
             assert!(op.operand2 >= 1 && op.operand2 < 100);
 
             let expected_result = match op.operation_type {
                 OperationType::Addition => op.operand1 + op.operand2,
+                OperationType::Subtraction => op.operand1 - op.operand2,
                 OperationType::Multiplication => op.operand1 * op.operand2,
+                OperationType::Division => op.operand1 / op.operand2,
             };
             assert_eq!(op.result, expected_result);
         }
@@ -1,15 +1,22 @@
+use crate::adaptive_difficulty::AdaptiveDifficulty;
 use crate::answer_evaluator_service::AnswerEvaluatorService;
+use crate::database::settings::{COMMUTATIVE_FACTS_KEY, DIFFICULTY_PROFILE_KEY, SCHEDULER_KEY};
 use crate::database::{
-    AnswersRepository, Database, DecksRepository, OperationsRepository, ReviewItemsRepository,
+    AnswersRepository, Database, DeckJournalRepository, DecksRepository, NewAnswer,
+    OperationsRepository, ReviewItemsRepository, ReviewLogRecord, ReviewLogRepository,
+    SettingsRepository,
 };
 use crate::deck::DeckSummary;
+use crate::difficulty::DifficultyProfile;
+use crate::error_analysis::{ErrorKind, classify};
 use crate::operations::{Operation, OperationType};
-use crate::spaced_repetition::{ReviewItem, ReviewScheduler};
+use crate::spaced_repetition::{ReviewItem, Scheduler, SchedulerKind, quality_grade};
 use crate::time_format::format_time_difference;
 use chrono::{DateTime, Utc};
-use log::info;
+use log::{info, warn};
 use rusqlite::Connection;
 use sra::sm_2::Quality;
+use std::fmt;
 use std::sync::Arc;
 
 /// Result of answering a single question
@@ -18,7 +25,14 @@ pub struct QuestionResult {
     pub operation: Operation,
     pub user_answer: i32,
     pub is_correct: bool,
+    /// Likely cause of the mistake when the answer is wrong
+    pub error_kind: Option<ErrorKind>,
+    /// Active answer time in seconds, without paused or idle time
     pub time_spent: f64,
+    /// Whether the question was paused while shown, so its time is left out of timing statistics
+    pub timing_interrupted: bool,
+    /// When the answer was given; None stores it with the time the deck is saved
+    pub answered_at: Option<DateTime<Utc>>,
     pub is_review: bool,
     pub original_operation_id: Option<i64>,
     /// Quality grade assigned to this answer (0-5, None if not yet graded)
@@ -27,20 +41,52 @@ pub struct QuestionResult {
     pub next_review_date: Option<DateTime<Utc>>,
 }
 
+/// Why a deck's results could not be saved
+///
+/// Persistence runs in a single transaction, so on error nothing from the deck was written.
+#[derive(Debug)]
+pub enum PersistError {
+    /// A review result did not carry the id of its stored operation
+    MissingOperation(String),
+    /// The database rejected a read or write
+    Database(rusqlite::Error),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::MissingOperation(question) => {
+                write!(f, "review of {} has no stored operation", question)
+            }
+            PersistError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::MissingOperation(_) => None,
+            PersistError::Database(e) => Some(e),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for PersistError {
+    fn from(e: rusqlite::Error) -> Self {
+        PersistError::Database(e)
+    }
+}
+
 /// Service layer for quiz operations, decoupled from GUI
 pub struct QuizService<'a> {
     conn: &'a Connection,
     db: Arc<Database>,
-    evaluator_service: AnswerEvaluatorService<'a>,
 }
 
 impl<'a> QuizService<'a> {
     pub fn new(conn: &'a Connection, db: Arc<Database>) -> Self {
-        Self {
-            conn,
-            evaluator_service: AnswerEvaluatorService::new(conn),
-            db,
-        }
+        Self { conn, db }
     }
 
     /// Process a user's answer to a question
@@ -58,7 +104,10 @@ impl<'a> QuizService<'a> {
             operation: question.clone(),
             user_answer,
             is_correct,
+            error_kind: classify(question, user_answer),
             time_spent,
+            timing_interrupted: false,
+            answered_at: None,
             is_review,
             original_operation_id,
             grade: None,
@@ -66,10 +115,53 @@ impl<'a> QuizService<'a> {
         }
     }
 
-    /// Write all results to database with proper review scheduling
+    /// Write all results to database with proper review scheduling, in a single transaction
     /// Returns updated results with grade and next_review_date populated
-    pub fn persist_results(&self, results: &[QuestionResult], deck_id: i64) -> Vec<QuestionResult> {
-        let scheduler = ReviewScheduler::new();
+    pub fn persist_results(
+        &self,
+        results: &[QuestionResult],
+        deck_id: i64,
+    ) -> Result<Vec<QuestionResult>, PersistError> {
+        self.in_transaction(|| self.write_results(results, deck_id))
+    }
+
+    /// Persist the results and complete the deck with its summary, in a single transaction
+    /// The deck's resume journal is removed in the same transaction
+    /// Returns updated results with grade and next_review_date populated
+    pub fn save_deck(
+        &self,
+        deck_id: i64,
+        results: &[QuestionResult],
+    ) -> Result<Vec<QuestionResult>, PersistError> {
+        let persisted = self.in_transaction(|| {
+            let persisted = self.write_results(results, deck_id)?;
+            self.write_deck_completion(deck_id, &persisted)?;
+            DeckJournalRepository::new(self.conn, self.db.user_id()).clear(deck_id)?;
+            Ok(persisted)
+        })?;
+        self.update_adaptive_levels();
+        Ok(persisted)
+    }
+
+    /// Run `write` in a transaction, rolled back if it fails
+    fn in_transaction<T>(
+        &self,
+        write: impl FnOnce() -> Result<T, PersistError>,
+    ) -> Result<T, PersistError> {
+        let tx = self.conn.unchecked_transaction()?;
+        let value = write()?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    fn write_results(
+        &self,
+        results: &[QuestionResult],
+        deck_id: i64,
+    ) -> Result<Vec<QuestionResult>, PersistError> {
+        let scheduler = self.scheduler_kind().create();
+        // Grades the whole deck against the same baselines, read once
+        let evaluator = AnswerEvaluatorService::new(self.conn, self.db.user_id());
         let mut updated_results = Vec::new();
 
         for result in results {
@@ -82,159 +174,276 @@ impl<'a> QuizService<'a> {
 
             if result.is_review {
                 // For reviews, update existing operation
-                let updated =
-                    self.persist_review_result(&scheduler, result, &question_str, deck_id);
+                let updated = self.persist_review_result(
+                    scheduler.as_ref(),
+                    &evaluator,
+                    result,
+                    &question_str,
+                    deck_id,
+                )?;
+                updated_results.push(updated);
+            } else if let Some(operation_id) = self.find_known_fact(&result.operation)? {
+                // A generated question for a fact that already has a schedule is a review
+                info!(
+                    "Known fact: {} (operation_id={})",
+                    question_str, operation_id
+                );
+                let mut review = result.clone();
+                review.is_review = true;
+                review.original_operation_id = Some(operation_id);
+                review.operation.id = Some(operation_id);
+                let updated = self.persist_review_result(
+                    scheduler.as_ref(),
+                    &evaluator,
+                    &review,
+                    &question_str,
+                    deck_id,
+                )?;
                 updated_results.push(updated);
             } else {
                 // For new questions, create operation and review item
-                let updated =
-                    self.persist_new_question_result(&scheduler, result, &question_str, deck_id);
+                let updated = self.persist_new_question_result(
+                    scheduler.as_ref(),
+                    &evaluator,
+                    result,
+                    &question_str,
+                    deck_id,
+                )?;
                 updated_results.push(updated);
             }
         }
 
-        updated_results
+        Ok(updated_results)
     }
 
     /// Persist a review result with updated scheduling
     /// Returns the result with grade and next_review_date populated
     fn persist_review_result(
         &self,
-        scheduler: &ReviewScheduler,
+        scheduler: &dyn Scheduler,
+        evaluator: &AnswerEvaluatorService,
         result: &QuestionResult,
         question_str: &str,
         deck_id: i64,
-    ) -> QuestionResult {
+    ) -> Result<QuestionResult, PersistError> {
         let mut updated_result = result.clone();
-
-        let answers_repo = AnswersRepository::new(self.conn);
-        let review_items_repo = ReviewItemsRepository::new(self.conn);
-        if let Some(operation_id) = result.original_operation_id
-            && answers_repo
-                .insert(
-                    operation_id,
-                    result.user_answer,
-                    result.is_correct,
-                    result.time_spent,
-                    Some(deck_id),
-                )
-                .is_ok()
-            && let Ok(Some(mut review_item)) = review_items_repo.get(operation_id)
-        {
-            let stats = self
-                .evaluator_service
-                .get_evaluator(result.operation.operation_type.as_str());
-
-            let quality = stats.evaluate_performance(result.is_correct, result.time_spent);
-            let (reps, interval, ease, next_date) = scheduler.process_review(&review_item, quality);
-
-            let quality_str = Self::quality_to_string(quality);
-
-            info!(
-                "Review: {} | Quality: {} | Next review: {} | Reps: {}, Interval: {} days, Ease: {:.2}",
-                question_str,
-                quality_str,
-                format_time_difference(Utc::now(), next_date),
-                reps,
-                interval,
-                ease
-            );
-
-            review_item.repetitions = reps;
-            review_item.interval = interval;
-            review_item.ease_factor = ease;
-            review_item.next_review_date = next_date;
-            review_item.last_reviewed_date = Some(Utc::now());
-
-            let _ = review_items_repo.update(&review_item);
-
-            // Update the result with grade and next review date
-            updated_result.grade = Some(quality);
-            updated_result.next_review_date = Some(next_date);
+        let now = self.db.get_current_time();
+        let answered_at = result.answered_at.unwrap_or(now);
+
+        let current_time = || self.db.get_current_time();
+        let answer_time = move || answered_at;
+        let answers_repo =
+            AnswersRepository::new_with_date_provider(self.conn, self.db.user_id(), &answer_time);
+        let review_items_repo =
+            ReviewItemsRepository::new(self.conn, self.db.user_id(), Box::new(current_time));
+        let operation_id = result
+            .original_operation_id
+            .ok_or_else(|| PersistError::MissingOperation(question_str.to_string()))?;
+        answers_repo.insert_answer(&NewAnswer {
+            operation_id,
+            user_answer: result.user_answer,
+            is_correct: result.is_correct,
+            time_spent_seconds: result.time_spent,
+            deck_id: Some(deck_id),
+            error_kind: result.error_kind,
+            timing_interrupted: result.timing_interrupted,
+        })?;
+        let review_item = review_items_repo.get(operation_id)?;
+
+        let quality = evaluator.evaluate(
+            &result.operation,
+            result.is_correct,
+            result.error_kind,
+            result.time_spent,
+        );
+        let previous_item =
+            review_item.unwrap_or_else(|| Self::initial_review_item(operation_id, now));
+        let mut review_item = scheduler.schedule(&previous_item, quality, now);
+
+        let quality_str = Self::quality_to_string(quality);
+
+        info!(
+            "Review ({}): {} | Quality: {} | Next review: {} | Reps: {}, Interval: {} days, Ease: {:.2}",
+            scheduler.kind().as_str(),
+            question_str,
+            quality_str,
+            format_time_difference(now, review_item.next_review_date),
+            review_item.repetitions,
+            review_item.interval,
+            review_item.ease_factor
+        );
+
+        if review_item.id.is_none() {
+            review_item.id = Some(review_items_repo.insert_item(&review_item)?);
+        } else {
+            review_items_repo.update(&review_item)?;
         }
+        self.log_review(scheduler, quality, &previous_item, &review_item, deck_id)?;
 
-        updated_result
+        // Update the result with grade and next review date
+        updated_result.grade = Some(quality);
+        updated_result.next_review_date = Some(review_item.next_review_date);
+
+        Ok(updated_result)
     }
 
     /// Persist a new question result with initial review scheduling
     /// Returns the result with grade and next_review_date populated
     fn persist_new_question_result(
         &self,
-        scheduler: &ReviewScheduler,
+        scheduler: &dyn Scheduler,
+        evaluator: &AnswerEvaluatorService,
         result: &QuestionResult,
         question_str: &str,
         deck_id: i64,
-    ) -> QuestionResult {
+    ) -> Result<QuestionResult, PersistError> {
         let mut updated_result = result.clone();
+        let now = self.db.get_current_time();
+        let answered_at = result.answered_at.unwrap_or(now);
 
+        let current_time = || self.db.get_current_time();
+        let answer_time = move || answered_at;
         let operations_repository = OperationsRepository::new(self.conn);
-        let answers_repository = AnswersRepository::new(self.conn);
-        if let Ok(operation_id) = operations_repository.insert(
+        let answers_repository =
+            AnswersRepository::new_with_date_provider(self.conn, self.db.user_id(), &answer_time);
+        let operation_id = operations_repository.insert(
             result.operation.operation_type.as_str(),
             result.operation.operand1,
             result.operation.operand2,
             result.operation.result,
             Some(deck_id),
-        ) && answers_repository
-            .insert(
-                operation_id,
-                result.user_answer,
-                result.is_correct,
-                result.time_spent,
-                Some(deck_id),
-            )
-            .is_ok()
-        {
-            let stats = self
-                .evaluator_service
-                .get_evaluator(result.operation.operation_type.as_str());
-
-            let quality = stats.evaluate_performance(result.is_correct, result.time_spent);
-
-            // Create a review item with SM-2 defaults and let the scheduler determine timing
-            let mut review_item = ReviewItem {
-                id: None,
-                operation_id,
-                repetitions: 0,
-                interval: 0,
-                ease_factor: 2.5,
-                next_review_date: Utc::now(),
-                last_reviewed_date: None,
-            };
-
-            let (reps, interval, ease, next_date) = scheduler.process_review(&review_item, quality);
-
-            let quality_str = Self::quality_to_string(quality);
-
-            info!(
-                "New question: {} | Quality: {} | First review: {} | Reps: {}, Interval: {} days, Ease: {:.2}",
-                question_str,
-                quality_str,
-                format_time_difference(Utc::now(), next_date),
-                reps,
-                interval,
-                ease
-            );
+        )?;
+        answers_repository.insert_answer(&NewAnswer {
+            operation_id,
+            user_answer: result.user_answer,
+            is_correct: result.is_correct,
+            time_spent_seconds: result.time_spent,
+            deck_id: Some(deck_id),
+            error_kind: result.error_kind,
+            timing_interrupted: result.timing_interrupted,
+        })?;
+
+        let quality = evaluator.evaluate(
+            &result.operation,
+            result.is_correct,
+            result.error_kind,
+            result.time_spent,
+        );
+
+        // Create a review item with SM-2 defaults and let the scheduler determine timing
+        let initial_item = Self::initial_review_item(operation_id, now);
+
+        let mut review_item = scheduler.schedule(&initial_item, quality, now);
+        let next_date = review_item.next_review_date;
+
+        let quality_str = Self::quality_to_string(quality);
+
+        info!(
+            "New question ({}): {} | Quality: {} | First review: {} | Reps: {}, Interval: {} days, Ease: {:.2}",
+            scheduler.kind().as_str(),
+            question_str,
+            quality_str,
+            format_time_difference(now, next_date),
+            review_item.repetitions,
+            review_item.interval,
+            review_item.ease_factor
+        );
+
+        let review_items_repository =
+            ReviewItemsRepository::new(self.conn, self.db.user_id(), Box::new(current_time));
+        review_item.id = Some(review_items_repository.insert_item(&review_item)?);
+        self.log_review(scheduler, quality, &initial_item, &review_item, deck_id)?;
+
+        // Update the result with grade and next review date
+        updated_result.grade = Some(quality);
+        updated_result.next_review_date = Some(next_date);
+
+        Ok(updated_result)
+    }
 
-            review_item.repetitions = reps;
-            review_item.interval = interval;
-            review_item.ease_factor = ease;
-            review_item.next_review_date = next_date;
+    /// Review item with SM-2 defaults for an operation that has never been scheduled
+    fn initial_review_item(operation_id: i64, now: DateTime<Utc>) -> ReviewItem {
+        ReviewItem {
+            id: None,
+            operation_id,
+            repetitions: 0,
+            interval: 0,
+            ease_factor: 2.5,
+            next_review_date: now,
+            last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
+        }
+    }
 
-            let review_items_repository = ReviewItemsRepository::new(self.conn);
-            let _ = review_items_repository.insert(operation_id, next_date);
-            let _ = review_items_repository.update(&review_item);
+    /// Id of the stored operation for the same fact, if it has been practised before
+    fn find_known_fact(&self, operation: &Operation) -> rusqlite::Result<Option<i64>> {
+        let either_order = operation.operation_type.is_commutative() && self.commutative_facts();
+        Ok(OperationsRepository::new(self.conn)
+            .find(
+                operation.operation_type.as_str(),
+                operation.operand1,
+                operation.operand2,
+                either_order,
+            )?
+            .map(|record| record.id))
+    }
 
-            // Update the result with grade and next review date
-            updated_result.grade = Some(quality);
-            updated_result.next_review_date = Some(next_date);
-        }
+    /// Record a scheduling decision in the review log
+    fn log_review(
+        &self,
+        scheduler: &dyn Scheduler,
+        quality: Quality,
+        before: &ReviewItem,
+        after: &ReviewItem,
+        deck_id: i64,
+    ) -> rusqlite::Result<()> {
+        let record = ReviewLogRecord::from_items(
+            scheduler.kind().as_str(),
+            quality_grade(quality),
+            before,
+            after,
+            Some(deck_id),
+            after
+                .last_reviewed_date
+                .unwrap_or_else(|| self.db.get_current_time()),
+        );
+        ReviewLogRepository::new(self.conn, self.db.user_id()).insert(&record)?;
+        Ok(())
+    }
 
-        updated_result
+    /// Complete a deck whose results are already persisted, recording its summary statistics
+    pub fn complete_deck(
+        &self,
+        deck_id: i64,
+        results: &[QuestionResult],
+    ) -> Result<(), PersistError> {
+        self.in_transaction(|| self.write_deck_completion(deck_id, results))?;
+        self.update_adaptive_levels();
+        Ok(())
+    }
+
+    /// Persist the answers given so far and mark the deck abandoned, in a single transaction
+    pub fn save_abandoned_deck(
+        &self,
+        deck_id: i64,
+        results: &[QuestionResult],
+    ) -> Result<Vec<QuestionResult>, PersistError> {
+        self.in_transaction(|| {
+            let persisted = self.write_results(results, deck_id)?;
+            let current_time = self.db.get_current_time();
+            DecksRepository::new(self.conn, self.db.user_id(), Box::new(move || current_time))
+                .abandon(deck_id)?;
+            DeckJournalRepository::new(self.conn, self.db.user_id()).clear(deck_id)?;
+            Ok(persisted)
+        })
     }
 
-    /// Complete a deck with summary statistics
-    pub fn complete_deck(&self, deck_id: i64, results: &[QuestionResult]) {
+    fn write_deck_completion(
+        &self,
+        deck_id: i64,
+        results: &[QuestionResult],
+    ) -> Result<(), PersistError> {
         // Collect results as (is_correct, time_spent) tuples
         let results_data: Vec<(bool, f64)> = results
             .iter()
@@ -244,22 +453,30 @@ impl<'a> QuizService<'a> {
         // Calculate summary
         let summary = DeckSummary::from_results(&results_data);
 
-        // Update deck with summary
-        let repo = DecksRepository::new(&self.db.conn, Box::new(|| self.db.get_current_time()));
-        let _ = repo.update_summary(deck_id, &summary);
-
-        // Mark deck as completed
+        // Update deck with summary and mark it completed
         let current_time = self.db.get_current_time();
-        let repo1 = DecksRepository::new(&self.db.conn, Box::new(move || current_time));
-        let _ = repo1.complete(deck_id);
+        let repo =
+            DecksRepository::new(self.conn, self.db.user_id(), Box::new(move || current_time));
+        repo.update_summary(deck_id, &summary)?;
+        repo.complete(deck_id)?;
+        Ok(())
+    }
+
+    /// Re-evaluate adaptive levels now that the deck counts towards the statistics
+    fn update_adaptive_levels(&self) {
+        if self.difficulty_profile().is_adaptive()
+            && let Err(e) = self.adaptive_difficulty().update_levels()
+        {
+            warn!("Failed to update adaptive difficulty levels: {}", e);
+        }
     }
 
     /// Fetch due review questions for a deck
     pub fn fetch_due_reviews(&self) -> Vec<Operation> {
         let mut questions = Vec::new();
-        let now = Utc::now();
+        let now = self.db.get_current_time();
 
-        let repo = ReviewItemsRepository::new(self.conn);
+        let repo = ReviewItemsRepository::new(self.conn, self.db.user_id(), Box::new(move || now));
         if let Ok(due_reviews) = repo.get_due(now) {
             let num_due = due_reviews.len();
             info!("Found {} review question(s) due for practice", num_due);
@@ -295,14 +512,72 @@ impl<'a> QuizService<'a> {
         questions
     }
 
+    /// Load the difficulty profile persisted in the database settings
+    ///
+    /// Falls back to the default profile if none is stored or the stored name is unknown.
+    pub fn difficulty_profile(&self) -> DifficultyProfile {
+        let stored = SettingsRepository::new(self.conn)
+            .get(DIFFICULTY_PROFILE_KEY)
+            .ok()
+            .flatten();
+
+        let profile = match stored {
+            Some(name) => DifficultyProfile::by_name(&name).unwrap_or_else(|| {
+                warn!("Unknown difficulty profile '{}', using default", name);
+                DifficultyProfile::default()
+            }),
+            None => DifficultyProfile::default(),
+        };
+
+        if profile.is_adaptive() {
+            self.adaptive_difficulty().profile().unwrap_or(profile)
+        } else {
+            profile
+        }
+    }
+
+    /// Load the scheduling algorithm persisted in the database settings (SM-2 by default)
+    pub fn scheduler_kind(&self) -> SchedulerKind {
+        SettingsRepository::new(self.conn)
+            .get(SCHEDULER_KEY)
+            .ok()
+            .flatten()
+            .and_then(|name| {
+                let kind = SchedulerKind::from(&name);
+                if kind.is_none() {
+                    warn!("Unknown scheduler '{}', using SM-2", name);
+                }
+                kind
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether swapped operands share a review item, as persisted in the settings (off by default)
+    pub fn commutative_facts(&self) -> bool {
+        SettingsRepository::new(self.conn)
+            .get(COMMUTATIVE_FACTS_KEY)
+            .ok()
+            .flatten()
+            .is_some_and(|value| value == "true")
+    }
+
+    fn adaptive_difficulty(&self) -> AdaptiveDifficulty<'_> {
+        AdaptiveDifficulty::new(
+            self.conn,
+            self.db.user_id(),
+            Box::new(|| self.db.get_current_time()),
+        )
+    }
+
     /// Convert SM-2 quality grade to human-readable string
     pub fn quality_to_string(quality: Quality) -> String {
         match quality {
             Quality::Grade0 => "Grade 0 (Incorrect)".to_string(),
+            Quality::Grade1 => "Grade 1 (Incorrect, recognisable mistake)".to_string(),
+            Quality::Grade2 => "Grade 2 (Incorrect, near miss)".to_string(),
             Quality::Grade3 => "Grade 3 (Serious difficulty)".to_string(),
             Quality::Grade4 => "Grade 4 (After hesitation)".to_string(),
             Quality::Grade5 => "Grade 5 (Perfect)".to_string(),
-            _ => "N/A".to_string(),
         }
     }
 }
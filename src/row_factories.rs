@@ -5,20 +5,22 @@ use rusqlite::Row;
 
 /// Parses a datetime string that may be in RFC3339 format (with timezone) or naive format
 /// Always returns a DateTime<Utc> to ensure timezone information
-fn parse_datetime_with_timezone(datetime_str: &str) -> DateTime<Utc> {
+pub(crate) fn parse_datetime_with_timezone(datetime_str: &str) -> DateTime<Utc> {
+    try_parse_datetime_with_timezone(datetime_str).expect("Unable to parse datetime string")
+}
+
+/// Like `parse_datetime_with_timezone`, but None for strings in none of the known formats
+pub(crate) fn try_parse_datetime_with_timezone(datetime_str: &str) -> Option<DateTime<Utc>> {
     // Try parsing as RFC3339 first (includes timezone info)
     if let Ok(dt) = DateTime::parse_from_rfc3339(datetime_str) {
-        return dt.with_timezone(&Utc);
+        return Some(dt.with_timezone(&Utc));
     }
 
-    // Fallback: parse as naive datetime and assume UTC
-    chrono::NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%d %H:%M:%S")
-        .unwrap_or_else(|_| {
-            // Last resort: try other common formats
-            chrono::NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%dT%H:%M:%S")
-                .expect("Unable to parse datetime string")
-        })
-        .and_utc()
+    // Fallback: parse as naive datetime and assume UTC, then try other common formats
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(datetime_str, format).ok())
+        .map(|naive| naive.and_utc())
 }
 
 /// Factory for creating Deck objects from database rows
@@ -62,7 +64,7 @@ pub struct ReviewItemRowFactory;
 impl ReviewItemRowFactory {
     /// Creates a ReviewItem from a database row
     /// Expected columns: id, operation_id, repetitions, interval, ease_factor,
-    ///                   next_review_date, last_reviewed_date
+    ///                   next_review_date, last_reviewed_date, fsrs_stability, fsrs_difficulty
     pub fn from_row(row: &Row) -> rusqlite::Result<ReviewItem> {
         Ok(ReviewItem {
             id: Some(row.get(0)?),
@@ -74,6 +76,8 @@ impl ReviewItemRowFactory {
             last_reviewed_date: row
                 .get::<_, Option<String>>(6)?
                 .map(|s| parse_datetime_with_timezone(&s)),
+            fsrs_stability: row.get(7)?,
+            fsrs_difficulty: row.get(8)?,
         })
     }
 }
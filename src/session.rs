@@ -0,0 +1,880 @@
+use crate::database::{Database, DeckJournalRepository, DecksRepository, ResumableDeck};
+use crate::operations::{Operation, generate_question_block};
+use crate::quiz_service::{PersistError, QuestionResult, QuizService};
+use log::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Monotonic clock used to time answers
+pub type AnswerClock = Box<dyn Fn() -> Instant>;
+
+/// Time without input after which a question is paused
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Lifecycle of a practice session's current deck
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// No deck has been started yet
+    NotStarted,
+    /// A question is shown and its answer time is running
+    Asking,
+    /// The current question is hidden and its answer time is stopped; `unpause` continues it
+    Paused,
+    /// The current question has been answered; `next_question` moves on
+    Answered,
+    /// Every question was answered and the results were persisted with scheduling
+    Completed,
+    /// The deck was left unfinished; the answers given so far were persisted
+    Abandoned,
+}
+
+/// Active time spent on the question being asked
+struct AnswerTimer {
+    /// Time counted before the last pause
+    active: Duration,
+    /// When the timer last started, None while paused
+    running_since: Option<Instant>,
+    last_activity: Instant,
+    /// Whether the question was paused at least once
+    interrupted: bool,
+}
+
+impl AnswerTimer {
+    fn start(now: Instant) -> Self {
+        Self {
+            active: Duration::ZERO,
+            running_since: Some(now),
+            last_activity: now,
+            interrupted: false,
+        }
+    }
+
+    /// The moment counting should stop if it stopped at `now`: time past `idle_timeout` without
+    /// input is not counted
+    fn counted_until(&self, now: Instant, idle_timeout: Option<Duration>) -> Instant {
+        idle_timeout
+            .and_then(|timeout| self.last_activity.checked_add(timeout))
+            .map_or(now, |deadline| now.min(deadline))
+    }
+
+    fn is_idle(&self, now: Instant, idle_timeout: Option<Duration>) -> bool {
+        self.counted_until(now, idle_timeout) < now
+    }
+
+    fn elapsed(&self, now: Instant, idle_timeout: Option<Duration>) -> Duration {
+        let until = self.counted_until(now, idle_timeout);
+        self.active
+            + self.running_since.map_or(Duration::ZERO, |since| {
+                until.saturating_duration_since(since)
+            })
+    }
+
+    fn pause(&mut self, now: Instant, idle_timeout: Option<Duration>) {
+        self.active = self.elapsed(now, idle_timeout);
+        self.running_since = None;
+        self.interrupted = true;
+    }
+
+    fn unpause(&mut self, now: Instant) {
+        self.running_since = Some(now);
+        self.last_activity = now;
+    }
+}
+
+/// UI-agnostic practice engine shared by the GUI and the terminal front end
+///
+/// Assembles each deck from due reviews padded with new questions, times every answer from
+/// the moment its question is shown, and persists the results when the deck completes.
+/// Only active time is counted: a question can be paused, and is paused by `check_idle` after
+/// `idle_timeout` without input; answers whose timing was interrupted are flagged as such.
+/// A deck still in progress when the session is dropped or restarted is abandoned, keeping
+/// the answers given so far. Questions and answers are journaled as they happen, so a deck
+/// interrupted by a crash can be resumed with `resume`.
+pub struct Session {
+    db: Arc<Database>,
+    clock: AnswerClock,
+    questions_per_block: usize,
+    state: SessionState,
+    deck_id: Option<i64>,
+    questions: Vec<Operation>,
+    current_index: usize,
+    timer: Option<AnswerTimer>,
+    idle_timeout: Option<Duration>,
+    results: Vec<QuestionResult>,
+    save_error: Option<PersistError>,
+}
+
+impl Session {
+    pub fn new(db: Arc<Database>, questions_per_block: usize) -> Self {
+        Self::with_clock(db, questions_per_block, Box::new(Instant::now))
+    }
+
+    /// Create a session timing answers with `clock` instead of the system clock
+    pub fn with_clock(db: Arc<Database>, questions_per_block: usize, clock: AnswerClock) -> Self {
+        Self {
+            db,
+            clock,
+            questions_per_block,
+            state: SessionState::NotStarted,
+            deck_id: None,
+            questions: Vec::new(),
+            current_index: 0,
+            timer: None,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            results: Vec::new(),
+            save_error: None,
+        }
+    }
+
+    /// Pause questions after `idle_timeout` without input, or never with None
+    pub fn set_idle_timeout(&mut self, idle_timeout: Option<Duration>) {
+        self.idle_timeout = idle_timeout;
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    fn create_service(&self) -> QuizService<'_> {
+        QuizService::new(&self.db.conn, self.db.clone())
+    }
+
+    /// Start a new deck of due reviews, padded with new questions from the difficulty profile
+    pub fn start(&mut self) {
+        let service = self.create_service();
+        let mut questions = service.fetch_due_reviews();
+        let profile = service.difficulty_profile();
+        let mut new_questions = generate_question_block(
+            self.questions_per_block.saturating_sub(questions.len()),
+            &profile,
+        );
+        questions.append(&mut new_questions);
+        questions.truncate(self.questions_per_block);
+
+        self.start_with_questions(questions);
+    }
+
+    /// Start a new deck with the given questions
+    ///
+    /// A failure to save the abandoned previous deck stays available from `save_error`.
+    pub fn start_with_questions(&mut self, questions: Vec<Operation>) {
+        self.save_error = None;
+        if self.is_in_progress() {
+            self.abandon();
+        }
+
+        let current_time = self.db.get_current_time();
+        let repo = DecksRepository::new(
+            &self.db.conn,
+            self.db.user_id(),
+            Box::new(move || current_time),
+        );
+        self.deck_id = match repo.create() {
+            Ok(deck_id) => Some(deck_id),
+            Err(e) => {
+                error!("Failed to create deck: {}", e);
+                self.save_error = Some(e.into());
+                None
+            }
+        };
+
+        if let Some(deck_id) = self.deck_id
+            && let Err(e) = DeckJournalRepository::new(&self.db.conn, self.db.user_id())
+                .record_questions(deck_id, &questions)
+        {
+            warn!("Failed to journal questions of deck {}: {}", deck_id, e);
+        }
+
+        debug!("Started new block with {} questions", questions.len());
+        self.questions = questions;
+        self.current_index = 0;
+        self.results.clear();
+        self.state = SessionState::Asking;
+        self.timer = Some(AnswerTimer::start((self.clock)()));
+    }
+
+    /// The most recent deck left in progress by a crash, if any
+    pub fn find_resumable(&self) -> Option<ResumableDeck> {
+        DeckJournalRepository::new(&self.db.conn, self.db.user_id())
+            .find_resumable()
+            .unwrap_or_else(|e| {
+                error!("Failed to look for an unfinished deck: {}", e);
+                None
+            })
+    }
+
+    /// Continue an interrupted deck from its next unanswered question
+    ///
+    /// A deck whose questions were all answered is completed straight away.
+    pub fn resume(&mut self, deck: ResumableDeck) {
+        self.save_error = None;
+        if self.is_in_progress() && self.deck_id != Some(deck.deck_id) {
+            self.abandon();
+        }
+
+        let results = self.journaled_results(&deck);
+        info!(
+            "Resuming deck {} at question {} of {}",
+            deck.deck_id,
+            results.len() + 1,
+            deck.total()
+        );
+
+        self.deck_id = Some(deck.deck_id);
+        self.questions = deck
+            .entries
+            .into_iter()
+            .map(|entry| entry.operation)
+            .collect();
+        self.current_index = results.len();
+        self.results = results;
+        if self.current_index >= self.questions.len() {
+            self.state = SessionState::Answered;
+            self.complete();
+        } else {
+            self.state = SessionState::Asking;
+            self.timer = Some(AnswerTimer::start((self.clock)()));
+        }
+    }
+
+    /// Give up an interrupted deck, saving the answers it has and marking it abandoned
+    ///
+    /// The session's own deck is left as it is; a fully answered deck is abandoned too.
+    pub fn discard(&mut self, deck: ResumableDeck) {
+        self.save_error = None;
+        if self.is_in_progress() && self.deck_id == Some(deck.deck_id) {
+            self.abandon();
+            return;
+        }
+        let results = self.journaled_results(&deck);
+        info!(
+            "Discarding deck {} with {} of {} answered",
+            deck.deck_id,
+            results.len(),
+            deck.total()
+        );
+        if let Err(e) = self
+            .create_service()
+            .save_abandoned_deck(deck.deck_id, &results)
+        {
+            error!("Failed to save discarded deck {}: {}", deck.deck_id, e);
+            self.save_error = Some(e);
+        }
+    }
+
+    /// Results of the journaled answers of an interrupted deck, given when they were journaled
+    fn journaled_results(&self, deck: &ResumableDeck) -> Vec<QuestionResult> {
+        let service = self.create_service();
+        deck.entries
+            .iter()
+            .filter_map(|entry| {
+                let (user_answer, time_spent) = entry.answer?;
+                let mut result = service.process_answer(&entry.operation, user_answer, time_spent);
+                result.timing_interrupted = entry.timing_interrupted;
+                result.answered_at = entry.answered_at;
+                Some(result)
+            })
+            .collect()
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Whether a deck has been started and neither completed nor abandoned
+    pub fn is_in_progress(&self) -> bool {
+        matches!(
+            self.state,
+            SessionState::Asking | SessionState::Paused | SessionState::Answered
+        )
+    }
+
+    pub fn questions_per_block(&self) -> usize {
+        self.questions_per_block
+    }
+
+    pub fn deck_id(&self) -> Option<i64> {
+        self.deck_id
+    }
+
+    pub fn questions(&self) -> &[Operation] {
+        &self.questions
+    }
+
+    /// Index of the question being asked or just answered
+    pub fn current_index(&self) -> usize {
+        self.current_index
+    }
+
+    /// The question being asked or just answered
+    pub fn current_question(&self) -> Option<&Operation> {
+        if self.is_in_progress() {
+            self.questions.get(self.current_index)
+        } else {
+            None
+        }
+    }
+
+    pub fn results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+
+    /// Why the last deck's results could not be saved, if they could not
+    pub fn save_error(&self) -> Option<&PersistError> {
+        self.save_error.as_ref()
+    }
+
+    /// Note input from the user, keeping the current question from being paused as idle
+    pub fn record_activity(&mut self) {
+        if self.state == SessionState::Asking
+            && let Some(timer) = &mut self.timer
+        {
+            timer.last_activity = (self.clock)();
+        }
+    }
+
+    /// Stop the current question's answer time
+    ///
+    /// Returns false unless a question is being asked.
+    pub fn pause(&mut self) -> bool {
+        if self.state != SessionState::Asking {
+            return false;
+        }
+        if let Some(timer) = &mut self.timer {
+            timer.pause((self.clock)(), self.idle_timeout);
+        }
+        self.state = SessionState::Paused;
+        true
+    }
+
+    /// Continue a paused question, counting its answer time again
+    ///
+    /// Returns false unless a question is paused.
+    pub fn unpause(&mut self) -> bool {
+        if self.state != SessionState::Paused {
+            return false;
+        }
+        if let Some(timer) = &mut self.timer {
+            timer.unpause((self.clock)());
+        }
+        self.state = SessionState::Asking;
+        true
+    }
+
+    /// Pause the current question if there was no input for `idle_timeout`
+    ///
+    /// Time past the timeout is not counted. Returns whether the question was paused.
+    pub fn check_idle(&mut self) -> bool {
+        let idle = self.state == SessionState::Asking
+            && self
+                .timer
+                .as_ref()
+                .is_some_and(|timer| timer.is_idle((self.clock)(), self.idle_timeout));
+        idle && self.pause()
+    }
+
+    /// Record the answer to the current question, timed by the active time since it was shown
+    ///
+    /// An answer given after more than `idle_timeout` without input counts only up to the
+    /// timeout and is flagged as interrupted. Returns None unless a question is being asked.
+    pub fn submit_answer(&mut self, user_answer: i32) -> Option<&QuestionResult> {
+        if self.state != SessionState::Asking {
+            return None;
+        }
+        let question = self.questions.get(self.current_index)?;
+        let now = (self.clock)();
+        let (time_spent, timing_interrupted) = self.timer.as_ref().map_or((0.0, false), |timer| {
+            (
+                timer.elapsed(now, self.idle_timeout).as_secs_f64(),
+                timer.interrupted || timer.is_idle(now, self.idle_timeout),
+            )
+        });
+
+        let mut result = self
+            .create_service()
+            .process_answer(question, user_answer, time_spent);
+        result.timing_interrupted = timing_interrupted;
+        result.answered_at = Some(self.db.get_current_time());
+        if let Some(deck_id) = self.deck_id
+            && let Err(e) = DeckJournalRepository::new(&self.db.conn, self.db.user_id())
+                .record_answer(
+                    deck_id,
+                    self.current_index,
+                    user_answer,
+                    time_spent,
+                    timing_interrupted,
+                    result
+                        .answered_at
+                        .unwrap_or_else(|| self.db.get_current_time()),
+                )
+        {
+            warn!("Failed to journal answer for deck {}: {}", deck_id, e);
+        }
+        self.results.push(result);
+        self.state = SessionState::Answered;
+        self.results.last()
+    }
+
+    /// Show the next question, or complete the deck after the last one
+    pub fn next_question(&mut self) -> SessionState {
+        if self.state == SessionState::Answered {
+            self.current_index += 1;
+            if self.current_index >= self.questions.len() {
+                self.complete();
+            } else {
+                self.state = SessionState::Asking;
+                self.timer = Some(AnswerTimer::start((self.clock)()));
+            }
+        }
+        self.state
+    }
+
+    /// Persist the answers given so far with scheduling and mark the deck completed
+    ///
+    /// Everything is saved in one transaction; on failure the answers stay in memory and
+    /// the error is kept in `save_error`.
+    pub fn complete(&mut self) {
+        if !self.is_in_progress() {
+            return;
+        }
+        if let Some(deck_id) = self.deck_id {
+            match self.create_service().save_deck(deck_id, &self.results) {
+                Ok(results) => self.results = results,
+                Err(e) => {
+                    error!("Failed to save deck {}: {}", deck_id, e);
+                    self.save_error = Some(e);
+                }
+            }
+        }
+        self.timer = None;
+        self.state = SessionState::Completed;
+    }
+
+    /// Persist the answers given so far and mark the deck abandoned
+    pub fn abandon(&mut self) {
+        if !self.is_in_progress() {
+            return;
+        }
+        if let Some(deck_id) = self.deck_id {
+            match self
+                .create_service()
+                .save_abandoned_deck(deck_id, &self.results)
+            {
+                Ok(results) => self.results = results,
+                Err(e) => {
+                    error!("Failed to save abandoned deck {}: {}", deck_id, e);
+                    self.save_error = Some(e);
+                }
+            }
+        }
+        self.timer = None;
+        self.state = SessionState::Abandoned;
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::arc_with_non_send_sync)]
+mod tests {
+    use super::*;
+    use crate::database::{AnswersRepository, DEFAULT_USER_ID, OperationsRepository};
+    use crate::date_provider::SimulatedDateProvider;
+    use crate::deck::DeckStatus;
+    use crate::operations::OperationType;
+    use chrono::{TimeZone, Utc};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    /// Clock advanced by hand; returns the shared offset and the clock
+    fn manual_clock() -> (Rc<Cell<Duration>>, AnswerClock) {
+        let start = Instant::now();
+        let elapsed = Rc::new(Cell::new(Duration::ZERO));
+        let clock_elapsed = elapsed.clone();
+        (elapsed, Box::new(move || start + clock_elapsed.get()))
+    }
+
+    fn deck_status(db: &Database, deck_id: i64) -> DeckStatus {
+        DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        )
+        .get(deck_id)
+        .unwrap()
+        .expect("Deck should exist")
+        .status
+    }
+
+    fn multiplication(a: i32, b: i32) -> Operation {
+        Operation::new(OperationType::Multiplication, a, b)
+    }
+
+    #[test]
+    fn test_start_assembles_full_block() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut session = Session::new(db.clone(), 10);
+        assert_eq!(session.state(), SessionState::NotStarted);
+        assert!(session.current_question().is_none());
+
+        session.start();
+        assert_eq!(session.state(), SessionState::Asking);
+        assert_eq!(session.questions().len(), 10);
+        assert_eq!(session.current_index(), 0);
+        assert_eq!(
+            deck_status(&db, session.deck_id().unwrap()),
+            DeckStatus::InProgress
+        );
+    }
+
+    #[test]
+    fn test_answer_time_uses_injected_clock() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let (elapsed, clock) = manual_clock();
+        let mut session = Session::with_clock(db, 2, clock);
+        session.start_with_questions(vec![multiplication(7, 8), multiplication(6, 9)]);
+
+        elapsed.set(Duration::from_millis(2500));
+        let result = session.submit_answer(56).expect("Question should be asked");
+        assert!(result.is_correct);
+        assert_eq!(result.time_spent, 2.5);
+
+        // The second question is timed from when it is shown, not from the first answer
+        elapsed.set(Duration::from_secs(10));
+        assert_eq!(session.next_question(), SessionState::Asking);
+        elapsed.set(Duration::from_secs(14));
+        let result = session.submit_answer(50).unwrap();
+        assert!(!result.is_correct);
+        assert_eq!(result.time_spent, 4.0);
+    }
+
+    #[test]
+    fn test_paused_time_is_not_counted() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let (elapsed, clock) = manual_clock();
+        let mut session = Session::with_clock(db.clone(), 2, clock);
+        session.start_with_questions(vec![multiplication(7, 8), multiplication(6, 9)]);
+
+        elapsed.set(Duration::from_secs(2));
+        assert!(session.pause());
+        assert_eq!(session.state(), SessionState::Paused);
+        // No answers while paused
+        assert!(session.submit_answer(56).is_none());
+        assert!(!session.pause());
+
+        elapsed.set(Duration::from_secs(300));
+        assert!(session.unpause());
+        assert_eq!(session.state(), SessionState::Asking);
+        elapsed.set(Duration::from_secs(303));
+        let result = session.submit_answer(56).unwrap();
+        assert_eq!(result.time_spent, 5.0);
+        assert!(result.timing_interrupted);
+
+        // The flag belongs to the paused question only
+        session.next_question();
+        elapsed.set(Duration::from_secs(306));
+        let result = session.submit_answer(54).unwrap();
+        assert_eq!(result.time_spent, 3.0);
+        assert!(!result.timing_interrupted);
+
+        session.next_question();
+        let answers = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
+        assert!(answers.get(1).unwrap().unwrap().timing_interrupted);
+        assert!(!answers.get(2).unwrap().unwrap().timing_interrupted);
+    }
+
+    #[test]
+    fn test_idle_question_is_paused() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let (elapsed, clock) = manual_clock();
+        let mut session = Session::with_clock(db, 1, clock);
+        session.set_idle_timeout(Some(Duration::from_secs(30)));
+        session.start_with_questions(vec![multiplication(7, 8)]);
+
+        elapsed.set(Duration::from_secs(20));
+        session.record_activity();
+        elapsed.set(Duration::from_secs(45));
+        assert!(!session.check_idle());
+
+        // Only the time up to the timeout after the last input is counted
+        elapsed.set(Duration::from_secs(120));
+        assert!(session.check_idle());
+        assert_eq!(session.state(), SessionState::Paused);
+        assert!(!session.check_idle());
+
+        session.unpause();
+        elapsed.set(Duration::from_secs(122));
+        let result = session.submit_answer(56).unwrap();
+        assert_eq!(result.time_spent, 52.0);
+        assert!(result.timing_interrupted);
+    }
+
+    #[test]
+    fn test_answer_after_inactivity_is_capped() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let (elapsed, clock) = manual_clock();
+        let mut session = Session::with_clock(db, 1, clock);
+        session.start_with_questions(vec![multiplication(7, 8)]);
+
+        // A front end that never checked for idleness, e.g. one reading whole lines
+        elapsed.set(Duration::from_secs(600));
+        let result = session.submit_answer(56).unwrap();
+        assert_eq!(result.time_spent, DEFAULT_IDLE_TIMEOUT.as_secs_f64());
+        assert!(result.timing_interrupted);
+    }
+
+    #[test]
+    fn test_transitions_follow_state_machine() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut session = Session::new(db.clone(), 1);
+
+        // Nothing to answer or advance before a deck starts
+        assert!(session.submit_answer(1).is_none());
+        assert_eq!(session.next_question(), SessionState::NotStarted);
+
+        session.start_with_questions(vec![multiplication(3, 4)]);
+        // Advancing requires an answer first
+        assert_eq!(session.next_question(), SessionState::Asking);
+        assert!(session.submit_answer(12).is_some());
+        assert_eq!(session.state(), SessionState::Answered);
+        // Only one answer per question
+        assert!(session.submit_answer(12).is_none());
+        assert_eq!(session.results().len(), 1);
+
+        assert_eq!(session.next_question(), SessionState::Completed);
+        assert!(session.current_question().is_none());
+        assert_eq!(
+            deck_status(&db, session.deck_id().unwrap()),
+            DeckStatus::Completed
+        );
+        let result = &session.results()[0];
+        assert!(result.grade.is_some());
+        assert!(result.next_review_date.is_some());
+    }
+
+    #[test]
+    fn test_answers_persisted_only_on_completion() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut session = Session::new(db.clone(), 2);
+        session.start_with_questions(vec![multiplication(2, 3), multiplication(4, 5)]);
+
+        session.submit_answer(6);
+        session.next_question();
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            0
+        );
+
+        session.submit_answer(20);
+        session.next_question();
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            2
+        );
+        assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_restart_abandons_deck_in_progress() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut session = Session::new(db.clone(), 2);
+        session.start_with_questions(vec![multiplication(2, 3), multiplication(4, 5)]);
+        let first_deck = session.deck_id().unwrap();
+        session.submit_answer(6);
+
+        session.start();
+        assert_eq!(deck_status(&db, first_deck), DeckStatus::Abandoned);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            1
+        );
+        assert_ne!(session.deck_id(), Some(first_deck));
+        assert_eq!(session.state(), SessionState::Asking);
+        assert!(session.results().is_empty());
+    }
+
+    #[test]
+    fn test_drop_abandons_only_unfinished_decks() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let (unfinished, finished) = {
+            let mut unfinished = Session::new(db.clone(), 2);
+            unfinished.start();
+            let mut finished = Session::new(db.clone(), 1);
+            finished.start_with_questions(vec![multiplication(2, 2)]);
+            finished.submit_answer(4);
+            finished.next_question();
+            (unfinished.deck_id().unwrap(), finished.deck_id().unwrap())
+        };
+
+        assert_eq!(deck_status(&db, unfinished), DeckStatus::Abandoned);
+        assert_eq!(deck_status(&db, finished), DeckStatus::Completed);
+    }
+
+    #[test]
+    fn test_crashed_deck_resumes_with_original_questions() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let questions = vec![
+            multiplication(2, 3),
+            multiplication(4, 5),
+            multiplication(6, 7),
+        ];
+        let deck_id = {
+            let mut session = Session::new(db.clone(), 3);
+            session.start_with_questions(questions.clone());
+            session.pause();
+            session.unpause();
+            session.submit_answer(6);
+            session.next_question();
+            let deck_id = session.deck_id().unwrap();
+            // A crash: no drop, so nothing is abandoned or saved
+            std::mem::forget(session);
+            deck_id
+        };
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            0
+        );
+
+        let mut session = Session::new(db.clone(), 3);
+        let deck = session.find_resumable().expect("Deck should be resumable");
+        assert_eq!(deck.deck_id, deck_id);
+        assert_eq!((deck.answered(), deck.total()), (1, 3));
+
+        session.resume(deck);
+        assert_eq!(session.state(), SessionState::Asking);
+        assert_eq!(session.deck_id(), Some(deck_id));
+        assert_eq!(session.questions(), questions.as_slice());
+        assert_eq!(session.current_index(), 1);
+        assert_eq!(session.current_question(), Some(&questions[1]));
+        assert_eq!(session.results()[0].user_answer, 6);
+        assert!(session.results()[0].timing_interrupted);
+
+        session.submit_answer(20);
+        session.next_question();
+        session.submit_answer(42);
+        assert_eq!(session.next_question(), SessionState::Completed);
+        assert_eq!(deck_status(&db, deck_id), DeckStatus::Completed);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            3
+        );
+        assert!(session.find_resumable().is_none());
+    }
+
+    #[test]
+    fn test_resumed_answers_keep_their_answer_time() {
+        let yesterday = Utc.with_ymd_and_hms(2025, 5, 1, 20, 0, 0).unwrap();
+        let dates = Arc::new(SimulatedDateProvider::new(yesterday));
+        let db = Arc::new(Database::with_date_provider(":memory:", dates.clone()).unwrap());
+        let mut crashed = Session::new(db.clone(), 2);
+        crashed.start_with_questions(vec![multiplication(2, 3), multiplication(4, 5)]);
+        crashed.submit_answer(6);
+        std::mem::forget(crashed);
+
+        dates.advance(chrono::Duration::days(1));
+        let mut session = Session::new(db.clone(), 2);
+        let deck = session.find_resumable().unwrap();
+        session.resume(deck);
+        session.submit_answer(20);
+        assert_eq!(session.next_question(), SessionState::Completed);
+
+        let answered_at: Vec<String> = db
+            .conn
+            .prepare("SELECT created_at FROM answers ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            answered_at,
+            vec![
+                yesterday.to_rfc3339(),
+                (yesterday + chrono::Duration::days(1)).to_rfc3339()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discarded_deck_keeps_answers_and_is_abandoned() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut crashed = Session::new(db.clone(), 2);
+        crashed.start_with_questions(vec![multiplication(2, 3), multiplication(4, 5)]);
+        crashed.submit_answer(6);
+        let deck_id = crashed.deck_id().unwrap();
+        std::mem::forget(crashed);
+
+        let mut session = Session::new(db.clone(), 2);
+        let deck = session.find_resumable().unwrap();
+        session.discard(deck);
+        // The discarded deck is not taken up by the session
+        assert_eq!(session.state(), SessionState::NotStarted);
+        assert_eq!(deck_status(&db, deck_id), DeckStatus::Abandoned);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            1
+        );
+        assert!(session.find_resumable().is_none());
+    }
+
+    #[test]
+    fn test_discarded_fully_answered_deck_is_abandoned() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut crashed = Session::new(db.clone(), 1);
+        crashed.start_with_questions(vec![multiplication(3, 3)]);
+        crashed.submit_answer(9);
+        let deck_id = crashed.deck_id().unwrap();
+        std::mem::forget(crashed);
+
+        let mut session = Session::new(db.clone(), 1);
+        let deck = session.find_resumable().unwrap();
+        session.discard(deck);
+        assert_eq!(deck_status(&db, deck_id), DeckStatus::Abandoned);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            1
+        );
+        assert!(session.find_resumable().is_none());
+    }
+
+    #[test]
+    fn test_fully_answered_deck_completes_on_resume() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut crashed = Session::new(db.clone(), 1);
+        crashed.start_with_questions(vec![multiplication(3, 3)]);
+        crashed.submit_answer(9);
+        let deck_id = crashed.deck_id().unwrap();
+        std::mem::forget(crashed);
+
+        let mut session = Session::new(db.clone(), 1);
+        let deck = session.find_resumable().unwrap();
+        session.resume(deck);
+        assert_eq!(session.state(), SessionState::Completed);
+        assert_eq!(deck_status(&db, deck_id), DeckStatus::Completed);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            1
+        );
+    }
+}
@@ -1,8 +1,10 @@
+use crate::error_analysis::ErrorKind;
+use crate::fsrs::FsrsScheduler;
 use chrono::{DateTime, Duration, Utc};
 use sra::sm_2::{Quality, SM2};
 
 /// Evaluates answer performance based on timing statistics for a specific operation type.
-/// Uses historical data to assign quality grades (0, 3, 4, 5) based on how long
+/// Uses historical data to assign quality grades (3, 4, 5) to correct answers based on how long
 /// the user took to answer relative to typical performance for that operation type.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct AnswerTimedEvaluator {
@@ -35,6 +37,95 @@ impl AnswerTimedEvaluator {
             Quality::Grade5
         }
     }
+
+    /// Grade an answer, giving wrong answers the partial credit `policy` allows for their likely
+    /// cause
+    pub fn evaluate_answer(
+        &self,
+        is_correct: bool,
+        error_kind: Option<ErrorKind>,
+        time_spent: f64,
+        policy: PartialCreditPolicy,
+    ) -> Quality {
+        if is_correct {
+            self.evaluate_performance(true, time_spent)
+        } else {
+            policy.grade(error_kind)
+        }
+    }
+}
+
+/// How much credit a wrong answer earns from its likely cause, selectable per database
+///
+/// Partial credit stays below Grade3, so the fact still counts as forgotten, but the schedulers
+/// let it keep part of its progress (see `retained_progress`) instead of starting over.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PartialCreditPolicy {
+    /// Every wrong answer is a complete blackout (Grade0), as before partial credit existed
+    #[default]
+    Off,
+    /// Slips next to the right fact earn Grade2, other recognisable mistakes Grade1
+    NearMiss,
+    /// Every recognisable mistake earns Grade2
+    Lenient,
+}
+
+impl PartialCreditPolicy {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PartialCreditPolicy::Off => "off",
+            PartialCreditPolicy::NearMiss => "near-miss",
+            PartialCreditPolicy::Lenient => "lenient",
+        }
+    }
+
+    pub fn from(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(PartialCreditPolicy::Off),
+            "near-miss" => Some(PartialCreditPolicy::NearMiss),
+            "lenient" => Some(PartialCreditPolicy::Lenient),
+            _ => None,
+        }
+    }
+
+    /// Grade of a wrong answer with the given likely cause (None when unclassified)
+    pub fn grade(&self, error_kind: Option<ErrorKind>) -> Quality {
+        let Some(kind) = error_kind.filter(|kind| *kind != ErrorKind::Other) else {
+            return Quality::Grade0;
+        };
+        match self {
+            PartialCreditPolicy::Off => Quality::Grade0,
+            PartialCreditPolicy::Lenient => Quality::Grade2,
+            PartialCreditPolicy::NearMiss => match kind {
+                ErrorKind::OffByOne | ErrorKind::TableNeighbour | ErrorKind::TransposedDigits => {
+                    Quality::Grade2
+                }
+                _ => Quality::Grade1,
+            },
+        }
+    }
+}
+
+/// Numeric value (0-5) of an SM-2 quality grade, as stored in the database
+pub fn quality_grade(quality: Quality) -> i32 {
+    match quality {
+        Quality::Grade0 => 0,
+        Quality::Grade1 => 1,
+        Quality::Grade2 => 2,
+        Quality::Grade3 => 3,
+        Quality::Grade4 => 4,
+        Quality::Grade5 => 5,
+    }
+}
+
+/// Share of its scheduling progress a wrongly answered fact keeps: none after a blackout, a
+/// quarter after Grade1 and half after Grade2
+pub fn retained_progress(quality: Quality) -> f64 {
+    match quality {
+        Quality::Grade1 => 0.25,
+        Quality::Grade2 => 0.5,
+        _ => 0.0,
+    }
 }
 
 /// Represents a single item scheduled for spaced repetition review
@@ -47,6 +138,52 @@ pub struct ReviewItem {
     pub ease_factor: f32,
     pub next_review_date: DateTime<Utc>,
     pub last_reviewed_date: Option<DateTime<Utc>>,
+    /// FSRS memory stability in days (None until first scheduled by FSRS)
+    pub fsrs_stability: Option<f64>,
+    /// FSRS item difficulty between 1 and 10 (None until first scheduled by FSRS)
+    pub fsrs_difficulty: Option<f64>,
+}
+
+/// Common interface for spaced repetition algorithms
+pub trait Scheduler {
+    /// Which algorithm this scheduler implements
+    fn kind(&self) -> SchedulerKind;
+
+    /// Applies a graded review at `now` and returns the item with its updated scheduling state
+    fn schedule(&self, item: &ReviewItem, quality: Quality, now: DateTime<Utc>) -> ReviewItem;
+}
+
+/// Available scheduling algorithms, selectable per database
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SchedulerKind {
+    #[default]
+    Sm2,
+    Fsrs,
+}
+
+impl SchedulerKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SchedulerKind::Sm2 => "sm2",
+            SchedulerKind::Fsrs => "fsrs",
+        }
+    }
+
+    pub fn from(s: &str) -> Option<Self> {
+        match s {
+            "sm2" => Some(SchedulerKind::Sm2),
+            "fsrs" => Some(SchedulerKind::Fsrs),
+            _ => None,
+        }
+    }
+
+    /// Instantiate the scheduler with its default parameters
+    pub fn create(&self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerKind::Sm2 => Box::new(ReviewScheduler::new()),
+            SchedulerKind::Fsrs => Box::new(FsrsScheduler::new()),
+        }
+    }
 }
 
 /// Wraps the SM-2 algorithm for convenient review scheduling
@@ -58,13 +195,14 @@ impl ReviewScheduler {
         Self
     }
 
-    /// Processes a review and returns updated scheduling parameters
+    /// Processes a review taking place at `now` and returns updated scheduling parameters
     ///
     /// Returns: (repetitions, interval, ease_factor, next_review_date)
     pub fn process_review(
         &self,
         item: &ReviewItem,
         quality: Quality,
+        now: DateTime<Utc>,
     ) -> (i32, i32, f32, DateTime<Utc>) {
         // Create a new SM2 with current item parameters
         let mut sm2 = SM2::new();
@@ -76,11 +214,23 @@ impl ReviewScheduler {
         // Apply the review
         let updated_sm2 = sm2.review(quality);
 
-        let next_review_date = Utc::now() + Duration::days(updated_sm2.interval() as i64);
+        // SM-2 restarts every failed item from scratch; partial credit still counts as a lapse,
+        // as in FSRS, but keeps part of the interval before the next review
+        let retained = retained_progress(quality);
+        let (repetitions, interval) = if retained > 0.0 {
+            (0, ((item.interval as f64 * retained).round() as i32).max(1))
+        } else {
+            (
+                updated_sm2.repetitions() as i32,
+                updated_sm2.interval() as i32,
+            )
+        };
+
+        let next_review_date = now + Duration::days(interval as i64);
 
         (
-            updated_sm2.repetitions() as i32,
-            updated_sm2.interval() as i32,
+            repetitions,
+            interval,
             updated_sm2.ease_factor(),
             next_review_date,
         )
@@ -93,7 +243,28 @@ impl Default for ReviewScheduler {
     }
 }
 
+impl Scheduler for ReviewScheduler {
+    fn kind(&self) -> SchedulerKind {
+        SchedulerKind::Sm2
+    }
+
+    fn schedule(&self, item: &ReviewItem, quality: Quality, now: DateTime<Utc>) -> ReviewItem {
+        let (repetitions, interval, ease_factor, next_review_date) =
+            self.process_review(item, quality, now);
+
+        ReviewItem {
+            repetitions,
+            interval,
+            ease_factor,
+            next_review_date,
+            last_reviewed_date: Some(now),
+            ..item.clone()
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::default_constructed_unit_structs)]
 mod tests {
     use super::*;
 
@@ -168,7 +339,6 @@ mod tests {
         let _scheduler = ReviewScheduler::new();
         let _default_scheduler = ReviewScheduler::default();
         // Just verify they can be created
-        assert!(true);
     }
 
     #[test]
@@ -182,9 +352,12 @@ mod tests {
             ease_factor: 2.5,
             next_review_date: Utc::now(),
             last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
-        let (reps, interval, ease, _next_date) = scheduler.process_review(&item, Quality::Grade5);
+        let (reps, interval, ease, _next_date) =
+            scheduler.process_review(&item, Quality::Grade5, Utc::now());
 
         // First review with perfect quality should schedule for 1 day
         assert_eq!(reps, 1);
@@ -203,9 +376,12 @@ mod tests {
             ease_factor: 2.5,
             next_review_date: Utc::now(),
             last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
-        let (reps, _interval, ease, _next_date) = scheduler.process_review(&item, Quality::Grade3);
+        let (reps, _interval, ease, _next_date) =
+            scheduler.process_review(&item, Quality::Grade3, Utc::now());
 
         // Difficult response should decrease ease factor but still increment repetitions
         assert_eq!(reps, 1);
@@ -244,9 +420,12 @@ mod tests {
             ease_factor: 2.5,
             next_review_date: Utc::now(),
             last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
-        let (reps1, interval1, ease1, _) = scheduler.process_review(&item, Quality::Grade5);
+        let (reps1, interval1, ease1, _) =
+            scheduler.process_review(&item, Quality::Grade5, Utc::now());
         assert_eq!(reps1, 1);
         assert_eq!(interval1, 1);
         assert!(ease1 >= 2.5);
@@ -256,7 +435,8 @@ mod tests {
         item.interval = interval1;
         item.ease_factor = ease1;
 
-        let (reps2, interval2, ease2, _) = scheduler.process_review(&item, Quality::Grade5);
+        let (reps2, interval2, ease2, _) =
+            scheduler.process_review(&item, Quality::Grade5, Utc::now());
         assert_eq!(reps2, 2);
         assert!(interval2 > interval1); // Interval should increase
         assert!(ease2 >= ease1); // Ease should not decrease
@@ -274,17 +454,21 @@ mod tests {
             ease_factor: 2.5,
             next_review_date: Utc::now(),
             last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
         // First review - correct (Grade5)
-        let (reps1, interval1, ease1, _) = scheduler.process_review(&item, Quality::Grade5);
+        let (reps1, interval1, ease1, _) =
+            scheduler.process_review(&item, Quality::Grade5, Utc::now());
 
         // Second review - serious difficulty (Grade3)
         item.repetitions = reps1;
         item.interval = interval1;
         item.ease_factor = ease1;
 
-        let (_reps2, _interval2, ease2, _) = scheduler.process_review(&item, Quality::Grade3);
+        let (_reps2, _interval2, ease2, _) =
+            scheduler.process_review(&item, Quality::Grade3, Utc::now());
         assert!(ease2 < ease1); // Ease should decrease with poor quality
         assert!(ease2 >= 1.3); // Ease factor has a minimum value (1.3)
     }
@@ -302,18 +486,20 @@ mod tests {
             ease_factor: 2.5,
             next_review_date: now,
             last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
         // First review
         let (reps1, interval1, ease1, next_date1) =
-            scheduler.process_review(&item, Quality::Grade5);
+            scheduler.process_review(&item, Quality::Grade5, now);
 
         // Second review
         item.repetitions = reps1;
         item.interval = interval1;
         item.ease_factor = ease1;
         let (_reps2, _interval2, _ease2, next_date2) =
-            scheduler.process_review(&item, Quality::Grade5);
+            scheduler.process_review(&item, Quality::Grade5, now);
 
         // Next review dates should be progressively further in the future
         assert!(next_date2 > next_date1);
@@ -361,6 +547,8 @@ mod tests {
             ease_factor: 2.6,
             next_review_date: Utc::now(),
             last_reviewed_date: Some(Utc::now()),
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
         let item2 = item1.clone();
@@ -382,13 +570,135 @@ mod tests {
             ease_factor: 2.5,
             next_review_date: Utc::now(),
             last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
         };
 
-        let (reps1, interval1, ease1, _) = default_scheduler.process_review(&item, Quality::Grade5);
-        let (reps2, interval2, ease2, _) = new_scheduler.process_review(&item, Quality::Grade5);
+        let (reps1, interval1, ease1, _) =
+            default_scheduler.process_review(&item, Quality::Grade5, Utc::now());
+        let (reps2, interval2, ease2, _) =
+            new_scheduler.process_review(&item, Quality::Grade5, Utc::now());
 
         assert_eq!(reps1, reps2);
         assert_eq!(interval1, interval2);
         assert_eq!(ease1, ease2);
     }
+
+    #[test]
+    fn test_scheduler_kind_round_trip() {
+        for kind in [SchedulerKind::Sm2, SchedulerKind::Fsrs] {
+            assert_eq!(SchedulerKind::from(kind.as_str()), Some(kind));
+            assert_eq!(kind.create().kind(), kind);
+        }
+        assert_eq!(SchedulerKind::from("sm18"), None);
+        assert_eq!(SchedulerKind::default(), SchedulerKind::Sm2);
+    }
+
+    #[test]
+    fn test_sm2_schedule_matches_process_review() {
+        let scheduler = ReviewScheduler::new();
+        let now = Utc::now();
+        let item = ReviewItem {
+            id: Some(1),
+            operation_id: 1,
+            repetitions: 0,
+            interval: 0,
+            ease_factor: 2.5,
+            next_review_date: Utc::now(),
+            last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
+        };
+
+        let (reps, interval, ease, _) = scheduler.process_review(&item, Quality::Grade4, now);
+        let updated = scheduler.schedule(&item, Quality::Grade4, now);
+
+        assert_eq!(updated.repetitions, reps);
+        assert_eq!(updated.interval, interval);
+        assert_eq!(updated.ease_factor, ease);
+        assert_eq!(updated.last_reviewed_date, Some(now));
+        assert_eq!(updated.fsrs_stability, None);
+    }
+
+    #[test]
+    fn test_partial_credit_policy_round_trip() {
+        for policy in [
+            PartialCreditPolicy::Off,
+            PartialCreditPolicy::NearMiss,
+            PartialCreditPolicy::Lenient,
+        ] {
+            assert_eq!(PartialCreditPolicy::from(policy.as_str()), Some(policy));
+        }
+        assert_eq!(PartialCreditPolicy::from("generous"), None);
+        assert_eq!(PartialCreditPolicy::default(), PartialCreditPolicy::Off);
+    }
+
+    #[test]
+    fn test_partial_credit_grades_by_cause() {
+        let near_miss = PartialCreditPolicy::NearMiss;
+        assert_eq!(
+            quality_grade(near_miss.grade(Some(ErrorKind::TableNeighbour))),
+            2
+        );
+        assert_eq!(quality_grade(near_miss.grade(Some(ErrorKind::OffByOne))), 2);
+        assert_eq!(
+            quality_grade(near_miss.grade(Some(ErrorKind::DroppedCarry))),
+            1
+        );
+        assert_eq!(quality_grade(near_miss.grade(Some(ErrorKind::Other))), 0);
+        assert_eq!(quality_grade(near_miss.grade(None)), 0);
+
+        let lenient = PartialCreditPolicy::Lenient;
+        assert_eq!(
+            quality_grade(lenient.grade(Some(ErrorKind::WrongOperation))),
+            2
+        );
+        assert_eq!(quality_grade(lenient.grade(Some(ErrorKind::Other))), 0);
+
+        for kind in ErrorKind::all() {
+            assert_eq!(quality_grade(PartialCreditPolicy::Off.grade(Some(kind))), 0);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_answer_applies_policy_to_wrong_answers_only() {
+        let stats = example_mock_stats();
+        let policy = PartialCreditPolicy::NearMiss;
+
+        // 55 for 7 × 8 is off by one, 12 is not a recognisable mistake
+        let near_miss = stats.evaluate_answer(false, Some(ErrorKind::OffByOne), 2.0, policy);
+        let blackout = stats.evaluate_answer(false, Some(ErrorKind::Other), 2.0, policy);
+        assert_eq!(quality_grade(near_miss), 2);
+        assert_eq!(quality_grade(blackout), 0);
+        assert_eq!(
+            quality_grade(stats.evaluate_answer(true, None, 1.0, policy)),
+            quality_grade(stats.evaluate_performance(true, 1.0))
+        );
+    }
+
+    #[test]
+    fn test_near_miss_reschedules_more_gently_than_blackout() {
+        let scheduler = ReviewScheduler::new();
+        let now = Utc::now();
+        let item = ReviewItem {
+            id: Some(1),
+            operation_id: 1,
+            repetitions: 4,
+            interval: 20,
+            ease_factor: 2.5,
+            next_review_date: now,
+            last_reviewed_date: None,
+            fsrs_stability: None,
+            fsrs_difficulty: None,
+        };
+
+        let blackout = scheduler.schedule(&item, Quality::Grade0, now);
+        let partial = scheduler.schedule(&item, Quality::Grade1, now);
+        let near_miss = scheduler.schedule(&item, Quality::Grade2, now);
+
+        assert_eq!((blackout.repetitions, blackout.interval), (0, 1));
+        assert_eq!((partial.repetitions, partial.interval), (0, 5));
+        assert_eq!((near_miss.repetitions, near_miss.interval), (0, 10));
+        assert_eq!(near_miss.ease_factor, blackout.ease_factor);
+    }
 }
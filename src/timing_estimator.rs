@@ -0,0 +1,218 @@
+use crate::spaced_repetition::AnswerTimedEvaluator;
+use std::fmt;
+
+/// Cut-off used by `mad` when none is given, in (scaled) median absolute deviations
+pub const DEFAULT_MAD_CUTOFF: f64 = 3.5;
+
+/// Share of answers trimmed at each end by `trimmed` when none is given
+pub const DEFAULT_TRIM_FRACTION: f64 = 0.1;
+
+/// Median absolute deviation and mean absolute deviation of normal data, in standard deviations
+const MAD_SCALE: f64 = 1.4826;
+const MEAN_ABSOLUTE_DEVIATION_SCALE: f64 = 1.2533;
+
+/// How a timing baseline is estimated from earlier answer times
+///
+/// A single answer given after leaving the room would otherwise inflate the mean and standard
+/// deviation, and with them the grade of every later answer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimingEstimator {
+    /// Mean and standard deviation of every answer
+    Mean,
+    /// Mean and standard deviation of the answers within `cutoff` median absolute deviations
+    /// (scaled to standard deviations) of the median
+    Mad { cutoff: f64 },
+    /// Mean and standard deviation without the `fraction` fastest and `fraction` slowest answers
+    Trimmed { fraction: f64 },
+}
+
+impl Default for TimingEstimator {
+    fn default() -> Self {
+        TimingEstimator::Mad {
+            cutoff: DEFAULT_MAD_CUTOFF,
+        }
+    }
+}
+
+impl TimingEstimator {
+    /// Parse "mean", "mad", "mad:CUTOFF", "trimmed" or "trimmed:FRACTION"
+    pub fn from(s: &str) -> Option<Self> {
+        let (name, value) = match s.split_once(':') {
+            Some((name, value)) => (name, Some(value.parse::<f64>().ok()?)),
+            None => (s, None),
+        };
+        match name {
+            "mean" if value.is_none() => Some(TimingEstimator::Mean),
+            "mad" => {
+                let cutoff = value.unwrap_or(DEFAULT_MAD_CUTOFF);
+                (cutoff.is_finite() && cutoff > 0.0).then_some(TimingEstimator::Mad { cutoff })
+            }
+            "trimmed" => {
+                let fraction = value.unwrap_or(DEFAULT_TRIM_FRACTION);
+                (0.0..0.5)
+                    .contains(&fraction)
+                    .then_some(TimingEstimator::Trimmed { fraction })
+            }
+            _ => None,
+        }
+    }
+
+    /// Positions in `times` of the answers left out of the baseline, in ascending order
+    pub fn outliers(&self, times: &[f64]) -> Vec<usize> {
+        match *self {
+            TimingEstimator::Mean => Vec::new(),
+            TimingEstimator::Mad { cutoff } => {
+                let Some(center) = median(times) else {
+                    return Vec::new();
+                };
+                let deviations: Vec<f64> = times.iter().map(|t| (t - center).abs()).collect();
+                let mut spread = median(&deviations).unwrap_or(0.0) * MAD_SCALE;
+                if spread == 0.0 {
+                    // More than half the answers took the same time
+                    spread = deviations.iter().sum::<f64>() / deviations.len() as f64
+                        * MEAN_ABSOLUTE_DEVIATION_SCALE;
+                }
+                if spread == 0.0 {
+                    return Vec::new();
+                }
+                (0..times.len())
+                    .filter(|&i| deviations[i] > cutoff * spread)
+                    .collect()
+            }
+            TimingEstimator::Trimmed { fraction } => {
+                let trimmed = (times.len() as f64 * fraction).floor() as usize;
+                let mut order: Vec<usize> = (0..times.len()).collect();
+                order.sort_by(|&a, &b| times[a].total_cmp(&times[b]));
+                let mut outliers: Vec<usize> = order[..trimmed]
+                    .iter()
+                    .chain(&order[times.len() - trimmed..])
+                    .copied()
+                    .collect();
+                outliers.sort_unstable();
+                outliers
+            }
+        }
+    }
+
+    /// Average and standard deviation of `times` without their outliers (None if none remain)
+    pub fn estimate(&self, times: &[f64]) -> Option<AnswerTimedEvaluator> {
+        let outliers = self.outliers(times);
+        let kept: Vec<f64> = times
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| outliers.binary_search(i).is_err())
+            .map(|(_, t)| *t)
+            .collect();
+        if kept.is_empty() {
+            return None;
+        }
+
+        let count = kept.len() as f64;
+        let average = kept.iter().sum::<f64>() / count;
+        let variance = kept.iter().map(|t| (t - average).powi(2)).sum::<f64>() / count;
+        Some(AnswerTimedEvaluator::new(average, variance.sqrt()))
+    }
+}
+
+impl fmt::Display for TimingEstimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimingEstimator::Mean => write!(f, "mean"),
+            TimingEstimator::Mad { cutoff } => write!(f, "mad:{}", cutoff),
+            TimingEstimator::Trimmed { fraction } => write!(f, "trimmed:{}", fraction),
+        }
+    }
+}
+
+/// Middle value of `values`, or the mean of the two middle values; None when empty
+pub(crate) fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    Some(if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Typical answers around 3 seconds and one given after leaving the room
+    const TIMES: [f64; 8] = [2.5, 3.0, 3.5, 2.8, 3.2, 300.0, 2.9, 3.1];
+
+    #[test]
+    fn test_mean_keeps_every_answer() {
+        let estimate = TimingEstimator::Mean.estimate(&TIMES).unwrap();
+        assert!(estimate.average > 30.0);
+        assert!(TimingEstimator::Mean.outliers(&TIMES).is_empty());
+    }
+
+    #[test]
+    fn test_mad_excludes_answer_after_leaving_the_room() {
+        let estimator = TimingEstimator::default();
+        assert_eq!(estimator.outliers(&TIMES), vec![5]);
+
+        let estimate = estimator.estimate(&TIMES).unwrap();
+        assert!((estimate.average - 3.0).abs() < 0.01);
+        assert!(estimate.standard_deviation < 0.5);
+    }
+
+    #[test]
+    fn test_mad_with_identical_times() {
+        let estimator = TimingEstimator::default();
+        assert_eq!(estimator.outliers(&[2.0, 2.0, 2.0, 2.0, 60.0]), vec![4]);
+        assert!(estimator.outliers(&[2.0, 2.0, 2.0]).is_empty());
+    }
+
+    #[test]
+    fn test_trimmed_drops_both_ends() {
+        let estimator = TimingEstimator::Trimmed { fraction: 0.125 };
+        // The fastest (2.5s) and the slowest (300s) of eight
+        assert_eq!(estimator.outliers(&TIMES), vec![0, 5]);
+        // Too few answers to trim any
+        assert!(estimator.outliers(&[1.0, 2.0, 3.0]).is_empty());
+    }
+
+    #[test]
+    fn test_estimate_of_no_times() {
+        assert!(TimingEstimator::default().estimate(&[]).is_none());
+        assert!(TimingEstimator::Mean.estimate(&[]).is_none());
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_estimator_round_trip() {
+        for estimator in [
+            TimingEstimator::Mean,
+            TimingEstimator::Mad { cutoff: 3.0 },
+            TimingEstimator::Trimmed { fraction: 0.05 },
+        ] {
+            assert_eq!(
+                TimingEstimator::from(&estimator.to_string()),
+                Some(estimator)
+            );
+        }
+        assert_eq!(
+            TimingEstimator::from("mad"),
+            Some(TimingEstimator::Mad {
+                cutoff: DEFAULT_MAD_CUTOFF
+            })
+        );
+        assert_eq!(TimingEstimator::from("trimmed:0.5"), None);
+        assert_eq!(TimingEstimator::from("mad:-1"), None);
+        assert_eq!(TimingEstimator::from("mean:2"), None);
+        assert_eq!(TimingEstimator::from("median"), None);
+    }
+}
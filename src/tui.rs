@@ -0,0 +1,461 @@
+use crate::database::{Database, ResumableDeck};
+use crate::quiz_service::QuizService;
+use crate::session::Session;
+use crate::time_format::format_time_difference;
+use colored::Colorize;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// Line-based terminal front end, usable over SSH and on machines without a display
+///
+/// Drives the same [`Session`] as the GUI: due reviews plus generated questions per deck, each
+/// answer timed from the moment its question is shown, results persisted with scheduling.
+pub struct TerminalQuiz<R, W> {
+    db: Arc<Database>,
+    input: R,
+    output: W,
+    session: Session,
+}
+
+/// How a deck ended
+#[derive(Debug, PartialEq)]
+enum DeckOutcome {
+    Completed,
+    /// The user typed `q` or the input ended; collected answers are saved and the deck abandoned
+    Quit,
+}
+
+impl<R: BufRead, W: Write> TerminalQuiz<R, W> {
+    pub fn new(db: Arc<Database>, input: R, output: W, questions_per_block: usize) -> Self {
+        Self::with_session(
+            db.clone(),
+            input,
+            output,
+            Session::new(db, questions_per_block),
+        )
+    }
+
+    /// Create a terminal quiz around an existing session, e.g. one with an injected clock
+    ///
+    /// Lines are only read once Enter is pressed, so there is no input to tell a slow answer from
+    /// an idle one: questions are never paused for inactivity here, only with `p`.
+    pub fn with_session(db: Arc<Database>, input: R, output: W, mut session: Session) -> Self {
+        session.set_idle_timeout(None);
+        Self {
+            db,
+            input,
+            output,
+            session,
+        }
+    }
+
+    /// Play decks until the user quits or the input ends
+    pub fn run(&mut self) -> io::Result<()> {
+        writeln!(
+            self.output,
+            "{}",
+            "Memory Practice - Math Operations".bold()
+        )?;
+        writeln!(
+            self.output,
+            "Type p to pause a question, q to quit at any time."
+        )?;
+
+        let mut resumable = self.session.find_resumable();
+        if let Some(deck) = &resumable {
+            let offer = format!(
+                "Continue the unfinished deck from {} ({} of {} answered)? [Y/n] ",
+                deck.created_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M"),
+                deck.answered(),
+                deck.total()
+            );
+            match self.prompt(&offer)? {
+                None => return Ok(()),
+                Some(line) if is_quit(&line) => return Ok(()),
+                Some(line)
+                    if line.is_empty()
+                        || line.eq_ignore_ascii_case("y")
+                        || line.eq_ignore_ascii_case("yes") => {}
+                Some(_) => {
+                    if let Some(deck) = resumable.take() {
+                        self.session.discard(deck);
+                    }
+                }
+            }
+        }
+
+        loop {
+            if self.play_deck(resumable.take())? == DeckOutcome::Quit {
+                if self.session.save_error().is_some() {
+                    self.print_save_error()?;
+                } else {
+                    writeln!(
+                        self.output,
+                        "Deck abandoned; answers given so far were saved."
+                    )?;
+                }
+                return Ok(());
+            }
+            self.print_results()?;
+
+            match self.prompt("Press Enter to start a new deck, or q to quit: ")? {
+                Some(line) if !is_quit(&line) => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Print `text` without a newline and read the reply; None when the input has ended
+    fn prompt(&mut self, text: &str) -> io::Result<Option<String>> {
+        write!(self.output, "{}", text)?;
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            writeln!(self.output)?;
+            return Ok(None);
+        }
+        Ok(Some(line.trim().to_string()))
+    }
+
+    /// Play a new deck, or the rest of an unfinished one
+    fn play_deck(&mut self, resume: Option<ResumableDeck>) -> io::Result<DeckOutcome> {
+        match resume {
+            Some(deck) => self.session.resume(deck),
+            None => self.session.start(),
+        }
+        let total = self.session.questions().len();
+
+        while let Some(question) = self.session.current_question() {
+            let question_text = format!("{} ", question.to_string().replace('?', "").trim_end());
+            writeln!(
+                self.output,
+                "\nQuestion {} of {}",
+                self.session.current_index() + 1,
+                total
+            )?;
+
+            let user_answer = loop {
+                let Some(line) = self.prompt(&question_text)? else {
+                    self.session.abandon();
+                    return Ok(DeckOutcome::Quit);
+                };
+                if is_quit(&line) {
+                    self.session.abandon();
+                    return Ok(DeckOutcome::Quit);
+                }
+                if is_pause(&line) {
+                    self.session.pause();
+                    match self.prompt("Paused; the clock is stopped. Press Enter to continue: ")? {
+                        Some(line) if !is_quit(&line) => {
+                            self.session.unpause();
+                            continue;
+                        }
+                        _ => {
+                            self.session.abandon();
+                            return Ok(DeckOutcome::Quit);
+                        }
+                    }
+                }
+                match line.parse::<i32>() {
+                    Ok(answer) => break answer,
+                    Err(_) if line.is_empty() => {}
+                    Err(_) => writeln!(self.output, "Please enter a whole number.")?,
+                }
+            };
+
+            self.session.submit_answer(user_answer);
+            self.session.next_question();
+        }
+
+        // A deck without questions has nothing to wait for
+        self.session.complete();
+        Ok(DeckOutcome::Completed)
+    }
+
+    fn print_save_error(&mut self) -> io::Result<()> {
+        if let Some(e) = self.session.save_error() {
+            let message = format!("Your results could not be saved: {}", e);
+            writeln!(self.output, "{}", message.red().bold())?;
+        }
+        Ok(())
+    }
+
+    fn print_results(&mut self) -> io::Result<()> {
+        writeln!(self.output, "\n{}", "Deck Results".bold())?;
+        if let Some(deck_id) = self.session.deck_id() {
+            writeln!(self.output, "Deck ID: {}", deck_id)?;
+        }
+
+        self.print_save_error()?;
+
+        let results = self.session.results();
+        let total = results.len();
+        if total == 0 {
+            writeln!(self.output, "No questions in this deck.")?;
+            return Ok(());
+        }
+        let correct_count = results.iter().filter(|r| r.is_correct).count();
+        let average_time = results.iter().map(|r| r.time_spent).sum::<f64>() / total as f64;
+        let accuracy = (correct_count as f64 / total as f64) * 100.0;
+        writeln!(
+            self.output,
+            "Score: {}/{} ({:.1}%)",
+            correct_count, total, accuracy
+        )?;
+        writeln!(self.output, "Average time: {:.2}s\n", average_time)?;
+
+        let now = self.db.get_current_time();
+        for (i, result) in results.iter().enumerate() {
+            let status = if result.is_correct {
+                "✓".green().bold()
+            } else {
+                "✗".red().bold()
+            };
+            let mut line = format!(
+                "{:>2}. {} {} (Your answer: {}) {} {:.2}s",
+                i + 1,
+                result.operation.to_string().replace('?', "").trim_end(),
+                result.operation.result,
+                result.user_answer,
+                status,
+                result.time_spent
+            );
+            if result.timing_interrupted {
+                line.push_str(" (paused)");
+            }
+            if let Some(kind) = result.error_kind {
+                line.push_str(&format!("  {}", kind.label()));
+            }
+            if let Some(grade) = result.grade {
+                line.push_str(&format!("  {}", QuizService::quality_to_string(grade)));
+            }
+            if let Some(next_date) = result.next_review_date {
+                line.push_str(&format!(
+                    "  Next: {}",
+                    format_time_difference(now, next_date)
+                ));
+            }
+            writeln!(self.output, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_quit(line: &str) -> bool {
+    line.eq_ignore_ascii_case("q") || line.eq_ignore_ascii_case("quit")
+}
+
+fn is_pause(line: &str) -> bool {
+    line.eq_ignore_ascii_case("p") || line.eq_ignore_ascii_case("pause")
+}
+
+/// Run the terminal quiz on standard input and output
+pub fn run_terminal(db: Arc<Database>, is_test_mode: bool) -> io::Result<()> {
+    // In test mode, use 1 question per block; in production, use 10
+    let questions_per_block = if is_test_mode { 1 } else { 10 };
+
+    let stdin = io::stdin();
+    TerminalQuiz::new(db, stdin.lock(), io::stdout(), questions_per_block).run()
+}
+
+#[cfg(test)]
+#[allow(clippy::arc_with_non_send_sync)]
+mod tests {
+    use super::*;
+    use crate::database::{AnswersRepository, DEFAULT_USER_ID, DecksRepository};
+    use crate::deck::DeckStatus;
+    use crate::session::Session;
+
+    fn run_script(db: &Arc<Database>, questions_per_block: usize, script: &str) -> String {
+        let mut output = Vec::new();
+        TerminalQuiz::new(
+            db.clone(),
+            script.as_bytes(),
+            &mut output,
+            questions_per_block,
+        )
+        .run()
+        .expect("Terminal quiz should run");
+        String::from_utf8(output).unwrap()
+    }
+
+    fn deck_status(db: &Database, deck_id: i64) -> DeckStatus {
+        DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time()),
+        )
+        .get(deck_id)
+        .unwrap()
+        .expect("Deck should exist")
+        .status
+    }
+
+    #[test]
+    fn test_completed_deck_is_persisted_and_summarised() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let output = run_script(&db, 2, "0\n0\nq\n");
+
+        assert!(output.contains("Question 1 of 2"));
+        assert!(output.contains("Question 2 of 2"));
+        assert!(output.contains("Deck Results"));
+        assert!(output.contains("Score: "));
+        assert_eq!(deck_status(&db, 1), DeckStatus::Completed);
+
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_slow_answer_is_timed_in_full() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        // Each reading of the clock is 90 seconds after the previous one
+        let start = std::time::Instant::now();
+        let readings = std::cell::Cell::new(0);
+        let clock = Box::new(move || {
+            readings.set(readings.get() + 1);
+            start + std::time::Duration::from_secs(90) * readings.get()
+        });
+        let mut output = Vec::new();
+        TerminalQuiz::with_session(
+            db.clone(),
+            "7\nq\n".as_bytes(),
+            &mut output,
+            Session::with_clock(db.clone(), 1, clock),
+        )
+        .run()
+        .expect("Terminal quiz should run");
+
+        let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .get(1)
+            .unwrap()
+            .expect("Answer should exist");
+        assert_eq!(answer.time_spent_seconds, 90.0);
+        assert!(!answer.timing_interrupted);
+    }
+
+    #[test]
+    fn test_invalid_answer_is_asked_again() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let output = run_script(&db, 1, "seven\n\n7\n");
+
+        assert!(output.contains("Please enter a whole number."));
+        assert_eq!(output.matches("Please enter a whole number.").count(), 1);
+        let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .get(1)
+            .unwrap()
+            .expect("Answer should exist");
+        assert_eq!(answer.user_answer, 7);
+    }
+
+    #[test]
+    fn test_paused_question_is_asked_again() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let output = run_script(&db, 1, "p\n\n7\n");
+
+        assert!(output.contains("Paused; the clock is stopped."));
+        assert_eq!(output.matches("Question 1 of 1").count(), 1);
+        let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .get(1)
+            .unwrap()
+            .expect("Answer should exist");
+        assert_eq!(answer.user_answer, 7);
+        assert!(answer.timing_interrupted);
+        assert!(output.contains("s (paused)"));
+    }
+
+    #[test]
+    fn test_quitting_mid_deck_saves_answers_and_abandons() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let output = run_script(&db, 3, "5\nq\n");
+
+        assert!(output.contains("Deck abandoned"));
+        assert!(!output.contains("Deck Results"));
+        assert_eq!(deck_status(&db, 1), DeckStatus::Abandoned);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_failed_save_is_reported() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        db.conn.execute("DROP TABLE review_log", []).unwrap();
+        let output = run_script(&db, 1, "3\nq\n");
+
+        assert!(output.contains("Your results could not be saved"));
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_unfinished_deck_is_offered_and_resumed() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut crashed = Session::new(db.clone(), 2);
+        crashed.start();
+        crashed.submit_answer(1);
+        let remaining = crashed.questions()[1].clone();
+        std::mem::forget(crashed);
+
+        let output = run_script(&db, 2, "\n5\nq\n");
+        assert!(output.contains("(1 of 2 answered)? [Y/n]"));
+        assert!(output.contains("Question 2 of 2"));
+        assert!(!output.contains("Question 1 of 2"));
+        assert!(output.contains(&remaining.to_string().replace('?', "")));
+        assert_eq!(deck_status(&db, 1), DeckStatus::Completed);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_declined_unfinished_deck_is_abandoned() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        let mut crashed = Session::new(db.clone(), 2);
+        crashed.start();
+        crashed.submit_answer(1);
+        std::mem::forget(crashed);
+
+        run_script(&db, 1, "n\n3\nq\n");
+        assert_eq!(deck_status(&db, 1), DeckStatus::Abandoned);
+        assert_eq!(deck_status(&db, 2), DeckStatus::Completed);
+        assert_eq!(
+            AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+                .count()
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_end_of_input_abandons_deck() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        run_script(&db, 2, "");
+        assert_eq!(deck_status(&db, 1), DeckStatus::Abandoned);
+    }
+
+    #[test]
+    fn test_enter_starts_another_deck() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        run_script(&db, 1, "1\n\n2\nq\n");
+        assert_eq!(deck_status(&db, 1), DeckStatus::Completed);
+        assert_eq!(deck_status(&db, 2), DeckStatus::Completed);
+    }
+}
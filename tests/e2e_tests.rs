@@ -1,8 +1,16 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
 use memory_practice::database::{
-    AnswersRepository, Database, DecksRepository, OperationsRepository,
+    AnswersRepository, DEFAULT_USER_ID, Database, DecksRepository, OperationsRepository,
+    ReviewLogRepository,
 };
 use memory_practice::gui::{AppState, MemoryPracticeApp};
+use memory_practice::session::{Session, SessionState};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[test]
 fn test_app_initialization() {
@@ -30,13 +38,22 @@ fn test_answer_submission_flow() {
     assert_eq!(app.get_results().len(), 1);
 
     assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 1);
-    assert_eq!(AnswersRepository::new(&db.conn).count().unwrap(), 1);
     assert_eq!(
-        DecksRepository::new(&db.conn, Box::new(|| db.get_current_time()))
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
             .count()
             .unwrap(),
         1
     );
+    assert_eq!(
+        DecksRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time())
+        )
+        .count()
+        .unwrap(),
+        1
+    );
 }
 
 #[test]
@@ -55,9 +72,50 @@ fn test_complete_question_block() {
     assert_eq!(*app.get_current_state(), AppState::ShowingResults);
     assert_eq!(app.get_results().len(), 10);
 
-    // Database should have all operations and answers
-    assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 10);
-    assert_eq!(AnswersRepository::new(&db.conn).count().unwrap(), 10);
+    // Database should have one operation per distinct fact and every answer
+    let facts: HashSet<_> = app
+        .get_results()
+        .iter()
+        .map(|result| {
+            (
+                result.operation.operation_type.as_str(),
+                result.operation.operand1,
+                result.operation.operand2,
+            )
+        })
+        .collect();
+    assert_eq!(
+        OperationsRepository::new(&db.conn).count().unwrap(),
+        facts.len() as i64
+    );
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        10
+    );
+}
+
+#[test]
+fn test_completed_block_logs_every_scheduling_decision() {
+    let db = Arc::new(Database::new(":memory:").unwrap());
+    let mut app = MemoryPracticeApp::new(db.clone(), 3);
+    app.start_new_block();
+
+    for i in 0..3 {
+        app.set_answer(i, "999".to_string());
+        app.submit_answer();
+    }
+
+    let log = ReviewLogRepository::new(&db.conn, DEFAULT_USER_ID)
+        .get_all()
+        .unwrap();
+    assert_eq!(log.len(), 3);
+    for record in &log {
+        assert_eq!(record.scheduler, "sm2");
+        assert_eq!(record.prev_repetitions, 0);
+        assert!(record.deck_id.is_some());
+    }
 }
 
 #[test]
@@ -72,7 +130,12 @@ fn test_empty_answer_not_submitted() {
     // Should still be on first question
     assert_eq!(app.get_current_question_index(), 0);
     assert_eq!(app.get_results().len(), 0);
-    assert_eq!(AnswersRepository::new(&db.conn).count().unwrap(), 0);
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        0
+    );
 }
 
 #[test]
@@ -124,7 +187,12 @@ fn test_multiple_answers_in_sequence() {
 
     assert_eq!(app.get_results().len(), 5);
     assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 5);
-    assert_eq!(AnswersRepository::new(&db.conn).count().unwrap(), 5);
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        5
+    );
 }
 
 #[test]
@@ -180,11 +248,18 @@ fn test_database_persistence_across_submissions() {
 
     // Verify database contains all data
     assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 3);
-    assert_eq!(AnswersRepository::new(&db.conn).count().unwrap(), 3);
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        3
+    );
 
     // Verify each answer is stored correctly
     for answer_id in 1..=3 {
-        let answer = AnswersRepository::new(&db.conn).get(answer_id).unwrap();
+        let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .get(answer_id)
+            .unwrap();
         assert!(answer.is_some());
     }
 }
@@ -198,7 +273,90 @@ fn test_timing_data_recorded() {
     app.set_answer(0, "50".to_string());
     app.submit_answer();
 
-    let answer = AnswersRepository::new(&db.conn).get(1).unwrap().unwrap();
+    let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+        .get(1)
+        .unwrap()
+        .unwrap();
     // Time should be a positive number (even if very small)
     assert!(answer.time_spent_seconds >= 0.0);
 }
+
+#[test]
+fn test_session_records_exact_answer_time() {
+    let db = Arc::new(Database::new(":memory:").unwrap());
+    let start = Instant::now();
+    let elapsed = Rc::new(Cell::new(Duration::ZERO));
+    let clock_elapsed = elapsed.clone();
+    let mut session =
+        Session::with_clock(db.clone(), 1, Box::new(move || start + clock_elapsed.get()));
+    session.start();
+
+    elapsed.set(Duration::from_millis(3250));
+    let expected = session.current_question().unwrap().result;
+    session.submit_answer(expected);
+    assert_eq!(session.next_question(), SessionState::Completed);
+
+    let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+        .get(1)
+        .unwrap()
+        .unwrap();
+    assert!(answer.is_correct);
+    assert_eq!(answer.time_spent_seconds, 3.25);
+}
+
+#[test]
+fn test_app_offers_to_resume_deck_after_crash() {
+    let db = Arc::new(Database::new(":memory:").unwrap());
+    let (deck_id, questions) = {
+        let mut app = MemoryPracticeApp::new(db.clone(), 3);
+        assert!(app.get_resumable_deck().is_none());
+        app.start_new_block();
+        app.set_answer(0, "7".to_string());
+        app.submit_answer();
+        let deck_id = app.get_current_deck_id().unwrap();
+        let questions: Vec<String> = app.get_questions().iter().map(|q| q.to_string()).collect();
+        // Killed: the app is never dropped
+        std::mem::forget(app);
+        (deck_id, questions)
+    };
+
+    let mut app = MemoryPracticeApp::new(db.clone(), 3);
+    let deck = app
+        .get_resumable_deck()
+        .expect("Unfinished deck should be offered");
+    assert_eq!(deck.deck_id, deck_id);
+    assert_eq!((deck.answered(), deck.total()), (1, 3));
+    let journaled: Vec<String> = deck
+        .entries
+        .iter()
+        .map(|e| e.operation.to_string())
+        .collect();
+    assert_eq!(journaled, questions);
+
+    app.resume_deck();
+    assert_eq!(*app.get_current_state(), AppState::ShowingQuestions);
+    assert_eq!(app.get_current_deck_id(), Some(deck_id));
+    assert_eq!(app.get_current_question_index(), 1);
+    for i in 1..3 {
+        app.set_answer(i, "999".to_string());
+        app.submit_answer();
+    }
+
+    assert_eq!(*app.get_current_state(), AppState::ShowingResults);
+    assert_eq!(app.get_results().len(), 3);
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        3
+    );
+    let deck = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    )
+    .get(deck_id)
+    .unwrap()
+    .unwrap();
+    assert_eq!(deck.total_questions, 3);
+}
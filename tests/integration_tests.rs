@@ -1,6 +1,19 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
 use OperationType::{Addition, Multiplication};
-use memory_practice::database::{AnswersRepository, Database, OperationsRepository};
+use chrono::{Datelike, TimeZone, Utc};
+use memory_practice::database::analytics::{ErrorsRepository, StreakRepository};
+use memory_practice::database::{
+    AnswersRepository, DEFAULT_USER_ID, Database, DecksRepository, OperationsRepository,
+    ReviewItemsRepository, ReviewLogRepository,
+};
+use memory_practice::database_factory::{DatabaseConfig, DatabaseFactory};
+use memory_practice::difficulty::DifficultyProfile;
+use memory_practice::error_analysis::ErrorKind;
 use memory_practice::operations::{Operation, OperationType, generate_question_block};
+use memory_practice::quiz_service::{PersistError, QuizService};
+use memory_practice::spaced_repetition::PartialCreditPolicy;
+use std::sync::Arc;
 
 #[test]
 fn test_store_and_retrieve_operation() {
@@ -48,7 +61,7 @@ fn test_full_question_answer_workflow() {
     let is_correct = operation.check_answer(user_answer);
     let time_spent = 2.3;
 
-    let answers_repo = AnswersRepository::new(&db.conn);
+    let answers_repo = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
     answers_repo
         .insert(op_id, user_answer, is_correct, time_spent, None)
         .unwrap();
@@ -64,7 +77,7 @@ fn test_full_question_answer_workflow() {
 #[test]
 fn test_question_block_storage() {
     let db = Database::new(":memory:").unwrap();
-    let block = generate_question_block(10);
+    let block = generate_question_block(10, &DifficultyProfile::default());
 
     // Store all operations from the block
     let operations_repo = OperationsRepository::new(&db.conn);
@@ -104,7 +117,7 @@ fn test_correct_and_incorrect_answers() {
     let is_correct = operation.check_answer(wrong_answer);
     assert!(!is_correct);
 
-    let answers_repo = AnswersRepository::new(&db.conn);
+    let answers_repo = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
     answers_repo
         .insert(op_id, wrong_answer, is_correct, 3.0, None)
         .unwrap();
@@ -140,7 +153,7 @@ fn test_multiple_operations_with_answers() {
     let times = [1.2, 2.5, 1.8];
 
     let operations_repo = OperationsRepository::new(&db.conn);
-    let answers_repo = AnswersRepository::new(&db.conn);
+    let answers_repo = AnswersRepository::new(&db.conn, DEFAULT_USER_ID);
     for (i, operation) in operations.iter().enumerate() {
         let op_id = operations_repo
             .insert(
@@ -204,3 +217,277 @@ fn test_operation_types_in_database() {
     assert_eq!(stored_add.operation_type, "ADD");
     assert_eq!(stored_mul.operation_type, "MULTIPLY");
 }
+
+#[test]
+fn test_due_reviews_follow_overridden_date() {
+    let config = DatabaseConfig::builder()
+        .test_mode()
+        .date_ymd(2025, 1, 1)
+        .build();
+    let db = Arc::new(DatabaseFactory::create(config).unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+
+    // Due four days after the overridden date, but long before the real clock
+    let op_id = OperationsRepository::new(&db.conn)
+        .insert("ADD", 2, 3, 5, None)
+        .unwrap();
+    let due_date = Utc.with_ymd_and_hms(2025, 1, 5, 0, 0, 0).unwrap();
+    ReviewItemsRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    )
+    .insert(op_id, due_date)
+    .unwrap();
+
+    assert!(service.fetch_due_reviews().is_empty());
+}
+
+#[test]
+fn test_persisted_results_use_overridden_date() {
+    let config = DatabaseConfig::builder()
+        .test_mode()
+        .date_ymd(2025, 1, 1)
+        .build();
+    let db = Arc::new(DatabaseFactory::create(config).unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+
+    let deck_id = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    )
+    .create()
+    .unwrap();
+    let question = Operation::new(Addition, 2, 3);
+    let result = service.process_answer(&question, 5, 1.0);
+    let persisted = service.persist_results(&[result], deck_id).unwrap();
+
+    let next_review = persisted[0].next_review_date.unwrap();
+    assert_eq!(next_review.year(), 2025);
+    assert!(next_review > db.get_current_time());
+
+    let log = ReviewLogRepository::new(&db.conn, DEFAULT_USER_ID)
+        .get_all()
+        .unwrap();
+    assert_eq!(
+        log[0].reviewed_at.date_naive(),
+        db.get_current_time().date_naive()
+    );
+
+    assert_eq!(
+        StreakRepository::new(&db.conn, DEFAULT_USER_ID)
+            .calculate_consecutive_days(db.get_current_time())
+            .unwrap(),
+        1
+    );
+}
+
+#[test]
+fn test_wrong_answers_are_labelled_and_aggregated() {
+    let db = Arc::new(Database::new(":memory:").unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+    let decks = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    );
+
+    let deck_id = decks.create().unwrap();
+    let results = vec![
+        service.process_answer(&Operation::new(Multiplication, 7, 8), 49, 1.0),
+        service.process_answer(&Operation::new(Multiplication, 6, 9), 45, 1.0),
+        service.process_answer(&Operation::new(Addition, 27, 15), 42, 1.0),
+    ];
+    assert_eq!(results[0].error_kind, Some(ErrorKind::TableNeighbour));
+    assert_eq!(results[2].error_kind, None);
+    service.persist_results(&results, deck_id).unwrap();
+    decks.complete(deck_id).unwrap();
+
+    let answer = AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+        .get(1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(answer.error_kind, Some(ErrorKind::TableNeighbour));
+    let breakdown = ErrorsRepository::new(&db.conn, DEFAULT_USER_ID)
+        .all_operations()
+        .unwrap();
+    assert_eq!(
+        breakdown.for_operation("MULTIPLY"),
+        &[(ErrorKind::TableNeighbour, 2)]
+    );
+}
+
+#[test]
+fn test_repeated_fact_shares_one_review_item() {
+    let db = Arc::new(Database::new(":memory:").unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+    let decks = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    );
+
+    for _ in 0..3 {
+        let deck_id = decks.create().unwrap();
+        let result = service.process_answer(&Operation::new(Multiplication, 7, 8), 56, 1.0);
+        let persisted = service.persist_results(&[result], deck_id).unwrap();
+        assert!(persisted[0].next_review_date.is_some());
+    }
+
+    let op = OperationsRepository::new(&db.conn)
+        .find("MULTIPLY", 7, 8, false)
+        .unwrap()
+        .unwrap();
+    assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 1);
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        3
+    );
+    let review_item = ReviewItemsRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    )
+    .get(op.id)
+    .unwrap()
+    .unwrap();
+    assert_eq!(review_item.repetitions, 3);
+    assert_eq!(
+        ReviewLogRepository::new(&db.conn, DEFAULT_USER_ID)
+            .get_for_operation(op.id)
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn test_commutative_facts_setting_merges_swapped_operands() {
+    let config = DatabaseConfig::builder()
+        .test_mode()
+        .commutative_facts(true)
+        .build();
+    let db = Arc::new(DatabaseFactory::create(config).unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+    let deck_id = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    )
+    .create()
+    .unwrap();
+
+    let results = [
+        service.process_answer(&Operation::new(Multiplication, 7, 8), 56, 1.0),
+        service.process_answer(&Operation::new(Multiplication, 8, 7), 56, 1.0),
+        service.process_answer(&Operation::new(OperationType::Subtraction, 8, 7), 1, 1.0),
+        service.process_answer(&Operation::new(OperationType::Subtraction, 7, 8), -1, 1.0),
+    ];
+    let persisted = service.persist_results(&results, deck_id).unwrap();
+
+    assert!(!persisted[0].is_review);
+    assert!(persisted[1].is_review);
+    assert!(!persisted[3].is_review);
+    assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 3);
+}
+
+/// Grade and new interval logged when a well-known 7 × 8 is answered with `answer`
+fn review_after_mistake(policy: PartialCreditPolicy, answer: i32) -> (i32, i32) {
+    let config = DatabaseConfig::builder()
+        .test_mode()
+        .partial_credit(policy)
+        .build();
+    let db = Arc::new(DatabaseFactory::create(config).unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+    let decks = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    );
+
+    for user_answer in [56, 56, 56, answer] {
+        let deck_id = decks.create().unwrap();
+        let result =
+            service.process_answer(&Operation::new(Multiplication, 7, 8), user_answer, 1.0);
+        service.persist_results(&[result], deck_id).unwrap();
+    }
+
+    let log = ReviewLogRepository::new(&db.conn, DEFAULT_USER_ID)
+        .get_for_operation(1)
+        .unwrap();
+    let last = log.last().unwrap();
+    (last.quality, last.new_interval)
+}
+
+#[test]
+fn test_near_miss_is_rescheduled_more_gently_than_a_blackout() {
+    let (near_miss_grade, near_miss_interval) =
+        review_after_mistake(PartialCreditPolicy::NearMiss, 55);
+    let (blackout_grade, blackout_interval) =
+        review_after_mistake(PartialCreditPolicy::NearMiss, 12);
+    assert_eq!(near_miss_grade, 2);
+    assert_eq!(blackout_grade, 0);
+    assert_eq!(blackout_interval, 1);
+    assert!(near_miss_interval > blackout_interval);
+
+    assert_eq!(
+        review_after_mistake(PartialCreditPolicy::Off, 55),
+        (blackout_grade, blackout_interval)
+    );
+}
+
+#[test]
+fn test_failed_persistence_rolls_back_the_whole_deck() {
+    let db = Arc::new(Database::new(":memory:").unwrap());
+    let service = QuizService::new(&db.conn, db.clone());
+    let deck_id = DecksRepository::new(
+        &db.conn,
+        DEFAULT_USER_ID,
+        Box::new(|| db.get_current_time()),
+    )
+    .create()
+    .unwrap();
+
+    let results = [
+        service.process_answer(&Operation::new(Addition, 2, 3), 5, 1.0),
+        service.process_answer(&Operation::new(Multiplication, 4, 6), 24, 1.0),
+    ];
+    // A review result that lost its operation id fails after the first result was written
+    let mut broken = service.process_answer(&Operation::new(Addition, 1, 1), 2, 1.0);
+    broken.is_review = true;
+    let error = service
+        .persist_results(&[results[0].clone(), broken], deck_id)
+        .unwrap_err();
+    assert!(matches!(error, PersistError::MissingOperation(_)));
+    assert_eq!(OperationsRepository::new(&db.conn).count().unwrap(), 0);
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        0
+    );
+
+    db.conn.execute("DROP TABLE review_log", []).unwrap();
+    let error = service.save_deck(deck_id, &results).unwrap_err();
+    assert!(matches!(error, PersistError::Database(_)));
+    assert_eq!(
+        AnswersRepository::new(&db.conn, DEFAULT_USER_ID)
+            .count()
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        ReviewItemsRepository::new(
+            &db.conn,
+            DEFAULT_USER_ID,
+            Box::new(|| db.get_current_time())
+        )
+        .get_all()
+        .unwrap()
+        .len(),
+        0
+    );
+}